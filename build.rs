@@ -0,0 +1,35 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    job_queue();
+}
+
+/// Generate the gRPC service for the job server. The messages it uses are
+/// written by hand in `src/grpc.rs`, so this doesn't need `protoc`.
+#[cfg(feature = "grpc")]
+fn job_queue() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::grpc::{input}"))
+            .output_type(format!("crate::grpc::{output}"))
+            .codec_path("tonic_prost::ProstCodec")
+    };
+    let service = Service::builder()
+        .name("JobQueue")
+        .package("subtitle_translate")
+        .method(method("submit_job", "SubmitJob", "SubmitJobRequest", "Job").build())
+        .method(method("get_status", "GetStatus", "JobId", "Job").build())
+        .method(
+            method("stream_progress", "StreamProgress", "JobId", "Job")
+                .server_streaming()
+                .build(),
+        )
+        .method(method("get_result", "GetResult", "JobId", "JobResult").build())
+        .method(method("cancel_job", "CancelJob", "JobId", "Job").build())
+        .build();
+    println!("cargo::rerun-if-changed=build.rs");
+    Builder::new().build_client(false).compile(&[service]);
+}
@@ -0,0 +1,76 @@
+/*
+ * C bindings for subtitle-translate.
+ *
+ * Build with `cargo build --release -p subtitle-translate-ffi`, then link
+ * against the resulting `subtitle_translate_ffi` shared library.
+ *
+ * All strings are UTF-8 and NUL terminated. Functions may be called from any
+ * thread, and block until they have finished.
+ */
+
+#ifndef SUBTITLE_TRANSLATE_H
+#define SUBTITLE_TRANSLATE_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Results, matching the command line tool's exit codes. */
+#define SUBTITLE_TRANSLATE_OK 0
+#define SUBTITLE_TRANSLATE_ERROR 1
+#define SUBTITLE_TRANSLATE_INVALID_ARGUMENT 2
+#define SUBTITLE_TRANSLATE_PARSE_FAILED 3
+#define SUBTITLE_TRANSLATE_UNREACHABLE 4
+#define SUBTITLE_TRANSLATE_TRANSLATION_FAILED 5
+#define SUBTITLE_TRANSLATE_WRITE_FAILED 6
+
+/*
+ * Called after each cue has been attempted, with how many of the `total`
+ * cues needing translation have been attempted so far, and the `user_data`
+ * given.
+ */
+typedef void (*subtitle_translate_progress)(size_t done, size_t total, void *user_data);
+
+/*
+ * Translate the subtitle file at `source_path` from `source_language` (which
+ * may be "auto") into `target_language`, writing it to `destination_path` in
+ * the format matching its extension.
+ *
+ * `instance` is the URL of a LibreTranslate instance's translation API.
+ * `api_key` and `progress` may be NULL.
+ *
+ * Returns SUBTITLE_TRANSLATE_OK on success, or one of the other results on
+ * failure, in which case subtitle_translate_last_error() describes why.
+ */
+int subtitle_translate_file(const char *instance, const char *api_key,
+                            const char *source_path, const char *destination_path,
+                            const char *source_language, const char *target_language,
+                            subtitle_translate_progress progress, void *user_data);
+
+/*
+ * Translate a single string from `source_language` (which may be "auto") into
+ * `target_language`.
+ *
+ * Returns the translation, which must be freed with
+ * subtitle_translate_string_free(), or NULL on failure, in which case
+ * subtitle_translate_last_error() describes why.
+ */
+char *subtitle_translate_string(const char *instance, const char *api_key, const char *text,
+                                const char *source_language, const char *target_language);
+
+/* Free a string returned by subtitle_translate_string(). NULL is ignored. */
+void subtitle_translate_string_free(char *string);
+
+/*
+ * Describe the last failure on the calling thread, or return NULL if nothing
+ * has failed. The string remains valid until the next call on the thread.
+ */
+const char *subtitle_translate_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SUBTITLE_TRANSLATE_H */
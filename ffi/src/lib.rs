@@ -0,0 +1,256 @@
+//! C bindings for subtitle-translate. The API is described in
+//! `include/subtitle_translate.h`.
+
+#![deny(clippy::pedantic)]
+
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{CStr, CString, c_char, c_int, c_void},
+    path::Path,
+    ptr,
+};
+
+use subtitle_translate::{
+    LibreTranslate, TranslationPipeline, pipeline::Event, subtitle, summary::Summary,
+};
+
+const OK: c_int = 0;
+const ERROR: c_int = 1;
+const INVALID_ARGUMENT: c_int = 2;
+const PARSE_FAILED: c_int = 3;
+const UNREACHABLE: c_int = 4;
+const TRANSLATION_FAILED: c_int = 5;
+const WRITE_FAILED: c_int = 6;
+
+/// Called after each cue has been attempted.
+pub type Progress = extern "C" fn(done: usize, total: usize, user_data: *mut c_void);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A failure, with the result code to report it as.
+struct Failure {
+    code: c_int,
+    error: anyhow::Error,
+}
+
+fn fail(code: c_int) -> impl FnOnce(anyhow::Error) -> Failure {
+    move |error| Failure { code, error }
+}
+
+/// Record a failure for `subtitle_translate_last_error`, returning its code.
+fn record(failure: &Failure) -> c_int {
+    let message = format!("{:#}", failure.error).replace('\0', " ");
+    LAST_ERROR.set(CString::new(message).ok());
+    failure.code
+}
+
+/// Read a required string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL terminated string.
+unsafe fn string<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    // SAFETY: upheld by the caller.
+    unsafe { optional_string(ptr, name) }?.ok_or_else(|| Failure {
+        code: INVALID_ARGUMENT,
+        error: anyhow::anyhow!("{name} must not be null"),
+    })
+}
+
+/// Read an optional string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL terminated string.
+unsafe fn optional_string<'a>(ptr: *const c_char, name: &str) -> Result<Option<&'a str>, Failure> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: upheld by the caller.
+    let string = unsafe { CStr::from_ptr(ptr) };
+    string.to_str().map(Some).map_err(|_| Failure {
+        code: INVALID_ARGUMENT,
+        error: anyhow::anyhow!("{name} isn't valid UTF-8"),
+    })
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime, Failure> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| fail(ERROR)(e.into()))
+}
+
+/// The result code for a failed translation.
+fn translation_failure(error: anyhow::Error) -> Failure {
+    let unreachable = error
+        .chain()
+        .filter_map(|e| e.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_connect() || e.is_timeout());
+    Failure {
+        code: if unreachable {
+            UNREACHABLE
+        } else {
+            TRANSLATION_FAILED
+        },
+        error,
+    }
+}
+
+/// Translate a subtitle file. See `include/subtitle_translate.h`.
+///
+/// # Safety
+///
+/// Every string must be null or point to a NUL terminated string, and
+/// `progress`, if given, must be safe to call with `user_data`.
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn subtitle_translate_file(
+    instance: *const c_char,
+    api_key: *const c_char,
+    source_path: *const c_char,
+    destination_path: *const c_char,
+    source_language: *const c_char,
+    target_language: *const c_char,
+    progress: Option<Progress>,
+    user_data: *mut c_void,
+) -> c_int {
+    // SAFETY: upheld by the caller.
+    let result = unsafe {
+        translate_file(
+            instance,
+            api_key,
+            source_path,
+            destination_path,
+            source_language,
+            target_language,
+            progress,
+            user_data,
+        )
+    };
+    match result {
+        Ok(()) => OK,
+        Err(failure) => record(&failure),
+    }
+}
+
+/// # Safety
+///
+/// See [`subtitle_translate_file`].
+#[allow(clippy::too_many_arguments)]
+unsafe fn translate_file(
+    instance: *const c_char,
+    api_key: *const c_char,
+    source_path: *const c_char,
+    destination_path: *const c_char,
+    source_language: *const c_char,
+    target_language: *const c_char,
+    progress: Option<Progress>,
+    user_data: *mut c_void,
+) -> Result<(), Failure> {
+    // SAFETY: upheld by the caller.
+    let (instance, api_key, source_path, destination_path, source_language, target_language) = unsafe {
+        (
+            string(instance, "instance")?,
+            optional_string(api_key, "api_key")?,
+            string(source_path, "source_path")?,
+            string(destination_path, "destination_path")?,
+            string(source_language, "source_language")?,
+            string(target_language, "target_language")?,
+        )
+    };
+
+    let mut subtitles = subtitle::read(Path::new(source_path)).map_err(fail(PARSE_FAILED))?;
+    let total = subtitles.iter().filter(|s| !s.text.is_empty()).count();
+    let translator = LibreTranslate::new(instance.to_string(), api_key.map(ToString::to_string));
+    let pipeline = TranslationPipeline::new(translator, target_language).source(source_language);
+
+    let done = Cell::new(0);
+    let on_event = |_: &Event<'_>| {
+        done.set(done.get() + 1);
+        if let Some(progress) = progress {
+            progress(done.get(), total, user_data);
+        }
+    };
+    runtime()?
+        .block_on(pipeline.translate(&mut subtitles, &mut Summary::new(), on_event))
+        .map_err(translation_failure)?;
+
+    subtitle::write(&subtitles, Path::new(destination_path)).map_err(fail(WRITE_FAILED))
+}
+
+/// Translate a single string. See `include/subtitle_translate.h`.
+///
+/// # Safety
+///
+/// Every string must be null or point to a NUL terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn subtitle_translate_string(
+    instance: *const c_char,
+    api_key: *const c_char,
+    text: *const c_char,
+    source_language: *const c_char,
+    target_language: *const c_char,
+) -> *mut c_char {
+    // SAFETY: upheld by the caller.
+    let result =
+        unsafe { translate_string(instance, api_key, text, source_language, target_language) };
+    match result {
+        Ok(translation) => translation.into_raw(),
+        Err(failure) => {
+            record(&failure);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// See [`subtitle_translate_string`].
+unsafe fn translate_string(
+    instance: *const c_char,
+    api_key: *const c_char,
+    text: *const c_char,
+    source_language: *const c_char,
+    target_language: *const c_char,
+) -> Result<CString, Failure> {
+    // SAFETY: upheld by the caller.
+    let (instance, api_key, text, source_language, target_language) = unsafe {
+        (
+            string(instance, "instance")?,
+            optional_string(api_key, "api_key")?,
+            string(text, "text")?,
+            string(source_language, "source_language")?,
+            string(target_language, "target_language")?,
+        )
+    };
+
+    let translator = LibreTranslate::new(instance.to_string(), api_key.map(ToString::to_string));
+    let translation = runtime()?
+        .block_on(translator.translate(text.to_string(), source_language, target_language, 0))
+        .map_err(translation_failure)?;
+    CString::new(translation.translated_text).map_err(|e| fail(ERROR)(e.into()))
+}
+
+/// Free a string returned by [`subtitle_translate_string`].
+///
+/// # Safety
+///
+/// `string` must be null or have been returned by
+/// [`subtitle_translate_string`], and not already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn subtitle_translate_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: upheld by the caller.
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/// Describe the last failure on the calling thread, or return null if
+/// nothing has failed.
+#[unsafe(no_mangle)]
+pub extern "C" fn subtitle_translate_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|error| error.as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
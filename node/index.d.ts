@@ -0,0 +1,63 @@
+/*
+ * Node.js bindings for subtitle-translate.
+ *
+ * Build with `npm run build`, which needs a Rust toolchain, then
+ * `require("subtitle-translate")`. Every function returns a promise, and
+ * translates without blocking the event loop.
+ */
+
+/** The LibreTranslate instance to translate with. */
+export interface Engine {
+  /** The URL of the instance's `/translate` endpoint. */
+  instance: string
+  apiKey?: string
+}
+
+export interface TranslateFileOptions {
+  engine: Engine
+  /** The subtitle file to translate, of any supported format. */
+  source: string
+  /** Where to write the translation, in the format its extension names. */
+  destination: string
+  /** The source language, detected if not given. */
+  from?: string
+  to: string
+  /** How many cues are translated at once. */
+  chunkSize?: number
+}
+
+export interface TranslateStringOptions {
+  engine: Engine
+  /** The source language, detected if not given. */
+  from?: string
+  to: string
+}
+
+/** Given to the progress callback as each cue is translated or fails to be. */
+export interface Progress {
+  /** The cues translated or failed so far. */
+  done: number
+  /** The cues with text to translate. */
+  total: number
+  /** The cue which was just attempted, numbered from 1. */
+  cue: number
+  translation?: string
+  error?: string
+}
+
+/** How the cues of a file were translated. */
+export interface Summary {
+  translated: number
+  skipped: number
+  failed: number
+  characters: number
+}
+
+/**
+ * Translate a subtitle file, resolving to how its cues were translated. It
+ * rejects with the first line's error if any fail to translate.
+ */
+export function translateFile(options: TranslateFileOptions, onProgress?: (progress: Progress) => void): Promise<Summary>
+
+/** Translate a single string. */
+export function translateString(text: string, options: TranslateStringOptions): Promise<string>
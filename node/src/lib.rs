@@ -0,0 +1,158 @@
+//! Node.js bindings for subtitle-translate. The API is described in
+//! `index.d.ts`.
+
+#![deny(clippy::pedantic)]
+
+use napi::{
+    Error, Result,
+    threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode},
+};
+use napi_derive::napi;
+use subtitle_translate::{LibreTranslate, TranslationJob, pipeline::Event, summary};
+
+/// The LibreTranslate instance to translate with.
+#[napi(object)]
+pub struct Engine {
+    /// The URL of the instance's `/translate` endpoint.
+    pub instance: String,
+    pub api_key: Option<String>,
+}
+
+#[napi(object)]
+pub struct TranslateFileOptions {
+    pub engine: Engine,
+    /// The subtitle file to translate, of any supported format.
+    pub source: String,
+    /// Where to write the translation, in the format its extension names.
+    pub destination: String,
+    /// The source language, detected if not given.
+    pub from: Option<String>,
+    pub to: String,
+    /// How many cues are translated at once.
+    pub chunk_size: Option<u32>,
+}
+
+#[napi(object)]
+pub struct TranslateStringOptions {
+    pub engine: Engine,
+    /// The source language, detected if not given.
+    pub from: Option<String>,
+    pub to: String,
+}
+
+/// Given to the progress callback as each cue is translated or fails to be.
+#[napi(object)]
+pub struct Progress {
+    /// The cues translated or failed so far.
+    pub done: u32,
+    /// The cues with text to translate.
+    pub total: u32,
+    /// The cue which was just attempted, numbered from 1.
+    pub cue: u32,
+    pub translation: Option<String>,
+    pub error: Option<String>,
+}
+
+/// How the cues of a file were translated.
+#[napi(object)]
+pub struct Summary {
+    pub translated: u32,
+    pub skipped: u32,
+    pub failed: u32,
+    pub characters: u32,
+}
+
+impl From<&summary::Summary> for Summary {
+    fn from(summary: &summary::Summary) -> Self {
+        Self {
+            translated: count(summary.translated),
+            skipped: count(summary.skipped),
+            failed: count(summary.failed),
+            characters: count(summary.characters),
+        }
+    }
+}
+
+/// Translate a subtitle file, resolving to how its cues were translated.
+///
+/// # Errors
+///
+/// Rejects if the file can't be read or written, or with the first line's
+/// error if any fail to translate.
+#[napi(ts_args_type = "options: TranslateFileOptions, onProgress?: (progress: Progress) => void")]
+pub async fn translate_file(
+    options: TranslateFileOptions,
+    on_progress: Option<ThreadsafeFunction<Progress, ErrorStrategy::Fatal>>,
+) -> Result<Summary> {
+    let mut builder = TranslationJob::builder()
+        .source(&options.source)
+        .destination(&options.destination)
+        .source_language(options.from.unwrap_or_else(|| "auto".to_string()))
+        .target(options.to)
+        .engine(translator(options.engine));
+    if let Some(chunk_size) = options.chunk_size {
+        builder = builder.chunk_size(chunk_size as usize);
+    }
+    if let Some(on_progress) = on_progress {
+        builder = builder.on_progress(move |progress| {
+            let (cue, translation, error) = match progress.event {
+                Event::LineDone {
+                    cue, translation, ..
+                } => (*cue, Some(translation.to_string()), None),
+                Event::LineFailed { cue, error } => (*cue, None, Some(error.clone())),
+                _ => return,
+            };
+            on_progress.call(
+                Progress {
+                    done: count(progress.done),
+                    total: count(progress.total),
+                    cue: count(cue),
+                    translation,
+                    error,
+                },
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+    }
+    let finished = builder
+        .build()
+        .map_err(reason)?
+        .run()
+        .await
+        .map_err(reason)?;
+    Ok(Summary::from(&finished.summary))
+}
+
+/// Translate a single string.
+///
+/// # Errors
+///
+/// Rejects if the string fails to translate.
+#[napi]
+pub async fn translate_string(text: String, options: TranslateStringOptions) -> Result<String> {
+    let translation = translator(options.engine)
+        .translate(
+            text,
+            options.from.as_deref().unwrap_or("auto"),
+            &options.to,
+            0,
+        )
+        .await
+        .map_err(reason)?;
+    Ok(translation.translated_text)
+}
+
+fn translator(engine: Engine) -> LibreTranslate {
+    LibreTranslate::new(engine.instance, engine.api_key)
+}
+
+/// A JavaScript error for a failure, with its causes.
+#[allow(clippy::needless_pass_by_value)]
+fn reason(error: anyhow::Error) -> Error {
+    Error::from_reason(format!("{error:#}"))
+}
+
+/// A count as JavaScript is given it.
+fn count(count: usize) -> u32 {
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
@@ -0,0 +1,58 @@
+//! Pairing up the cues of two subtitle files for the same video by when they
+//! are shown, as their cues are rarely numbered alike.
+
+use crate::subtitle::GenericSubtitle;
+
+/// Cues from each of two files shown at about the same time, or a cue from
+/// one with nothing matching it in the other. Cues are given by index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pair {
+    Both(usize, usize),
+    Left(usize),
+    Right(usize),
+}
+
+/// Pair up the cues of two files, each in the order they start. Cues are
+/// paired if they overlap for at least half of the shorter one, and are
+/// otherwise left on their own.
+///
+/// ```
+/// # use subtitle_translate::{GenericSubtitle, align::{Pair, align}};
+/// let cue = |start: i64, end: i64| GenericSubtitle {
+///     text: String::new(),
+///     start: start.into(),
+///     end: end.into(),
+///     coordinates: None,
+///     style: None,
+/// };
+/// let left = [cue(0, 1000), cue(2000, 3000)];
+/// let right = [cue(100, 1100), cue(1200, 1800), cue(2000, 2900)];
+/// assert_eq!(
+///     align(&left, &right),
+///     [Pair::Both(0, 0), Pair::Right(1), Pair::Both(1, 2)]
+/// );
+/// ```
+#[must_use]
+pub fn align(left: &[GenericSubtitle], right: &[GenericSubtitle]) -> Vec<Pair> {
+    let mut pairs = Vec::with_capacity(left.len().max(right.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        let (a, b) = (&left[i], &right[j]);
+        let overlap = a.end.as_ms().min(b.end.as_ms()) - a.start.as_ms().max(b.start.as_ms());
+        let shorter = a.duration_ms().min(b.duration_ms()).max(1);
+        if overlap * 2 >= shorter {
+            pairs.push(Pair::Both(i, j));
+            i += 1;
+            j += 1;
+        } else if a.end.as_ms() <= b.end.as_ms() {
+            pairs.push(Pair::Left(i));
+            i += 1;
+        } else {
+            pairs.push(Pair::Right(j));
+            j += 1;
+        }
+    }
+    pairs.extend((i..left.len()).map(Pair::Left));
+    pairs.extend((j..right.len()).map(Pair::Right));
+    pairs
+}
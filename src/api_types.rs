@@ -24,20 +24,37 @@ pub enum TranslationResult {
     Err(TranslationError),
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Translation {
     pub translated_text: String,
     pub alternatives: Option<Vec<String>>,
     pub detected_language: Option<DetectedLanguage>,
+    /// Whether the translation came from a cache, rather than the engine.
+    #[serde(skip)]
+    pub cached: bool,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DetectedLanguage {
-    pub confidence: u8,
+    pub confidence: f32,
     pub language: String,
 }
 
+#[derive(Serialize, Debug, Default)]
+pub struct DetectQuery {
+    pub q: String,
+    pub api_key: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Language {
+    pub code: String,
+    pub name: String,
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct TranslationError {
     pub error: String,
@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use subtitle_translate::languages;
+
+use crate::{
+    checkpoint,
+    cli::{BatchArgs, EngineArgs, TranslateArgs},
+    config::Config,
+    exit_code::{Failure, WithFailure},
+    translate,
+};
+
+/// A manifest of translation jobs.
+///
+/// Settings at the top level apply to every job, unless the job overrides
+/// them. Relative paths are resolved from the manifest's directory.
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(flatten)]
+    defaults: Settings,
+    #[serde(rename = "job")]
+    jobs: Vec<Job>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Job {
+    source: PathBuf,
+    /// Defaults to the source's name with the language added.
+    destination: Option<PathBuf>,
+    language: String,
+    report: Option<PathBuf>,
+    #[serde(flatten)]
+    settings: Settings,
+}
+
+/// What each destination was last translated from, so jobs whose sources and
+/// settings haven't changed since aren't run again. It is kept beside the
+/// manifest, as `<manifest>.state.json`.
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    /// The fingerprint of each job's source and settings, by destination.
+    translated: BTreeMap<PathBuf, String>,
+}
+
+/// The settings which can be given for all jobs or for a single job.
+#[derive(Clone, Default, Deserialize)]
+struct Settings {
+    language_from: Option<String>,
+    chunk_size: Option<usize>,
+    overwrite: Option<bool>,
+    backup: Option<bool>,
+}
+
+impl Settings {
+    /// Use these settings, falling back to the defaults for any not given.
+    fn or(&self, defaults: &Self) -> Self {
+        Self {
+            language_from: self
+                .language_from
+                .clone()
+                .or_else(|| defaults.language_from.clone()),
+            chunk_size: self.chunk_size.or(defaults.chunk_size),
+            overwrite: self.overwrite.or(defaults.overwrite),
+            backup: self.backup.or(defaults.backup),
+        }
+    }
+}
+
+/// Run every job in a manifest, continuing past failed jobs.
+pub async fn run(engine: &EngineArgs, args: &BatchArgs) -> anyhow::Result<()> {
+    let manifest = std::fs::read_to_string(&args.manifest)
+        .context("Failed to read manifest")
+        .failure(Failure::Parse)?;
+    let manifest: Manifest = toml::from_str(&manifest)
+        .context("Failed to parse manifest")
+        .failure(Failure::Parse)?;
+    let base = args.manifest.parent().unwrap_or(Path::new(""));
+    let state_path = state_path(&args.manifest);
+    let mut state = read_state(&state_path)?;
+
+    let total = manifest.jobs.len();
+    let mut failed = 0;
+    let mut skipped = 0;
+    let mut first_error = None;
+    for (idx, job) in manifest.jobs.into_iter().enumerate() {
+        let source = job.source.display().to_string();
+        let destination = job.destination.clone().unwrap_or_else(|| {
+            let language =
+                languages::resolve_target(&job.language).unwrap_or_else(|_| job.language.clone());
+            translate::default_destination(&job.source, &language)
+        });
+        eprint!(
+            "[{}/{total}] {source} -> {} ... ",
+            idx + 1,
+            destination.display()
+        );
+        let fingerprint = translate_args(job.clone(), &manifest.defaults, base)
+            .and_then(|args| fingerprint(engine, args))
+            .ok();
+        let job_args = translate_args(job, &manifest.defaults, base);
+        if !args.force
+            && fingerprint.is_some()
+            && state.translated.get(&destination) == fingerprint.as_ref()
+            && base.join(&destination).exists()
+        {
+            eprintln!("unchanged, skipped");
+            skipped += 1;
+            continue;
+        }
+        let result = match job_args {
+            Ok(args) => translate::run(engine, args, true).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => {
+                eprintln!("done");
+                if let Some(fingerprint) = fingerprint {
+                    state.translated.insert(destination, fingerprint);
+                    write_state(&state_path, &state);
+                }
+            }
+            Err(e) => {
+                eprintln!("failed: {e:#}");
+                failed += 1;
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    if skipped > 0 {
+        eprintln!("{skipped} job(s) were skipped, as their sources and settings hadn't changed");
+    }
+    eprintln!("{} of {total} job(s) succeeded", total - failed);
+    match first_error {
+        Some(e) => Err(e).context(format!("{failed} of {total} job(s) failed")),
+        None => Ok(()),
+    }
+}
+
+/// Where the state of a manifest's jobs is kept.
+fn state_path(manifest: &Path) -> PathBuf {
+    let mut name = manifest.as_os_str().to_owned();
+    name.push(".state.json");
+    PathBuf::from(name)
+}
+
+fn read_state(path: &Path) -> anyhow::Result<State> {
+    if !path.exists() {
+        return Ok(State::default());
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Save the state of the jobs. Failing to is only warned about, as it only
+/// means jobs are run again.
+fn write_state(path: &Path, state: &State) {
+    let result = serde_json::to_string_pretty(state)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(std::fs::write(path, json)?));
+    if let Err(e) = result {
+        tracing::warn!("Failed to save the state of the jobs: {e:#}");
+    }
+}
+
+/// A hash of a job's source and the settings it is translated with, once
+/// the settings files have been applied as the translation will, which
+/// changes if either does. This covers what the checkpoint of a run is
+/// checked against, and the settings which only change how the translation
+/// is written.
+fn fingerprint(engine: &EngineArgs, mut args: TranslateArgs) -> anyhow::Result<String> {
+    let mut engine = engine.clone();
+    let formatting = Config::for_source(&args.source_file)?.apply(&mut args, &mut engine)?;
+    let target = args.language_to.clone().unwrap_or_default();
+    formatting.apply(&target, &mut args);
+    let mut hasher = Sha256::new();
+    let settings = [
+        checkpoint::fingerprint(&args, &engine.instances(), &target)?,
+        args.chunk_size.to_string(),
+        args.report
+            .as_deref()
+            .unwrap_or(Path::new(""))
+            .display()
+            .to_string(),
+        format!(
+            "{:?} {:?} {:?} {:?} {:?}",
+            args.max_line_length, args.hyphenation, args.max_cps, args.min_gap, args.formality
+        ),
+        format!(
+            "{}{}{}{}",
+            u8::from(args.space_punctuation),
+            u8::from(args.smart_quotes),
+            u8::from(args.fix_case),
+            u8::from(args.localize_numbers),
+        ),
+    ];
+    for setting in settings {
+        hasher.update([0]);
+        hasher.update(setting);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Build the arguments to translate a job with.
+fn translate_args(job: Job, defaults: &Settings, base: &Path) -> anyhow::Result<TranslateArgs> {
+    let settings = job.settings.or(defaults);
+    let language_from = match settings.language_from.as_deref() {
+        None => "auto".to_string(),
+        Some(language) if language.eq_ignore_ascii_case("auto") => "auto".to_string(),
+        Some(language) => resolve(language)?,
+    };
+    let mut args = TranslateArgs::new(
+        base.join(job.source),
+        language_from,
+        resolve_target(&job.language)?,
+        job.destination.map(|destination| base.join(destination)),
+    );
+    args.chunk_size = settings.chunk_size.unwrap_or(args.chunk_size);
+    args.overwrite = settings.overwrite.unwrap_or(false);
+    args.backup = settings.backup.unwrap_or(false);
+    args.report = job.report.map(|report| base.join(report));
+    Ok(args)
+}
+
+/// Resolve a language given in the manifest.
+fn resolve(language: &str) -> anyhow::Result<String> {
+    languages::resolve(language)
+        .map_err(|_| anyhow::anyhow!("Unknown language '{language}' in manifest"))
+        .failure(Failure::Parse)
+}
+
+/// Resolve a target language given in the manifest, keeping its region.
+fn resolve_target(language: &str) -> anyhow::Result<String> {
+    languages::resolve_target(language)
+        .map_err(|_| anyhow::anyhow!("Unknown language '{language}' in manifest"))
+        .failure(Failure::Parse)
+}
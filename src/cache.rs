@@ -0,0 +1,300 @@
+//! The persistent cache of translations, so lines which have been translated
+//! before aren't sent to the engine again.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use subtitle_translate::{
+    api_types::Translation,
+    translator::{Hints, Translator},
+};
+
+use crate::{cli::CacheCommand, dirs};
+
+/// What a translation was made from.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Key {
+    /// The engine which made it, as its instance's URL.
+    engine: String,
+    source: String,
+    target: String,
+    /// Only given for translations made with hints.
+    #[serde(default, skip_serializing_if = "Hints::is_empty")]
+    hints: Hints,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    key: Key,
+    translation: Translation,
+    /// When the entry was made and last used, in seconds since the Unix epoch.
+    created: u64,
+    used: u64,
+}
+
+/// The cache as it is stored.
+#[derive(Default, Serialize, Deserialize)]
+struct Stored {
+    hits: u64,
+    misses: u64,
+    entries: Vec<Entry>,
+}
+
+#[derive(Default)]
+struct State {
+    hits: u64,
+    misses: u64,
+    entries: HashMap<Key, Entry>,
+}
+
+pub struct Cache {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+/// Manage the cache.
+pub fn run(command: CacheCommand) -> anyhow::Result<()> {
+    let cache = Cache::open()?;
+    match command {
+        CacheCommand::Stats => {
+            let state = cache.state();
+            let size = std::fs::metadata(&cache.path).map_or(0, |m| m.len());
+            let lookups = state.hits + state.misses;
+            println!("Location:  {}", cache.path.display());
+            println!("Entries:   {}", state.entries.len());
+            println!("Size:      {} KiB", size.div_ceil(1024));
+            println!("Hits:      {}", state.hits);
+            println!("Misses:    {}", state.misses);
+            if lookups > 0 {
+                #[allow(clippy::cast_precision_loss)]
+                let rate = state.hits as f64 / lookups as f64 * 100.0;
+                println!("Hit rate:  {rate:.1}%");
+            }
+            if let Some(oldest) = state.entries.values().map(|entry| entry.used).min() {
+                let days = now().saturating_sub(oldest) / DAY.as_secs();
+                println!("Least recently used {days} day(s) ago");
+            }
+        }
+        CacheCommand::Clear { older_than } => {
+            let mut state = cache.state();
+            let before = state.entries.len();
+            match older_than {
+                Some(days) => {
+                    let cutoff = now().saturating_sub(days * DAY.as_secs());
+                    state.entries.retain(|_, entry| entry.used >= cutoff);
+                }
+                None => *state = State::default(),
+            }
+            let removed = before - state.entries.len();
+            drop(state);
+            cache.save()?;
+            println!("Removed {removed} entries");
+        }
+        CacheCommand::Export { file } => {
+            cache.write(&file)?;
+        }
+        CacheCommand::Import { file } => {
+            let imported = read(&file)?;
+            let count = imported.entries.len();
+            let mut state = cache.state();
+            for entry in imported.entries {
+                match state.entries.get(&entry.key) {
+                    Some(existing) if existing.used >= entry.used => {}
+                    _ => {
+                        state.entries.insert(entry.key.clone(), entry);
+                    }
+                }
+            }
+            drop(state);
+            cache.save()?;
+            println!("Imported {count} entries");
+        }
+    }
+    Ok(())
+}
+
+const DAY: Duration = Duration::from_hours(24);
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+fn read(path: &Path) -> anyhow::Result<Stored> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+impl Cache {
+    /// Open the cache in the cache directory, which is empty if it doesn't
+    /// exist yet.
+    pub fn open() -> anyhow::Result<Self> {
+        let path = dirs::cache()
+            .context("Couldn't find a cache directory")?
+            .join("translations.json");
+        let stored = if path.exists() {
+            read(&path)?
+        } else {
+            Stored::default()
+        };
+        let state = State {
+            hits: stored.hits,
+            misses: stored.misses,
+            entries: stored
+                .entries
+                .into_iter()
+                .map(|entry| (entry.key.clone(), entry))
+                .collect(),
+        };
+        Ok(Self {
+            path,
+            state: Mutex::new(state),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn get(&self, key: &Key) -> Option<Translation> {
+        let mut state = self.state();
+        let Some(entry) = state.entries.get_mut(key) else {
+            state.misses += 1;
+            return None;
+        };
+        entry.used = now();
+        let translation = Translation {
+            cached: true,
+            ..entry.translation.clone()
+        };
+        state.hits += 1;
+        Some(translation)
+    }
+
+    fn insert(&self, key: Key, translation: Translation) {
+        let now = now();
+        let entry = Entry {
+            key: key.clone(),
+            translation,
+            created: now,
+            used: now,
+        };
+        self.state().entries.insert(key, entry);
+    }
+
+    /// Store the cache, so it can be used next time.
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create the cache directory")?;
+        }
+        self.write(&self.path)
+    }
+
+    fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let state = self.state();
+        let mut entries: Vec<_> = state.entries.values().collect();
+        entries.sort_by_key(|entry| entry.created);
+        let json = serde_json::to_string(&serde_json::json!({
+            "hits": state.hits,
+            "misses": state.misses,
+            "entries": entries,
+        }))?;
+        drop(state);
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// A translator which looks lines up in the cache before translating them,
+/// and caches the translations it makes.
+pub struct Cached<T> {
+    inner: T,
+    /// Identifies the engine, so translations by others aren't used.
+    engine: String,
+    cache: Option<Arc<Cache>>,
+}
+
+impl<T> Cached<T> {
+    /// Cache the translations made by `inner`, unless there is no cache.
+    pub fn new(inner: T, engine: impl Into<String>, cache: Option<Arc<Cache>>) -> Self {
+        Self {
+            inner,
+            engine: engine.into(),
+            cache,
+        }
+    }
+
+    /// The translator, bypassing the cache.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Translator> Translator for Cached<T> {
+    async fn translate(
+        &self,
+        input: String,
+        source: &str,
+        target: &str,
+        alternatives: u32,
+    ) -> anyhow::Result<Translation> {
+        self.translate_with_hints(input, source, target, alternatives, Hints::default())
+            .await
+    }
+
+    fn language_code(&self, language: &str) -> String {
+        self.inner.language_code(language)
+    }
+
+    fn supports_hints(&self) -> bool {
+        self.inner.supports_hints()
+    }
+
+    async fn translate_with_hints(
+        &self,
+        input: String,
+        source: &str,
+        target: &str,
+        alternatives: u32,
+        hints: Hints,
+    ) -> anyhow::Result<Translation> {
+        // Alternatives are only asked for when the cached translation isn't
+        // wanted.
+        let Some(cache) = self.cache.as_ref().filter(|_| alternatives == 0) else {
+            return self
+                .inner
+                .translate_with_hints(input, source, target, alternatives, hints)
+                .await;
+        };
+        let key = Key {
+            engine: self.engine.clone(),
+            source: source.to_string(),
+            target: target.to_string(),
+            hints: if self.inner.supports_hints() {
+                hints
+            } else {
+                Hints::default()
+            },
+            text: input,
+        };
+        if let Some(translation) = cache.get(&key) {
+            tracing::debug!("Found translation in cache");
+            return Ok(translation);
+        }
+        let translation = self
+            .inner
+            .translate_with_hints(key.text.clone(), source, target, alternatives, hints)
+            .await?;
+        cache.insert(key, translation.clone());
+        Ok(translation)
+    }
+}
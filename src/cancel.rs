@@ -0,0 +1,103 @@
+//! Cancelling translations from elsewhere, such as a frontend's cancel button,
+//! without depending on any async runtime.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Waker},
+};
+
+/// Tells a [`TranslationPipeline`](crate::TranslationPipeline) to stop. Clones
+/// share the same state, so one can be kept to cancel with while another is
+/// given away.
+///
+/// ```
+/// use subtitle_translate::cancel::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let given = token.clone();
+/// token.cancel();
+/// assert!(given.is_cancelled());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    /// The tasks waiting for cancellation.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel, waking anything waiting on [`cancelled`](Self::cancelled).
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut *self.wakers());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until cancelled.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + '_ {
+        WaitForCancellation { token: self }
+    }
+
+    fn wakers(&self) -> std::sync::MutexGuard<'_, Vec<Waker>> {
+        self.0.wakers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The future returned by [`CancellationToken::cancelled`].
+struct WaitForCancellation<'a> {
+    token: &'a CancellationToken,
+}
+
+impl Future for WaitForCancellation<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.token.wakers();
+        // Checked again now the lock is held, so a cancellation in between
+        // isn't missed.
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// The error a cancelled translation ends with. The lines translated before
+/// it was cancelled are kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Translation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
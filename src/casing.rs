@@ -0,0 +1,130 @@
+//! Tidying the capitalisation of translations, which engines often get wrong
+//! at the start of sentences and with lines written in capitals.
+//!
+//! Other capitalisation, such as of German nouns, is left to the engine.
+
+use crate::segment;
+
+/// Fix the capitalisation of a translation into a language, given the line
+/// it was translated from.
+///
+/// If the original is in capitals, as lines being shouted or sound effects
+/// often are, so is the translation. Otherwise each sentence in it starts
+/// with a capital, as does the line if the original did, and in English a
+/// lone `i` becomes `I`. Formatting tags are left alone.
+///
+/// ```
+/// use subtitle_translate::casing::fix_case;
+///
+/// assert_eq!(fix_case("Well. Yes!", "well. i think so", "en"), "Well. I think so");
+/// assert_eq!(fix_case("STOP!", "Halt!", "de"), "HALT!");
+/// assert_eq!(fix_case("YES", "<i>evet</i>", "tr"), "<i>EVET</i>");
+/// ```
+#[must_use]
+pub fn fix_case(original: &str, translation: &str, language: &str) -> String {
+    let language = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let turkic = matches!(language.as_str(), "tr" | "az");
+    if is_capitals(original) {
+        return map_text(translation, |_, c, _| upper(c, turkic));
+    }
+
+    let starts_capitalised = first_letter(original).is_some_and(char::is_uppercase);
+    let mut capitalise = starts_capitalised;
+    let mut fixed = map_text(translation, |before, c, _| {
+        if !c.is_alphabetic() {
+            if c.is_whitespace() && segment::ends_sentence(before, &language) {
+                capitalise = true;
+            }
+            return c.to_string();
+        }
+        if std::mem::take(&mut capitalise) {
+            upper(c, turkic)
+        } else {
+            c.to_string()
+        }
+    });
+    if language == "en" {
+        fixed = map_text(&fixed, |before, c, after| {
+            let alone = !before
+                .chars()
+                .next_back()
+                .is_some_and(char::is_alphanumeric)
+                && !after.chars().next().is_some_and(char::is_alphanumeric);
+            if c == 'i' && alone {
+                "I".to_string()
+            } else {
+                c.to_string()
+            }
+        });
+    }
+    fixed
+}
+
+/// Whether a line is written in capitals, having at least two letters and
+/// none in lowercase.
+fn is_capitals(text: &str) -> bool {
+    let mut letters = 0;
+    let mut capitals = true;
+    map_text(text, |_, c, _| {
+        if c.is_alphabetic() {
+            letters += 1;
+            capitals &= !c.is_lowercase();
+        }
+        String::new()
+    });
+    letters >= 2 && capitals
+}
+
+/// The first letter of the visible text.
+fn first_letter(text: &str) -> Option<char> {
+    let mut first = None;
+    map_text(text, |_, c, _| {
+        if c.is_alphabetic() {
+            first.get_or_insert(c);
+        }
+        String::new()
+    });
+    first
+}
+
+/// A letter in uppercase, with the dotted and dotless `i` of Turkic languages.
+fn upper(c: char, turkic: bool) -> String {
+    match c {
+        'i' if turkic => "İ".to_string(),
+        c => c.to_uppercase().collect(),
+    }
+}
+
+/// Replace each visible character, given the text before and after it,
+/// leaving formatting tags, override blocks and escapes such as `\N` alone.
+fn map_text(text: &str, mut f: impl FnMut(&str, char, &str) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.char_indices();
+    let mut closing = None;
+    while let Some((idx, c)) = chars.next() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (Some(end), c) if c == end => closing = None,
+            (Some(_), _) => {}
+            (None, '\\') => {
+                result.push(c);
+                if let Some((_, escaped)) = chars.next() {
+                    result.push(escaped);
+                }
+                continue;
+            }
+            (None, c) => {
+                let after = &text[idx + c.len_utf8()..];
+                result.push_str(&f(&text[..idx], c, after));
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
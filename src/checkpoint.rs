@@ -0,0 +1,190 @@
+//! Checkpoints of runs which were interrupted or failed, kept beside their
+//! destination so the next run of the same source and settings can carry on
+//! where they stopped rather than starting over.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtitle_translate::{subtitle::GenericSubtitle, summary::Summary};
+
+use crate::{
+    cli::{EmptyCues, EngineArgs, TranslateArgs},
+    glossary,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// A hash of the source and the settings which change which cues there
+    /// are and what is sent to the engine.
+    fingerprint: String,
+    /// The translations of the cues which were translated, by index, before
+    /// they were tidied up.
+    translations: BTreeMap<usize, String>,
+}
+
+impl Checkpoint {
+    /// A checkpoint of the cues the summary says were translated.
+    pub fn new(fingerprint: String, translated: &[GenericSubtitle], summary: &Summary) -> Self {
+        let translations = translated
+            .iter()
+            .enumerate()
+            .filter(|(cue, _)| summary.outcome(*cue).status.is_translated())
+            .map(|(cue, subtitle)| (cue, subtitle.text.clone()))
+            .collect();
+        Self {
+            fingerprint,
+            translations,
+        }
+    }
+
+    /// Keep the checkpoint beside the destination if the run didn't finish,
+    /// or remove any there if it did. Failing to is only warned about.
+    pub fn update(&self, destination: &Path, finished: bool) {
+        let path = path(destination);
+        let result = if finished {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        } else {
+            serde_json::to_string(self)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(std::fs::write(&path, json)?))
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to update the checkpoint {}: {e:#}", path.display());
+        }
+    }
+}
+
+/// The path of the checkpoint of a run writing to `destination`.
+pub fn path(destination: &Path) -> PathBuf {
+    let mut name = destination.as_os_str().to_owned();
+    name.push(".checkpoint.json");
+    PathBuf::from(name)
+}
+
+/// The translations to resume from, by cue index, if a run of the same source
+/// and settings left a checkpoint and it is to be resumed, and otherwise
+/// `None`, with the fingerprint to checkpoint this run with. With neither `--resume` nor
+/// `--restart`, the user is asked in a terminal, and it is resumed otherwise.
+pub fn resume(
+    args: &TranslateArgs,
+    engine: &EngineArgs,
+    target: &str,
+    destination: &Path,
+) -> anyhow::Result<(Option<BTreeMap<usize, String>>, String)> {
+    let fingerprint = fingerprint(args, &engine.instances(), target)?;
+    let path = path(destination);
+    if !path.exists() {
+        return Ok((None, fingerprint));
+    }
+    let checkpoint = match read(&path) {
+        Ok(checkpoint) if checkpoint.fingerprint == fingerprint => checkpoint,
+        Ok(_) => {
+            tracing::info!("Ignoring the checkpoint of a run with another source or settings");
+            return Ok((None, fingerprint));
+        }
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable checkpoint: {e:#}");
+            return Ok((None, fingerprint));
+        }
+    };
+    let count = checkpoint.translations.len();
+    let resume = if args.resume || args.restart {
+        args.resume
+    } else if std::io::stdin().is_terminal() {
+        ask(count)?
+    } else {
+        tracing::info!("Resuming an unfinished run, pass --restart to start over instead");
+        true
+    };
+    if resume {
+        tracing::info!("Resuming with {count} cue(s) already translated");
+        Ok((Some(checkpoint.translations), fingerprint))
+    } else {
+        tracing::info!("Starting over");
+        Ok((None, fingerprint))
+    }
+}
+
+fn read(path: &Path) -> anyhow::Result<Checkpoint> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Ask whether to resume an unfinished run which translated `count` cues.
+fn ask(count: usize) -> anyhow::Result<bool> {
+    eprint!(
+        "An unfinished run of this file translated {count} cue(s). Resume it, rather than \
+         starting over? [Y/n] "
+    );
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(!answer.trim().to_ascii_lowercase().starts_with('n'))
+}
+
+/// A hash of the source and the settings which change which cues there are
+/// and what is sent to the engine, including the contents of the glossaries,
+/// plugins and scripts run over each line, so a checkpoint isn't resumed by a
+/// run which would translate differently.
+pub fn fingerprint(args: &TranslateArgs, instance: &str, target: &str) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(
+        std::fs::read(&args.source_file)
+            .with_context(|| format!("Failed to read {}", args.source_file.display()))?,
+    );
+    let mut files = args
+        .glossary
+        .iter()
+        .map(|glossary| glossary::file(glossary))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    #[cfg(feature = "plugins")]
+    files.extend(args.plugin.iter().cloned());
+    #[cfg(feature = "lua")]
+    files.extend(args.script.iter().cloned());
+    files.extend(args.sync_to.clone());
+    let settings = [
+        instance.to_string(),
+        args.language_from.clone(),
+        target.to_string(),
+        args.alternatives.to_string(),
+        args.skip_styles.join(","),
+        args.only_styles.join(","),
+        format!("{:?}", args.merge_short),
+        format!("{:?}", args.fix_overlaps),
+        format!("{:?}", args.ruby),
+        format!("{:?}", args.hints()),
+        format!("{:?} {}", args.sync_to, args.max_offset),
+        format!(
+            "{}{}{}{}{}{}{}{}",
+            u8::from(args.lenient),
+            u8::from(args.forced_only),
+            u8::from(args.detect_per_line),
+            u8::from(args.keep_line_breaks),
+            u8::from(args.sort),
+            u8::from(matches!(args.empty_cues, EmptyCues::Drop)),
+            u8::from(args.no_protect_symbols),
+            u8::from(args.preserve_honorifics),
+        ),
+    ];
+    for setting in settings {
+        hasher.update([0]);
+        hasher.update(setting);
+    }
+    for file in files {
+        hasher.update([0]);
+        hasher.update(
+            std::fs::read(&file).with_context(|| format!("Failed to read {}", file.display()))?,
+        );
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
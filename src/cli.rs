@@ -0,0 +1,1532 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Write as _,
+    net::SocketAddr,
+    path::PathBuf,
+    time::Duration,
+};
+
+use clap::{
+    Arg, CommandFactory, Parser, Subcommand, ValueEnum,
+    builder::{PossibleValue, TypedValueParser},
+    error::ErrorKind,
+};
+use clap_complete::Shell;
+use clap_verbosity_flag::Verbosity;
+use reqwest::Url;
+use serde::Deserialize;
+use subtitle_translate::{
+    guidelines::Guidelines,
+    languages::{KNOWN_LANGUAGES, resolve, resolve_target},
+    libretranslate::{Balancing, LibreTranslate},
+    qa::Limits,
+    repair::OverlapStrategy,
+    subtitle,
+    translator::{Formality, Gender, Hints},
+};
+
+use crate::exit_code;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about,
+    arg_required_else_help = true,
+    after_long_help = exit_code::HELP
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+
+    #[command(flatten)]
+    pub engine: EngineArgs,
+
+    /// The format to write log messages in
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Also write log messages to this file
+    #[arg(long, global = true, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// How often to start a new log file, keeping the old ones with the date
+    /// appended
+    #[arg(long, global = true, value_enum, default_value_t = LogRotation::Daily, requires = "log_file")]
+    pub log_rotation: LogRotation,
+
+    /// Instead of rotating by time, start a new log file once it would grow
+    /// beyond this many megabytes, keeping the last 5 as FILE.1 to FILE.5
+    #[arg(
+        long,
+        global = true,
+        value_name = "MB",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "log_file",
+        conflicts_with = "log_rotation"
+    )]
+    pub log_max_size: Option<u64>,
+
+    #[command(flatten)]
+    pub verbose: Verbosity,
+}
+
+impl Cli {
+    /// Parse the command line. For compatibility, the original form without a
+    /// subcommand is treated as `translate`, in which case `true` is returned
+    /// alongside the arguments.
+    pub fn parse_with_compat() -> (Self, bool) {
+        let mut args: Vec<OsString> = std::env::args_os().collect();
+        let compat = needs_translate_shim(&args);
+        if compat {
+            args.insert(1, OsString::from("translate"));
+        }
+        (Self::parse_from(args), compat)
+    }
+}
+
+/// Whether the arguments are in the original form, without a subcommand.
+fn needs_translate_shim(args: &[OsString]) -> bool {
+    let cmd = Cli::command();
+    let mut known = vec!["help", "-h", "--help", "-V", "--version"];
+    for sub in cmd.get_subcommands() {
+        known.push(sub.get_name());
+        known.extend(sub.get_all_aliases());
+    }
+    let rest = args.get(1..).unwrap_or_default();
+    !rest.is_empty()
+        && !rest
+            .iter()
+            .any(|a| a.to_str().is_some_and(|a| known.contains(&a)))
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable text
+    Text,
+    /// One JSON object per event, for log pipelines
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LogRotation {
+    /// Always write to the same file
+    Never,
+    Hourly,
+    Daily,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Translate a subtitle file
+    ///
+    /// Defaults for the source and target languages, chunk size, overwriting
+    /// and glossaries can be given in `config.toml` in the configuration
+    /// directory, and in a `.subtitle-translate.toml` in the source file's
+    /// folder or any folder above it.
+    Translate(Box<TranslateArgs>),
+
+    /// Translate many subtitle files, as listed in a manifest
+    Batch(BatchArgs),
+
+    /// Find videos in a media library with subtitles in one language but not
+    /// another, and translate them
+    ScanLibrary(ScanLibraryArgs),
+
+    /// Run an HTTP server which translates uploaded subtitle files from a
+    /// persistent queue
+    #[command(alias = "daemon")]
+    Serve(ServeArgs),
+
+    /// Submit a subtitle file to a running server's queue
+    Submit(SubmitArgs),
+
+    /// Translate an evenly spaced sample of a subtitle file's cues, to judge
+    /// how well it will be translated before translating all of it
+    Sample(SampleArgs),
+
+    /// Review and edit a translated subtitle file in a full-screen interface
+    Review(ReviewArgs),
+
+    /// Convert a subtitle file to another format, without translating it
+    Convert(ConvertArgs),
+
+    /// Compare two subtitle files, pairing their cues by when they are shown
+    Diff(DiffArgs),
+
+    /// Combine two subtitle files, such as an original and its translation,
+    /// into one bilingual file
+    Merge(MergeArgs),
+
+    /// Write a subtitle file and its translation side by side as a CSV or TSV
+    /// file, with index, start, end, source and translation columns, for
+    /// reviewers to edit in a spreadsheet
+    ExportCsv(ExportCsvArgs),
+
+    /// Rebuild a subtitle file from the timing and translations of a CSV or
+    /// TSV file written by `export-csv`, once it has been edited
+    ImportCsv(ImportCsvArgs),
+
+    /// Change when the cues of a subtitle file are shown
+    #[command(subcommand)]
+    Timing(TimingCommand),
+
+    /// Cut a subtitle file into parts at the given times, such as for a film
+    /// released on several discs
+    Split(SplitArgs),
+
+    /// Join the subtitle files for the parts of a video into one
+    Concat(ConcatArgs),
+
+    /// Show statistics about a subtitle file
+    Inspect(InspectArgs),
+
+    /// Check a subtitle file for timing, length and content problems
+    Validate(ValidateArgs),
+
+    /// Maintain glossaries of terms which must be translated a particular way
+    #[command(subcommand)]
+    Glossary(GlossaryCommand),
+
+    /// Inspect and manage the cache of previous translations
+    #[command(subcommand)]
+    Cache(CacheCommand),
+
+    /// Look through the files translated before, and how they were
+    #[command(subcommand)]
+    History(HistoryCommand),
+
+    /// List the languages supported by the LibreTranslate instance
+    ListLanguages,
+
+    /// Check that the LibreTranslate instance and local setup work, suggesting
+    /// fixes for any problems
+    Doctor(DoctorArgs),
+
+    /// Update to the latest release from GitHub
+    SelfUpdate(SelfUpdateArgs),
+
+    /// Generate shell completions
+    #[command(hide = true)]
+    Completions {
+        /// The shell to generate completions for
+        shell: Shell,
+    },
+
+    /// Generate man pages
+    #[command(hide = true)]
+    Mangen {
+        /// Write a page for every subcommand into this directory, rather than
+        /// printing the main page
+        #[arg(short, long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+/// The LibreTranslate instance used if no other is given.
+pub const LIBRETRANSLATE_INSTANCE: &str = "http://localhost:5000/translate";
+
+#[derive(clap::Args, Clone)]
+pub struct EngineArgs {
+    /// The URL of the LibreTranslate instance's translation API. Give more
+    /// than one to share requests between them
+    #[arg(
+        short = 'L',
+        long = "libretranslate-instance",
+        value_name = "URL",
+        global = true,
+        default_value = LIBRETRANSLATE_INSTANCE
+    )]
+    pub libretranslate_instances: Vec<String>,
+
+    /// How requests are shared between several LibreTranslate instances
+    #[arg(long, global = true, value_enum, default_value_t = Balancing::RoundRobin)]
+    pub balance: Balancing,
+
+    /// Give up on requests to LibreTranslate taking longer than this
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECONDS",
+        default_value_t = 60,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub timeout: u64,
+
+    /// Rest an instance, sending its requests to the others, once this many
+    /// requests to it in a row have failed or timed out
+    #[arg(long, global = true, value_name = "FAILURES", default_value_t = 3)]
+    pub trip_after: usize,
+
+    /// How long a failing instance is rested for before it is tried again
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = 30)]
+    pub cool_down: u64,
+
+    /// The API key for the LibreTranslate instance, if it is needed
+    #[arg(short = 'A', long, global = true)]
+    pub libretranslate_apikey: Option<String>,
+}
+
+impl EngineArgs {
+    pub fn translator(&self) -> LibreTranslate {
+        LibreTranslate::with_instances(
+            self.libretranslate_instances.clone(),
+            self.libretranslate_apikey.clone(),
+            self.balance,
+        )
+        .timeout(Duration::from_secs(self.timeout))
+        .circuit_breaker(self.trip_after, Duration::from_secs(self.cool_down))
+    }
+
+    /// The instances, separated by commas, as they are recorded and
+    /// translations are cached under.
+    pub fn instances(&self) -> String {
+        self.libretranslate_instances.join(",")
+    }
+}
+
+/// The default number of lines translated at once.
+pub const CHUNK_SIZE: usize = 5;
+
+#[derive(clap::Args)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct TranslateArgs {
+    /// Set the size of the chunk used for parallel processing
+    #[arg(
+        short = 'C',
+        long,
+        default_value_t = CHUNK_SIZE,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub chunk_size: usize,
+
+    /// The source language, as a code or name.
+    #[arg(
+        short = 'f',
+        long,
+        default_value = "auto",
+        value_parser = LanguageCodeParser::source(),
+        hide_possible_values = true
+    )]
+    pub language_from: String,
+
+    /// The source subtitle file
+    #[arg(index = 1)]
+    pub source_file: PathBuf,
+
+    /// The target language, as a code or name. If it is left out when running
+    /// in a terminal, you will be asked to pick one.
+    #[arg(index = 2, value_parser = LanguageCodeParser::target(), hide_possible_values = true)]
+    pub language_to: Option<String>,
+
+    /// The destination subtitle file. Defaults to the source's name with the
+    /// target language added, such as `Film.de.srt`, beside it
+    #[arg(index = 3)]
+    pub destination_file: Option<PathBuf>,
+
+    /// Name the destination from a template when none is given, beside the
+    /// source, with `{name}` replaced by the source's name and `{lang}` by the
+    /// target language, such as `{name}.{lang}.srt`
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "destination_file")]
+    pub output_template: Option<String>,
+
+    /// Replace the source file with its translation, first copying it to
+    /// `.orig` beside it. Only SRT sources can be, as translations are
+    /// written as SRT
+    #[arg(
+        long,
+        conflicts_with_all = ["destination_file", "output_template", "existing_destination"]
+    )]
+    pub in_place: bool,
+
+    /// Use the settings of a profile from the settings files, such as
+    /// `[profile.anime-de]`
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Replace the destination file if it already exists
+    #[arg(long, group = "existing_destination")]
+    pub overwrite: bool,
+
+    /// Refuse to replace the destination file if it already exists (default)
+    #[arg(long, group = "existing_destination")]
+    pub no_clobber: bool,
+
+    /// Copy the destination file to `.bak` if it already exists, before
+    /// replacing it
+    #[arg(long, group = "existing_destination")]
+    pub backup: bool,
+
+    /// Carry on from where an unfinished run of the same source and settings
+    /// stopped, without asking. Runs which are interrupted or fail leave a
+    /// checkpoint beside the destination to carry on from
+    #[arg(long, conflicts_with = "restart")]
+    pub resume: bool,
+
+    /// Start over, rather than carrying on from where an unfinished run of
+    /// the same source and settings stopped, without asking
+    #[arg(long)]
+    pub restart: bool,
+
+    /// Review each translated line before the destination is written
+    #[arg(short = 'i', long)]
+    pub interactive: bool,
+
+    /// Show the original and translated lines side by side, either in the
+    /// terminal or, if a path is given, as an HTML file
+    #[arg(long, value_name = "HTML_FILE", num_args = 0..=1, default_missing_value = "-")]
+    pub preview: Option<PathBuf>,
+
+    /// Write a report of each line's status, quality problems and timing, as
+    /// JSON or CSV depending on the file's extension
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+
+    /// Write a QA report, flagging each cue which is too long, too fast to
+    /// read, left untranslated, much shorter or longer than the original, or
+    /// of low confidence, as JSON to this file, and summarise it
+    #[arg(long, value_name = "FILE")]
+    pub qa_report: Option<PathBuf>,
+
+    /// Exit with an error once the destination is written if any cue would be
+    /// flagged in the QA report, or any line failed to translate
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Carry on translating after lines fail, rather than stopping, leaving
+    /// them untranslated. The destination is still written, but the run ends
+    /// with an error
+    #[arg(long)]
+    pub continue_on_error: bool,
+
+    /// Write the lines which failed to translate, with their timing, text and
+    /// error, as JSON or CSV depending on the file's extension, for retrying
+    /// them with --lines-from
+    #[arg(long, value_name = "FILE")]
+    pub errors: Option<PathBuf>,
+
+    /// Translate only the cues listed in a file written by --errors, keeping
+    /// the existing translations of the rest in the destination, which is
+    /// replaced
+    #[arg(long, value_name = "FILE", conflicts_with = "in_place")]
+    pub lines_from: Option<PathBuf>,
+
+    /// Print what translating the file would cost with commercial engines,
+    /// rather than translating it
+    #[arg(long)]
+    pub estimate_cost: bool,
+
+    /// Write progress events to stdout as they happen, for frontends to follow
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub progress: Option<ProgressFormat>,
+
+    /// Translate every line again, rather than using translations cached by
+    /// previous runs
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Translate terms as a glossary says to. Either the name of a glossary, or
+    /// a CSV, TSV or TBX file. A `glossary.csv` in the source file's folder, or
+    /// any folder above it, is always used
+    #[arg(short = 'g', long, value_name = "GLOSSARY")]
+    pub glossary: Vec<String>,
+
+    /// Detect the language of each cue, and only translate the cues which
+    /// aren't already in the target language, for files which mix languages
+    #[arg(long)]
+    pub detect_per_line: bool,
+
+    /// Make a forced track: translate only the cues in a language other than
+    /// the rest of the file, such as signs and foreign dialogue, and leave
+    /// out the others. A source named as a forced track, such as
+    /// `Film.en.forced.srt`, is translated whole
+    #[arg(long, conflicts_with = "detect_per_line")]
+    pub forced_only: bool,
+
+    /// Leave the cues of these ASS or SSA styles untranslated, such as typeset
+    /// signs and karaoke, separated by commas
+    #[arg(long, value_name = "STYLES", value_delimiter = ',')]
+    pub skip_styles: Vec<String>,
+
+    /// Only translate the cues of these ASS or SSA styles, separated by commas
+    #[arg(
+        long,
+        value_name = "STYLES",
+        value_delimiter = ',',
+        conflicts_with = "skip_styles"
+    )]
+    pub only_styles: Vec<String>,
+
+    /// Keep Japanese and Korean honorifics, such as `-san` and `oppa`,
+    /// attached to the names in the glossaries, rather than letting them be
+    /// dropped or translated. The source language must be given
+    #[arg(long)]
+    pub preserve_honorifics: bool,
+
+    /// Read a malformed SRT source as well as possible, recovering from
+    /// missing blank lines, broken cue numbers and stray byte order marks, and
+    /// dropping what can't be read
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// What to do with cues without any visible text, being empty or only
+    /// whitespace or formatting. They are never sent to be translated
+    #[arg(long, value_enum, value_name = "HOW", default_value_t = EmptyCues::Keep)]
+    pub empty_cues: EmptyCues,
+
+    /// Send each line of a cue to be translated as it is, rather than joining
+    /// them so the whole of what is said is translated at once and wrapping
+    /// the translation into as many lines
+    #[arg(long)]
+    pub keep_line_breaks: bool,
+
+    /// Put the cues in the order they start, rather than the order they are
+    /// in the source file
+    #[arg(long)]
+    pub sort: bool,
+
+    /// Repair cues which overlap the cue before them, by shortening the earlier
+    /// cue or by merging them
+    #[arg(
+        long,
+        value_enum,
+        value_name = "STRATEGY",
+        num_args = 0..=1,
+        default_missing_value = "trim"
+    )]
+    pub fix_overlaps: Option<OverlapStrategy>,
+
+    /// Join consecutive cues shorter than this many milliseconds, such as
+    /// word-by-word automatic captions, into sentences before translating
+    #[arg(long, value_name = "MS")]
+    pub merge_short: Option<u32>,
+
+    /// How long, in milliseconds, to show cues which end before they start,
+    /// which are always repaired
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    pub min_duration: u32,
+
+    /// Show cues which end before they start for as long as reading them at
+    /// this many characters per second takes, if that is longer than the
+    /// minimum duration
+    #[arg(long, value_name = "CPS")]
+    pub duration_cps: Option<f64>,
+
+    /// Tidy the capitalisation of translations: start sentences with a
+    /// capital, keep lines written in capitals in capitals, and write a lone
+    /// `i` as `I` in English
+    #[arg(long)]
+    pub fix_case: bool,
+
+    /// Write decimal points, thousands separators and units in translations
+    /// as the target language does, converting imperial units to metric ones
+    /// unless translating into English
+    #[arg(long)]
+    pub localize_numbers: bool,
+
+    /// Rewrap translations with lines longer than this many characters, and
+    /// flag any still longer in the QA report [default: 42 in the report]
+    #[arg(long, value_name = "CHARS")]
+    pub max_line_length: Option<usize>,
+
+    /// When rewrapping, hyphenate words too long for a line where a TeX
+    /// hyphenation pattern file, such as `hyph-de-1996.pat.txt` from
+    /// hyph-utf8, allows, rather than breaking them anywhere
+    #[arg(long, value_name = "FILE")]
+    pub hyphenation: Option<PathBuf>,
+
+    /// Flag cues in the QA report needing to be read faster than this many
+    /// characters per second [default: 21]
+    #[arg(long, value_name = "CPS")]
+    pub max_cps: Option<f64>,
+
+    /// Put a narrow no-break space before `? ! : ;` and inside guillemets in
+    /// translations, as French typography asks
+    #[arg(long)]
+    pub space_punctuation: bool,
+
+    /// Replace straight quotes and apostrophes in translations with those the
+    /// target language uses, such as `„…“` in German
+    #[arg(long)]
+    pub smart_quotes: bool,
+
+    /// Leave at least this long between each cue and the next, as many style
+    /// guides require, such as `80ms`, by ending cues earlier
+    #[arg(long, value_name = "MS", value_parser = parse_ms)]
+    pub min_gap: Option<u32>,
+
+    /// Follow a style guide's limits: rewrap lines longer than it allows,
+    /// lengthen cues shown too briefly, leave the gaps it asks for between
+    /// cues, and flag cues with too many lines or read too fast in the QA
+    /// report. Any limit given with its own option is used instead
+    #[arg(long, value_enum, value_name = "GUIDE")]
+    pub profile_rules: Option<Guidelines>,
+
+    /// What to do with ruby annotations, such as furigana, which would
+    /// otherwise be translated as part of the line
+    #[arg(long, value_enum, default_value_t = RubyPolicy::Strip)]
+    pub ruby: RubyPolicy,
+
+    /// Mark translations scoring less than this, from 0 to 100, as uncertain.
+    /// Translations score less if the source language was detected with
+    /// little confidence, and for each sign of a bad translation, such as
+    /// being left untranslated or repeating a word
+    #[arg(long, value_name = "SCORE")]
+    pub min_confidence: Option<f64>,
+
+    /// What to put before uncertain translations, for editors to search for
+    #[arg(long, value_name = "TEXT", default_value = "[??] ")]
+    pub uncertain_marker: String,
+
+    /// Keep each cue's original text beside its translation, for reviewers
+    #[arg(long, value_enum, value_name = "HOW")]
+    pub keep_original: Option<KeepOriginal>,
+
+    /// Request this many alternative translations of each cue, and add them
+    /// after the translation in a comment hidden by players, and to the
+    /// report, for reviewers to choose from
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub alternatives: u32,
+
+    /// Send emoji, arrows and dingbats to the translator as they are, rather
+    /// than protecting them from being dropped or repeated
+    #[arg(long)]
+    pub no_protect_symbols: bool,
+
+    /// How formally to address people, in languages which distinguish it,
+    /// if the engine can be told
+    #[arg(long, value_enum)]
+    pub formality: Option<Formality>,
+
+    /// The gender of the speaker, for languages where words referring to them
+    /// agree with it, if the engine can be told
+    #[arg(long, value_enum)]
+    pub speaker_gender: Option<Gender>,
+
+    /// If the source file is a video, download its subtitles in the source
+    /// language from OpenSubtitles, unless they have been downloaded already
+    #[arg(long, conflicts_with = "transcribe")]
+    pub fetch: bool,
+
+    /// The IMDb ID of the video, to help find its subtitles when fetching them
+    #[arg(long, value_name = "ID", requires = "fetch")]
+    pub imdb: Option<String>,
+
+    /// The API key for OpenSubtitles, needed to fetch subtitles
+    #[arg(long, env = "OPENSUBTITLES_API_KEY", hide_env_values = true)]
+    pub opensubtitles_apikey: Option<String>,
+
+    /// Correct the source subtitles' timing against the speech in this video
+    /// before translating them, which needs ffmpeg
+    #[arg(long, value_name = "VIDEO")]
+    pub sync_to: Option<PathBuf>,
+
+    /// The furthest, in seconds, the subtitles may be shifted when syncing
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 60,
+        requires = "sync_to"
+    )]
+    pub max_offset: u32,
+
+    #[command(flatten)]
+    pub whisper: WhisperArgs,
+
+    /// Run a WebAssembly plugin's hooks on each line before and after it is
+    /// translated. Plugins in the `plugins` folder of the configuration
+    /// directory are always run, after these
+    #[cfg(feature = "plugins")]
+    #[arg(long, value_name = "FILE.wasm")]
+    pub plugin: Vec<PathBuf>,
+
+    /// Run a Lua script's `on_source_line`, `on_translated_line` and `on_cue`
+    /// functions on each line before and after it is translated
+    #[cfg(feature = "lua")]
+    #[arg(long, value_name = "FILE.lua")]
+    pub script: Vec<PathBuf>,
+}
+
+impl TranslateArgs {
+    /// The hints to give the translator.
+    pub fn hints(&self) -> Hints {
+        Hints {
+            formality: self.formality,
+            speaker_gender: self.speaker_gender,
+        }
+    }
+
+    /// The limits translations are checked against in the QA report.
+    pub fn limits(&self) -> Limits {
+        let defaults = self
+            .profile_rules
+            .map_or_else(Limits::default, Guidelines::limits);
+        Limits {
+            max_line_length: self.max_line_length.unwrap_or(defaults.max_line_length),
+            max_cps: self.max_cps.unwrap_or(defaults.max_cps),
+            min_confidence: self.min_confidence.unwrap_or(defaults.min_confidence),
+            min_gap: self.min_gap.or(defaults.min_gap),
+            ..defaults
+        }
+    }
+
+    /// Use the line length, reading speed and gap of the style guide given
+    /// with `--profile-rules` for any not given on the command line.
+    pub fn apply_profile_rules(&mut self) {
+        if let Some(guidelines) = self.profile_rules {
+            let limits = guidelines.limits();
+            self.max_line_length = self.max_line_length.or(Some(limits.max_line_length));
+            self.max_cps = self.max_cps.or(Some(limits.max_cps));
+            self.min_gap = self.min_gap.or(limits.min_gap);
+        }
+    }
+
+    /// Arguments to translate a file non-interactively, with the defaults for
+    /// everything else.
+    pub fn new(
+        source_file: PathBuf,
+        language_from: String,
+        language_to: String,
+        destination_file: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            chunk_size: CHUNK_SIZE,
+            language_from,
+            source_file,
+            language_to: Some(language_to),
+            destination_file,
+            output_template: None,
+            profile: None,
+            overwrite: false,
+            in_place: false,
+            no_clobber: false,
+            backup: false,
+            resume: false,
+            restart: false,
+            interactive: false,
+            preview: None,
+            report: None,
+            qa_report: None,
+            strict: false,
+            continue_on_error: false,
+            errors: None,
+            lines_from: None,
+            estimate_cost: false,
+            progress: None,
+            no_cache: false,
+            glossary: Vec::new(),
+            detect_per_line: false,
+            forced_only: false,
+            skip_styles: Vec::new(),
+            only_styles: Vec::new(),
+            preserve_honorifics: false,
+            lenient: false,
+            empty_cues: EmptyCues::Keep,
+            keep_line_breaks: false,
+            sort: false,
+            fix_overlaps: None,
+            merge_short: None,
+            min_duration: 1000,
+            duration_cps: None,
+            fix_case: false,
+            localize_numbers: false,
+            max_line_length: None,
+            hyphenation: None,
+            max_cps: None,
+            space_punctuation: false,
+            smart_quotes: false,
+            min_gap: None,
+            profile_rules: None,
+            ruby: RubyPolicy::Strip,
+            min_confidence: None,
+            uncertain_marker: "[??] ".to_string(),
+            keep_original: None,
+            alternatives: 0,
+            no_protect_symbols: false,
+            formality: None,
+            speaker_gender: None,
+            fetch: false,
+            imdb: None,
+            opensubtitles_apikey: None,
+            sync_to: None,
+            max_offset: 60,
+            whisper: WhisperArgs::default(),
+            #[cfg(feature = "plugins")]
+            plugin: Vec::new(),
+            #[cfg(feature = "lua")]
+            script: Vec::new(),
+        }
+    }
+}
+
+/// Parse a number of milliseconds, optionally followed by `ms`.
+fn parse_ms(value: &str) -> Result<u32, String> {
+    value
+        .trim()
+        .trim_end_matches("ms")
+        .trim_end()
+        .parse()
+        .map_err(|_| format!("'{value}' isn't a number of milliseconds"))
+}
+
+/// The default Whisper API, that of a local server.
+const WHISPER_URL: &str = "http://localhost:8000/v1/audio/transcriptions";
+const WHISPER_MODEL: &str = "whisper-1";
+
+#[derive(clap::Args)]
+#[command(next_help_heading = "Transcription")]
+pub struct WhisperArgs {
+    /// If the source file is a video or audio file, transcribe it with a
+    /// Whisper API and translate the transcription, which is kept beside it
+    #[arg(long)]
+    pub transcribe: bool,
+
+    /// The URL of the Whisper API's transcription endpoint
+    #[arg(long, value_name = "URL", default_value = WHISPER_URL)]
+    pub whisper_url: String,
+
+    /// The Whisper model to transcribe with
+    #[arg(long, value_name = "MODEL", default_value = WHISPER_MODEL)]
+    pub whisper_model: String,
+
+    /// The API key for the Whisper API, if it is needed
+    #[arg(long, env = "WHISPER_API_KEY", hide_env_values = true)]
+    pub whisper_apikey: Option<String>,
+}
+
+impl Default for WhisperArgs {
+    fn default() -> Self {
+        Self {
+            transcribe: false,
+            whisper_url: WHISPER_URL.to_string(),
+            whisper_model: WHISPER_MODEL.to_string(),
+            whisper_apikey: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RubyPolicy {
+    /// Remove the annotations, translating only the text they annotate
+    Strip,
+    /// Remove the annotations before translating, then add them after the
+    /// translation with the text they annotate
+    Keep,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum EmptyCues {
+    /// Write them out as they were
+    Keep,
+    /// Leave them out of the translation
+    Drop,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum KeepOriginal {
+    /// In a comment before each translation, hidden by players, as
+    /// `{original text}`
+    Comments,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ProgressFormat {
+    /// One JSON object per line for each event
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct BatchArgs {
+    /// The TOML manifest listing the jobs to run
+    #[arg(short, long, value_name = "FILE")]
+    pub manifest: PathBuf,
+
+    /// Run every job, even those whose sources and settings haven't changed
+    /// since they were last translated
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ScanLibraryArgs {
+    /// The folder to search for videos, including its subfolders
+    pub media_root: PathBuf,
+
+    /// The language to translate into, as a code or name
+    #[arg(value_parser = LanguageCodeParser::target(), hide_possible_values = true)]
+    pub language_to: String,
+
+    /// The language of the subtitles to translate from, as a code or name
+    #[arg(
+        short = 'f',
+        long,
+        default_value = "en",
+        value_parser = LanguageCodeParser::target(),
+        hide_possible_values = true
+    )]
+    pub language_from: String,
+
+    /// Set the size of the chunk used for parallel processing
+    #[arg(
+        short = 'C',
+        long,
+        default_value_t = 5,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub chunk_size: usize,
+
+    /// List the videos which would be translated, without translating them
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// With --dry-run, also print what translating the videos' external
+    /// subtitles would cost with commercial engines
+    #[arg(long, requires = "dry_run")]
+    pub estimate_cost: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ServeArgs {
+    /// The address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    pub listen: SocketAddr,
+
+    /// Also serve the gRPC interface, on this address
+    #[cfg(feature = "grpc")]
+    #[arg(long, value_name = "ADDRESS")]
+    pub grpc_listen: Option<SocketAddr>,
+
+    /// Set the size of the chunk used for parallel processing
+    #[arg(
+        short = 'C',
+        long,
+        default_value_t = 5,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub chunk_size: usize,
+
+    /// How many jobs to translate at once
+    #[arg(short, long, default_value_t = 2)]
+    pub workers: usize,
+
+    /// Where to keep jobs, so they survive restarts. Defaults to a directory
+    /// in the user's data directory
+    #[arg(long, value_name = "DIR")]
+    pub state_dir: Option<PathBuf>,
+
+    /// How many finished jobs to keep, with their files, before the oldest
+    /// are removed from the state directory
+    #[arg(long, value_name = "JOBS", default_value_t = 1000)]
+    pub keep_jobs: usize,
+
+    /// The languages to translate the subtitles of videos imported by Sonarr
+    /// or Radarr into, separated by commas
+    #[arg(
+        long,
+        value_name = "LANGUAGES",
+        value_delimiter = ',',
+        value_parser = LanguageCodeParser::target(),
+        hide_possible_values = true
+    )]
+    pub arr_languages: Vec<String>,
+
+    /// The language of the subtitles to translate imported videos from
+    #[arg(
+        long,
+        value_name = "LANGUAGE",
+        default_value = "en",
+        value_parser = LanguageCodeParser::target(),
+        hide_possible_values = true
+    )]
+    pub arr_source_language: String,
+
+    /// The secret Sonarr and Radarr must send in the `X-Webhook-Secret`
+    /// header, set as a custom header of their webhook connection. Needed
+    /// with --arr-languages
+    #[arg(long, env = "SUBTITLE_TRANSLATE_ARR_SECRET", hide_env_values = true)]
+    pub arr_secret: Option<String>,
+
+    /// A media library folder which Sonarr and Radarr import into. Only
+    /// videos inside one are translated. Can be given more than once, and is
+    /// needed with --arr-languages
+    #[arg(long = "arr-root", value_name = "DIR")]
+    pub arr_roots: Vec<PathBuf>,
+
+    /// POST a JSON description of each job to this URL when it finishes or
+    /// fails
+    #[arg(long, value_name = "URL")]
+    pub webhook: Option<Url>,
+}
+
+#[derive(clap::Args)]
+pub struct SubmitArgs {
+    /// The URL of the server
+    #[arg(short, long, default_value = "http://127.0.0.1:8080")]
+    pub server: String,
+
+    /// Jobs with a higher priority are translated first
+    #[arg(short, long, default_value_t = 0, allow_negative_numbers = true)]
+    pub priority: i32,
+
+    /// The source language, as a code or name.
+    #[arg(
+        short = 'f',
+        long,
+        default_value = "auto",
+        value_parser = LanguageCodeParser::source(),
+        hide_possible_values = true
+    )]
+    pub language_from: String,
+
+    /// The format to translate into, by extension, if not the same as the
+    /// source's
+    #[arg(short, long)]
+    pub output_format: Option<String>,
+
+    /// The subtitle file to translate
+    pub source_file: PathBuf,
+
+    /// The target language, as a code or name.
+    #[arg(value_parser = LanguageCodeParser::target(), hide_possible_values = true)]
+    pub language_to: String,
+}
+
+#[derive(clap::Args)]
+pub struct ReviewArgs {
+    /// The source language, as a code or name, used when re-translating.
+    #[arg(
+        short = 'f',
+        long,
+        default_value = "auto",
+        value_parser = LanguageCodeParser::source(),
+        hide_possible_values = true
+    )]
+    pub language_from: String,
+
+    /// The target language, as a code or name, used when re-translating.
+    #[arg(
+        short = 't',
+        long,
+        value_parser = LanguageCodeParser::target(),
+        hide_possible_values = true
+    )]
+    pub language_to: Option<String>,
+
+    /// The original subtitle file the translation was made from
+    #[arg(short = 's', long)]
+    pub source: Option<PathBuf>,
+
+    /// The translated subtitle file to review
+    #[arg(index = 1)]
+    pub file: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct SampleArgs {
+    /// The source language, as a code or name
+    #[arg(
+        short = 'f',
+        long,
+        default_value = "auto",
+        value_parser = LanguageCodeParser::source(),
+        hide_possible_values = true
+    )]
+    pub language_from: String,
+
+    /// How many cues to translate
+    #[arg(short = 'n', long, default_value_t = 30)]
+    pub lines: usize,
+
+    /// The subtitle file to sample
+    #[arg(index = 1)]
+    pub file: PathBuf,
+
+    /// The target language, as a code or name
+    #[arg(
+        index = 2,
+        value_parser = LanguageCodeParser::target(),
+        hide_possible_values = true
+    )]
+    pub language_to: String,
+}
+
+#[derive(clap::Args)]
+pub struct InspectArgs {
+    /// Don't ask the LibreTranslate instance to detect the file's language
+    #[arg(long)]
+    pub no_detect: bool,
+
+    /// The subtitle file to inspect
+    #[arg(index = 1)]
+    pub file: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct ValidateArgs {
+    /// The longest a line may be, in characters [default: 42]
+    #[arg(long)]
+    pub max_line_length: Option<usize>,
+
+    /// The fastest a cue may need to be read, in characters per second
+    /// [default: 21]
+    #[arg(long)]
+    pub max_cps: Option<f64>,
+
+    /// Check against a style guide's limits on line length, lines, reading
+    /// speed, duration and gaps between cues. Any limit given with its own
+    /// option is used instead
+    #[arg(long, value_enum, value_name = "GUIDE")]
+    pub profile_rules: Option<Guidelines>,
+
+    /// How many problems are tolerated before validation fails
+    #[arg(long, default_value_t = 0)]
+    pub max_violations: usize,
+
+    /// The subtitle file to validate
+    #[arg(index = 1)]
+    pub file: PathBuf,
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Show how many translations are cached and how often they are used
+    Stats,
+
+    /// Remove cached translations
+    Clear {
+        /// Only remove those which haven't been used for this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
+
+    /// Write the cache to a file, to move it to another machine
+    Export {
+        /// The JSON file to write
+        file: PathBuf,
+    },
+
+    /// Add the translations in an exported cache to this one
+    Import {
+        /// The JSON file to read
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HistoryCommand {
+    /// List the most recent translations, numbered
+    List {
+        /// How many to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Show the details of a translation, by its number in the list
+    Show {
+        /// The translation's number
+        id: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GlossaryCommand {
+    /// List the glossaries, or the terms in one
+    List {
+        /// The glossary to list the terms of
+        name: Option<String>,
+    },
+
+    /// Add a term to a glossary, creating the glossary if needed, or change
+    /// the term's translation
+    Add {
+        /// The glossary to add to
+        name: String,
+        /// The term in the source language
+        source: String,
+        /// The translation the term must be given
+        target: String,
+    },
+
+    /// Remove a term from a glossary
+    Remove {
+        /// The glossary to remove from
+        name: String,
+        /// The term in the source language
+        source: String,
+    },
+
+    /// Add the terms in a CSV, TSV or TBX file to a glossary
+    Import {
+        /// The glossary to add to
+        name: String,
+        /// The file to read, with source and target columns after a header
+        /// row if it is CSV or TSV
+        file: PathBuf,
+        #[command(flatten)]
+        languages: TermLanguages,
+    },
+
+    /// Write a glossary to a CSV, TSV or TBX file
+    Export {
+        /// The glossary to write
+        name: String,
+        /// The file to write
+        file: PathBuf,
+        #[command(flatten)]
+        languages: TermLanguages,
+    },
+}
+
+/// Which languages a glossary's terms are in, for TBX files, which hold terms
+/// in many languages.
+#[derive(clap::Args)]
+pub struct TermLanguages {
+    /// The language of the source terms
+    #[arg(
+        short = 'f',
+        long = "from",
+        default_value = "en",
+        value_parser = LanguageCodeParser::target(),
+        hide_possible_values = true
+    )]
+    pub from: String,
+
+    /// The language of the translations. When importing, it defaults to the
+    /// first other language of each term
+    #[arg(
+        short = 't',
+        long = "to",
+        value_parser = LanguageCodeParser::target(),
+        hide_possible_values = true
+    )]
+    pub to: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct DoctorArgs {
+    /// A source language to check the instance supports
+    #[arg(
+        short = 'f',
+        long,
+        default_value = "auto",
+        value_parser = LanguageCodeParser::source(),
+        hide_possible_values = true
+    )]
+    pub language_from: String,
+
+    /// A target language to check the instance supports
+    #[arg(short = 't', long, value_parser = LanguageCodeParser::target(), hide_possible_values = true)]
+    pub language_to: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct SelfUpdateArgs {
+    /// Only check whether an update is available, without installing it
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ConvertArgs {
+    /// Replace the output file if it already exists
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// The subtitle file to convert
+    #[arg(index = 1)]
+    pub input: PathBuf,
+
+    /// The file to write, in the format given by its extension
+    #[arg(index = 2)]
+    pub output: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct DiffArgs {
+    /// Only compare the cues' text, not their timing
+    #[arg(long)]
+    pub ignore_timing: bool,
+
+    /// The first subtitle file
+    #[arg(index = 1)]
+    pub left: PathBuf,
+
+    /// The second subtitle file, shown as changes to the first
+    #[arg(index = 2)]
+    pub right: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct MergeArgs {
+    /// Replace the output file if it already exists
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// The file to write, in the format given by its extension
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// The subtitle file whose text is shown first, and whose timing is kept
+    /// where cues are combined
+    #[arg(index = 1)]
+    pub top: PathBuf,
+
+    /// The subtitle file whose text is shown below
+    #[arg(index = 2)]
+    pub bottom: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct ExportCsvArgs {
+    /// Replace the output file if it already exists
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// The file to write, as TSV if its extension is `.tsv` and CSV otherwise
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// The original subtitle file
+    #[arg(index = 1)]
+    pub source: PathBuf,
+
+    /// Its translation, whose cues are paired with the original's by when
+    /// they are shown
+    #[arg(index = 2)]
+    pub translation: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct ImportCsvArgs {
+    /// Replace the output file if it already exists
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// The subtitle file to write, in the format given by its extension
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// The CSV or TSV file, as written by `export-csv`
+    #[arg(index = 1)]
+    pub sheet: PathBuf,
+}
+
+#[derive(Subcommand)]
+pub enum TimingCommand {
+    /// Move every cue later by an amount of time, or earlier if it is
+    /// negative, such as `+2.3s` or `-500ms`
+    Shift {
+        /// How far to move the cues, such as `+2.3s`, `-500ms` or `1m`, or a
+        /// number of milliseconds
+        #[arg(allow_hyphen_values = true, value_name = "OFFSET", value_parser = parse_offset)]
+        offset_ms: i64,
+        #[command(flatten)]
+        files: TimingFiles,
+    },
+
+    /// Stretch the timing by a factor, given as a number or a ratio of frame
+    /// rates such as `25/23.976`
+    Scale {
+        /// The factor, such as `1.04` or `25/23.976`
+        #[arg(value_parser = parse_factor)]
+        factor: f64,
+        #[command(flatten)]
+        files: TimingFiles,
+    },
+
+    /// Show each cue for as long as reading it takes, without running into
+    /// the next cue
+    Retime {
+        /// The reading speed, in characters per second
+        #[arg(long, default_value_t = 17.0, value_parser = parse_cps)]
+        cps: f64,
+        /// The shortest time to show a cue for, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        min_duration: u32,
+        #[command(flatten)]
+        files: TimingFiles,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct SplitArgs {
+    /// Replace the part files if they already exist
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// A time to cut at, such as `00:45:00`. Can be given more than once
+    #[arg(long, required = true, value_name = "TIME", value_parser = parse_time)]
+    pub at: Vec<i64>,
+
+    /// The subtitle file to cut
+    #[arg(index = 1)]
+    pub file: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct ConcatArgs {
+    /// Replace the output file if it already exists
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// When the second part starts, such as `00:45:00`. Give it again for
+    /// each later part, in order. Parts without one start straight after the
+    /// last cue of the part before
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub offset: Vec<i64>,
+
+    /// The file to write, in the format given by its extension
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// The subtitle files for each part, in order
+    #[arg(required = true, num_args = 2..)]
+    pub files: Vec<PathBuf>,
+}
+
+/// The files timing is changed in.
+#[derive(clap::Args)]
+pub struct TimingFiles {
+    /// Replace the output file if it already exists, or the input file if no
+    /// output file is given
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// The file to write, in the format given by its extension
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// The subtitle file to change
+    pub input: PathBuf,
+}
+
+/// Parse an amount of time to shift by, such as `+2.3s`, `-500ms`, `1m` or
+/// `1500`, which is in milliseconds.
+fn parse_offset(value: &str) -> Result<i64, String> {
+    let error = || format!("'{value}' isn't an amount of time, such as +2.3s or -500ms");
+    let trimmed = value.trim();
+    let (sign, rest) = match trimmed.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (number, scale) = [
+        ("ms", 1.0),
+        ("s", 1000.0),
+        ("m", 60_000.0),
+        ("h", 3_600_000.0),
+    ]
+    .into_iter()
+    .find_map(|(unit, scale)| Some((rest.strip_suffix(unit)?, scale)))
+    .unwrap_or((rest, 1.0));
+    let number: f64 = number.trim().parse().map_err(|_| error())?;
+    if !number.is_finite() {
+        return Err(error());
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    Ok((sign * number * scale).round() as i64)
+}
+
+/// Parse a time in a video, `hh:mm:ss,mmm`, into milliseconds.
+fn parse_time(value: &str) -> Result<i64, String> {
+    subtitle::parse_timestamp(value)
+        .ok_or_else(|| format!("'{value}' isn't a time, such as 00:45:00 or 00:45:00,500"))
+}
+
+fn parse_cps(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(cps) if cps.is_finite() && cps > 0.0 => Ok(cps),
+        _ => Err(format!(
+            "'{value}' isn't a positive number of characters per second"
+        )),
+    }
+}
+
+/// Parse a factor to stretch timing by, either a number or a ratio such as
+/// `25/23.976`.
+fn parse_factor(value: &str) -> Result<f64, String> {
+    let error = || format!("'{value}' isn't a number or ratio, such as 25/23.976");
+    let factor = match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator: f64 = numerator.trim().parse().map_err(|_| error())?;
+            let denominator: f64 = denominator.trim().parse().map_err(|_| error())?;
+            numerator / denominator
+        }
+        None => value.trim().parse().map_err(|_| error())?,
+    };
+    if factor.is_finite() && factor > 0.0 {
+        Ok(factor)
+    } else {
+        Err(error())
+    }
+}
+
+/// Parses a language code, name or tag into the code LibreTranslate uses,
+/// offering the known codes for shell completion.
+#[derive(Clone)]
+pub struct LanguageCodeParser {
+    allow_auto: bool,
+}
+
+impl LanguageCodeParser {
+    /// A parser for source languages, which may also be `auto`.
+    pub fn source() -> Self {
+        Self { allow_auto: true }
+    }
+
+    /// A parser for target languages.
+    pub fn target() -> Self {
+        Self { allow_auto: false }
+    }
+}
+
+impl TypedValueParser for LanguageCodeParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value.to_string_lossy();
+        if self.allow_auto && value.eq_ignore_ascii_case("auto") {
+            return Ok("auto".to_string());
+        }
+
+        let resolved = if self.allow_auto {
+            resolve(&value)
+        } else {
+            resolve_target(&value)
+        };
+        resolved.map_err(|suggestions| {
+            let arg = arg.map_or_else(|| "...".to_string(), ToString::to_string);
+            let mut message = format!("unknown language '{value}' for '{arg}'");
+            if !suggestions.is_empty() {
+                let suggestions: Vec<_> = suggestions
+                    .iter()
+                    .map(|l| format!("{} ({})", l.code, l.name))
+                    .collect();
+                let _ = write!(
+                    message,
+                    "\n\n  tip: did you mean {}?",
+                    suggestions.join(", ")
+                );
+            }
+            message.push('\n');
+            clap::Error::raw(ErrorKind::InvalidValue, message).with_cmd(cmd)
+        })
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let auto = self
+            .allow_auto
+            .then(|| PossibleValue::new("auto").help("Detect the language"));
+        Some(Box::new(
+            auto.into_iter().chain(
+                KNOWN_LANGUAGES
+                    .iter()
+                    .map(|l| PossibleValue::new(l.code).help(l.name)),
+            ),
+        ))
+    }
+}
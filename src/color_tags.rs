@@ -0,0 +1,32 @@
+//! Keeping font colours, which often tell speakers apart, out of the text
+//! engines are sent, so they aren't mangled, and putting them back around the
+//! translations.
+
+use std::collections::HashMap;
+
+use subtitle_translate::colors;
+
+use crate::hooks::Hook;
+
+/// Removes the colours of lines which are all one colour before they are
+/// translated, and puts them back afterwards. WebVTT colour classes are
+/// written as `<font color>` tags, as translations are written as SRT.
+#[derive(Default)]
+pub struct ColorTags {
+    /// The colour of each line of each cue, by cue, until their translations
+    /// are seen.
+    removed: HashMap<usize, Vec<Option<String>>>,
+}
+
+impl Hook for ColorTags {
+    fn pre_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let (text, colors) = colors::strip_colors(&colors::from_webvtt(line));
+        self.removed.insert(cue, colors);
+        Ok(text)
+    }
+
+    fn post_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let colors = self.removed.remove(&cue).unwrap_or_default();
+        Ok(colors::restore_colors(line, &colors))
+    }
+}
@@ -0,0 +1,322 @@
+//! Font colours, which often tell speakers apart, kept out of the text engines
+//! are sent and written as each format writes them: `<font color>` tags in
+//! SRT, `\c` override tags in ASS and SSA, and colour classes in WebVTT.
+
+/// Colours by name, as `<font color>` tags may give them. The first eight
+/// are also the colour classes WebVTT players define.
+const NAMED: &[(&str, [u8; 3])] = &[
+    ("white", [0xff, 0xff, 0xff]),
+    ("lime", [0x00, 0xff, 0x00]),
+    ("cyan", [0x00, 0xff, 0xff]),
+    ("red", [0xff, 0x00, 0x00]),
+    ("yellow", [0xff, 0xff, 0x00]),
+    ("magenta", [0xff, 0x00, 0xff]),
+    ("blue", [0x00, 0x00, 0xff]),
+    ("black", [0x00, 0x00, 0x00]),
+    ("green", [0x00, 0x80, 0x00]),
+    ("gray", [0x80, 0x80, 0x80]),
+    ("grey", [0x80, 0x80, 0x80]),
+    ("silver", [0xc0, 0xc0, 0xc0]),
+    ("orange", [0xff, 0xa5, 0x00]),
+    ("purple", [0x80, 0x00, 0x80]),
+    ("maroon", [0x80, 0x00, 0x00]),
+    ("navy", [0x00, 0x00, 0x80]),
+    ("olive", [0x80, 0x80, 0x00]),
+    ("teal", [0x00, 0x80, 0x80]),
+    ("fuchsia", [0xff, 0x00, 0xff]),
+    ("aqua", [0x00, 0xff, 0xff]),
+];
+
+/// The number of colour classes WebVTT players define.
+const WEBVTT_CLASSES: usize = 8;
+
+/// Parse a colour as `<font color>` tags give it, such as `#FF0000`, `#F00`
+/// or `red`.
+#[must_use]
+pub fn parse(color: &str) -> Option<[u8; 3]> {
+    let color = color.trim().trim_matches(['"', '\'']);
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+    match hex.len() {
+        6 if hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Some([channel(0)?, channel(2)?, channel(4)?])
+        }
+        3 if hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+            let mut rgb = [0; 3];
+            for (channel, c) in rgb.iter_mut().zip(hex.chars()) {
+                *channel = u8::from_str_radix(&format!("{c}{c}"), 16).ok()?;
+            }
+            Some(rgb)
+        }
+        _ => NAMED
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(color))
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+/// Parse a colour as ASS and SSA give it, such as `&H0000FF&` for red, which
+/// is written blue, green, red, optionally after an alpha channel.
+///
+/// ```
+/// use subtitle_translate::colors::parse_ass;
+///
+/// assert_eq!(parse_ass("&H0000FF&"), Some([0xff, 0, 0]));
+/// assert_eq!(parse_ass("&H00FFFF00"), Some([0, 0xff, 0xff]));
+/// ```
+#[must_use]
+pub fn parse_ass(color: &str) -> Option<[u8; 3]> {
+    let color = color.trim().trim_end_matches('&');
+    let hex = color
+        .strip_prefix("&H")
+        .or_else(|| color.strip_prefix("&h"))
+        .unwrap_or(color);
+    if hex.is_empty() || hex.len() > 8 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let [_, blue, green, red] = value.to_be_bytes();
+    Some([red, green, blue])
+}
+
+/// A colour as `<font color>` tags give it, such as `#FF0000`.
+#[must_use]
+pub fn to_hex([red, green, blue]: [u8; 3]) -> String {
+    format!("#{red:02X}{green:02X}{blue:02X}")
+}
+
+/// Remove the colour of each line of a cue's text which is all one colour, so
+/// the tags aren't translated, returning the text and the opening tag taken
+/// from each line.
+///
+/// ```
+/// use subtitle_translate::colors::strip_colors;
+///
+/// let (text, colors) = strip_colors("<font color=\"#FFFF00\">Run!</font>\nWhy?");
+/// assert_eq!(text, "Run!\nWhy?");
+/// assert_eq!(colors, [Some("<font color=\"#FFFF00\">".to_string()), None]);
+///
+/// let (text, colors) = strip_colors("Привет > мир");
+/// assert_eq!(text, "Привет > мир");
+/// assert_eq!(colors, [None]);
+/// ```
+#[must_use]
+pub fn strip_colors(text: &str) -> (String, Vec<Option<String>>) {
+    let mut colors = Vec::new();
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+            let inner = font_tag_len(trimmed).and_then(|len| {
+                let inner = trimmed[len..].strip_suffix("</font>")?;
+                // The line must be one span, not several.
+                (!inner.contains("<font")).then_some((len, inner))
+            });
+            if let Some((len, inner)) = inner {
+                colors.push(Some(trimmed[..len].to_string()));
+                inner.to_string()
+            } else {
+                colors.push(None);
+                line.to_string()
+            }
+        })
+        .collect();
+    (lines.join("\n"), colors)
+}
+
+/// Put the colours taken by [`strip_colors`] back around each line of the
+/// translated text. If the translation has a different number of lines, each
+/// of its lines is given the first colour.
+///
+/// ```
+/// use subtitle_translate::colors::restore_colors;
+///
+/// let colors = [Some("<font color=\"#FFFF00\">".to_string()), None];
+/// assert_eq!(restore_colors("Cours !\nPourquoi ?", &colors), "<font color=\"#FFFF00\">Cours !</font>\nPourquoi ?");
+/// assert_eq!(restore_colors("Cours !", &colors), "<font color=\"#FFFF00\">Cours !</font>");
+/// ```
+#[must_use]
+pub fn restore_colors(text: &str, colors: &[Option<String>]) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let first = colors.iter().flatten().next();
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            let color = if lines.len() == colors.len() {
+                colors[idx].as_ref()
+            } else {
+                first
+            };
+            match color {
+                Some(tag) if !line.trim().is_empty() => format!("{tag}{line}</font>"),
+                _ => (*line).to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Write `<font color>` tags as ASS and SSA colour override tags. Other font
+/// attributes are dropped.
+///
+/// ```
+/// use subtitle_translate::colors::to_ass;
+///
+/// assert_eq!(to_ass("<font color=\"#FF8000\">Hey</font> you"), r"{\c&H0080FF&}Hey{\c} you");
+/// assert_eq!(to_ass("<Привет> мир"), "<Привет> мир");
+/// ```
+#[must_use]
+pub fn to_ass(text: &str) -> String {
+    map_font_tags(
+        text,
+        |color| match color {
+            Some([red, green, blue]) => format!("{{\\c&H{blue:02X}{green:02X}{red:02X}&}}"),
+            None => String::new(),
+        },
+        |color| match color {
+            Some(_) => "{\\c}".to_string(),
+            None => String::new(),
+        },
+    )
+}
+
+/// Write `<font color>` tags as the WebVTT colour classes, such as
+/// `<c.yellow>`, nearest their colours, which players define without a style
+/// sheet.
+///
+/// ```
+/// use subtitle_translate::colors::to_webvtt;
+///
+/// assert_eq!(to_webvtt("<font color=\"#FFEE10\">Hey</font>"), "<c.yellow>Hey</c>");
+/// ```
+#[must_use]
+pub fn to_webvtt(text: &str) -> String {
+    map_font_tags(
+        text,
+        |color| match color {
+            Some(rgb) => format!("<c.{}>", nearest_class(rgb)),
+            None => "<c>".to_string(),
+        },
+        |_| "</c>".to_string(),
+    )
+}
+
+/// Write WebVTT colour classes, such as `<c.yellow>`, as `<font color>` tags.
+/// Other classes are dropped.
+///
+/// ```
+/// use subtitle_translate::colors::from_webvtt;
+///
+/// assert_eq!(from_webvtt("<c.yellow.loud>Hey</c> <c.big>you</c>"), "<font color=\"#FFFF00\">Hey</font> you");
+/// ```
+#[must_use]
+pub fn from_webvtt(text: &str) -> String {
+    let mut mapped = String::with_capacity(text.len());
+    // Whether each open class span had a colour.
+    let mut open: Vec<bool> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        mapped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..end];
+        if tag == "c" || tag.starts_with("c.") {
+            let color = tag.split('.').skip(1).find_map(|class| {
+                NAMED[..WEBVTT_CLASSES]
+                    .iter()
+                    .find(|(name, _)| *name == class)
+            });
+            if let Some((_, rgb)) = color {
+                mapped.push_str("<font color=\"");
+                mapped.push_str(&to_hex(*rgb));
+                mapped.push_str("\">");
+            }
+            open.push(color.is_some());
+        } else if tag == "/c" {
+            if open.pop().unwrap_or(false) {
+                mapped.push_str("</font>");
+            }
+        } else {
+            mapped.push_str(&rest[..=end]);
+        }
+        rest = &rest[end + 1..];
+    }
+    mapped.push_str(rest);
+    mapped
+}
+
+/// Replace each `<font>` tag with what `open` makes of its colour, if it has
+/// one, and each `</font>` with what `close` makes of the colour it closes.
+fn map_font_tags(
+    text: &str,
+    open: impl Fn(Option<[u8; 3]>) -> String,
+    close: impl Fn(Option<[u8; 3]>) -> String,
+) -> String {
+    let mut mapped = String::with_capacity(text.len());
+    let mut colors: Vec<Option<[u8; 3]>> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        mapped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(len) = font_tag_len(rest) {
+            let color = font_color(&rest[..len]);
+            mapped.push_str(&open(color));
+            colors.push(color);
+            rest = &rest[len..];
+        } else if rest
+            .get(..7)
+            .is_some_and(|tag| tag.eq_ignore_ascii_case("</font>"))
+        {
+            mapped.push_str(&close(colors.pop().flatten()));
+            rest = &rest[7..];
+        } else {
+            mapped.push('<');
+            rest = &rest[1..];
+        }
+    }
+    mapped.push_str(rest);
+    mapped
+}
+
+/// The length of the `<font>` tag at the start of `text`, if there is one.
+fn font_tag_len(text: &str) -> Option<usize> {
+    let end = text.find('>')?;
+    let tag = &text[..=end];
+    let is_font = tag.len() > 5
+        && tag
+            .get(..5)
+            .is_some_and(|start| start.eq_ignore_ascii_case("<font"));
+    (is_font && !tag[1..].contains('<')).then_some(end + 1)
+}
+
+/// The colour of a `<font>` tag, if it gives one.
+fn font_color(tag: &str) -> Option<[u8; 3]> {
+    let lower = tag.to_ascii_lowercase();
+    let start = lower.find("color=")? + "color=".len();
+    let value = &tag[start..tag.len() - 1];
+    let value = if let Some(quoted) = value.strip_prefix('"') {
+        quoted.split('"').next()?
+    } else if let Some(quoted) = value.strip_prefix('\'') {
+        quoted.split('\'').next()?
+    } else {
+        value.split_whitespace().next()?
+    };
+    parse(value)
+}
+
+/// The name of the WebVTT colour class nearest a colour.
+fn nearest_class(rgb: [u8; 3]) -> &'static str {
+    let distance = |other: [u8; 3]| -> u32 {
+        rgb.iter()
+            .zip(other)
+            .map(|(a, b)| u32::from(a.abs_diff(b)).pow(2))
+            .sum()
+    };
+    NAMED[..WEBVTT_CLASSES]
+        .iter()
+        .min_by_key(|(_, other)| distance(*other))
+        .map_or("white", |(name, _)| name)
+}
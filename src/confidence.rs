@@ -0,0 +1,100 @@
+//! Estimating how confident to be in a translation, so editors can find the
+//! translations most likely to be wrong.
+
+use crate::{stats, subtitle::visible_text};
+
+/// The range of lengths, relative to the source, translations are expected to
+/// be in.
+pub const LENGTH_RATIO: std::ops::RangeInclusive<f64> = 0.4..=2.5;
+
+/// How much to lower the score of a translation identical to its source.
+const UNTRANSLATED: f64 = 50.0;
+/// How much to lower the score of a translation much shorter or longer than
+/// its source.
+const LENGTH: f64 = 30.0;
+/// How much to lower the score of a translation repeating a word over and
+/// over, as engines sometimes do.
+const REPEATED: f64 = 30.0;
+/// How much to lower the score of a translation whose numbers differ from its
+/// source's.
+const NUMBERS: f64 = 20.0;
+
+/// Score how confident to be in a translation, from 0 to 100, starting from
+/// how confident the engine was of the source language, if it detected it,
+/// and lowering it for each sign of a bad translation: being untranslated,
+/// being much shorter or longer than the source, repeating a word, and
+/// changing the numbers.
+///
+/// ```
+/// use subtitle_translate::confidence::score;
+///
+/// assert_eq!(score("Where are you going?", "Wohin gehst du?", None), 100.0);
+/// assert_eq!(score("Where are you going?", "Where are you going?", Some(90.0)), 40.0);
+/// assert!(score("I have 3 cats.", "Ich habe Katzen Katzen Katzen.", None) < 60.0);
+/// ```
+#[must_use]
+pub fn score(source: &str, translation: &str, detection_confidence: Option<f64>) -> f64 {
+    let source = visible_text(source);
+    let translation = visible_text(translation);
+    if translation.trim().is_empty() {
+        return 0.0;
+    }
+    let mut score = detection_confidence.unwrap_or(100.0).clamp(0.0, 100.0);
+
+    if is_untranslated(&source, &translation) {
+        score -= UNTRANSLATED;
+    }
+    if length_ratio(&source, &translation).is_some_and(|ratio| !LENGTH_RATIO.contains(&ratio)) {
+        score -= LENGTH;
+    }
+    if repeats_word(&translation) {
+        score -= REPEATED;
+    }
+    if digits(&source) != digits(&translation) {
+        score -= NUMBERS;
+    }
+    score.max(0.0)
+}
+
+/// Whether a translation is the same as its source, ignoring case and
+/// spacing, though it has words to translate.
+#[must_use]
+pub fn is_untranslated(source: &str, translation: &str) -> bool {
+    let (source, translation) = (visible_text(source), visible_text(translation));
+    translation.chars().any(char::is_alphabetic) && normalise(&source) == normalise(&translation)
+}
+
+/// How many times as long as its source a translation is, if the source is
+/// long enough for that to mean anything.
+#[must_use]
+pub fn length_ratio(source: &str, translation: &str) -> Option<f64> {
+    let source_length = stats::characters(source);
+    #[allow(clippy::cast_precision_loss)]
+    (source_length >= 10).then(|| stats::characters(translation) as f64 / source_length as f64)
+}
+
+/// Text in lowercase with its words separated by single spaces.
+fn normalise(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether the same word appears three or more times in a row.
+fn repeats_word(text: &str) -> bool {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.windows(3).any(|w| w[0] == w[1] && w[1] == w[2])
+}
+
+/// The digits in some text, ignoring how the numbers are separated.
+fn digits(text: &str) -> String {
+    text.chars().filter(char::is_ascii_digit).collect()
+}
@@ -0,0 +1,335 @@
+//! Settings files, which give defaults for translating.
+//!
+//! `config.toml` in the configuration directory applies to every file. A
+//! `.subtitle-translate.toml` in the source file's folder, or any folder above
+//! it, is merged over that, the nearest taking precedence, so each project can
+//! carry its own defaults. A `glossary.csv` in any of those folders is used as
+//! a glossary too. Settings given on the command line take precedence over
+//! all of them.
+//!
+//! Settings files can also hold profiles, which bundle settings for a
+//! recurring kind of job and are chosen with `--profile`, such as:
+//!
+//! Several LibreTranslate instances can be given as a list, to share requests
+//! between them:
+//!
+//! ```toml
+//! libretranslate_instance = ["http://gpu-1:5000/translate", "http://gpu-2:5000/translate"]
+//! balance = "least-in-flight"
+//! ```
+//!
+//! ```toml
+//! [profile.anime-de]
+//! language_to = "de"
+//! glossary = ["anime"]
+//! preserve_honorifics = true
+//! output = "{name}.{lang}.srt"
+//! ```
+//!
+//! A profile's settings are merged over all the others, but not over those
+//! given on the command line.
+//!
+//! Formatting can be set for each target language, and is used for whichever
+//! the translation is into:
+//!
+//! ```toml
+//! [lang.de]
+//! max_line_length = 42
+//! hyphenation = "hyph-de-1996.pat.txt"
+//!
+//! [lang.fr]
+//! space_punctuation = true
+//! smart_quotes = true
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+use subtitle_translate::{languages, libretranslate::Balancing, translator::Formality};
+
+use crate::{
+    cli::{CHUNK_SIZE, EngineArgs, LIBRETRANSLATE_INSTANCE, RubyPolicy, TranslateArgs},
+    dirs,
+};
+
+/// The name of a project's settings file.
+const PROJECT_FILE: &str = ".subtitle-translate.toml";
+/// The name of a project's glossary.
+const PROJECT_GLOSSARY: &str = "glossary.csv";
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    libretranslate_instance: Option<Instances>,
+    balance: Option<Balancing>,
+    libretranslate_apikey: Option<String>,
+    language_from: Option<String>,
+    language_to: Option<String>,
+    chunk_size: Option<usize>,
+    overwrite: Option<bool>,
+    backup: Option<bool>,
+    /// Names of glossaries, or paths to glossary files relative to the
+    /// settings file.
+    #[serde(default)]
+    glossary: Vec<String>,
+    preserve_honorifics: Option<bool>,
+    fix_case: Option<bool>,
+    localize_numbers: Option<bool>,
+    min_gap: Option<u32>,
+    ruby: Option<RubyPolicy>,
+    formality: Option<Formality>,
+    /// The template destinations are named from, as for `--output-template`.
+    output: Option<String>,
+    /// Profiles, by name.
+    #[serde(default)]
+    profile: BTreeMap<String, Config>,
+    /// Formatting for translations into each language, by code.
+    #[serde(default)]
+    lang: BTreeMap<String, Formatting>,
+}
+
+/// A LibreTranslate instance, or several to share requests between.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Instances {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Formatting for translations into a language.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Formatting {
+    max_line_length: Option<usize>,
+    /// A hyphenation pattern file, relative to the settings file.
+    hyphenation: Option<PathBuf>,
+    max_cps: Option<f64>,
+    space_punctuation: Option<bool>,
+    smart_quotes: Option<bool>,
+}
+
+/// The formatting for each target language.
+pub struct Languages(BTreeMap<String, Formatting>);
+
+impl Config {
+    /// The settings for translating a source file: the global settings, with
+    /// those of each project folder above the file merged over them.
+    pub fn for_source(source: &Path) -> anyhow::Result<Self> {
+        let mut config = match dirs::config() {
+            Some(dir) => Self::read(&dir.join("config.toml"))?.unwrap_or_default(),
+            None => Self::default(),
+        };
+        let source = std::path::absolute(source).context("Failed to resolve the source file")?;
+        let folders: Vec<_> = source.ancestors().skip(1).collect();
+        for folder in folders.into_iter().rev() {
+            let mut project = Self::read(&folder.join(PROJECT_FILE))?.unwrap_or_default();
+            let glossary = folder.join(PROJECT_GLOSSARY);
+            if glossary.is_file() {
+                tracing::debug!("Using glossary {}", glossary.display());
+                project.glossary.push(glossary.display().to_string());
+            }
+            config = project.over(config);
+        }
+        Ok(config)
+    }
+
+    /// Read a settings file, if it exists.
+    fn read(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        tracing::debug!("Using settings from {}", path.display());
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config: Self = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        config.resolve_paths(base);
+        for profile in config.profile.values_mut() {
+            profile.resolve_paths(base);
+        }
+        Ok(Some(config))
+    }
+
+    /// Make the paths of glossary and hyphenation files relative to the
+    /// settings file's folder, `base`.
+    fn resolve_paths(&mut self, base: &Path) {
+        for glossary in &mut self.glossary {
+            // Named glossaries have no extension, but files always do.
+            if Path::new(glossary).extension().is_some() {
+                *glossary = base.join(&*glossary).display().to_string();
+            }
+        }
+        for formatting in self.lang.values_mut() {
+            if let Some(path) = &mut formatting.hyphenation {
+                *path = base.join(&*path);
+            }
+        }
+    }
+
+    /// Use these settings, falling back to the base settings for any not
+    /// given. Glossaries from both are used, these first, and profiles from
+    /// both, these replacing any of the same name.
+    fn over(mut self, base: Self) -> Self {
+        self.glossary.extend(base.glossary);
+        let mut profile = base.profile;
+        profile.extend(self.profile);
+        let mut lang = base.lang;
+        for (language, formatting) in self.lang {
+            let base = lang.remove(&language).unwrap_or_default();
+            lang.insert(language, formatting.over(base));
+        }
+        Self {
+            libretranslate_instance: self
+                .libretranslate_instance
+                .or(base.libretranslate_instance),
+            balance: self.balance.or(base.balance),
+            libretranslate_apikey: self.libretranslate_apikey.or(base.libretranslate_apikey),
+            language_from: self.language_from.or(base.language_from),
+            language_to: self.language_to.or(base.language_to),
+            chunk_size: self.chunk_size.or(base.chunk_size),
+            overwrite: self.overwrite.or(base.overwrite),
+            backup: self.backup.or(base.backup),
+            glossary: self.glossary,
+            preserve_honorifics: self.preserve_honorifics.or(base.preserve_honorifics),
+            fix_case: self.fix_case.or(base.fix_case),
+            localize_numbers: self.localize_numbers.or(base.localize_numbers),
+            min_gap: self.min_gap.or(base.min_gap),
+            ruby: self.ruby.or(base.ruby),
+            formality: self.formality.or(base.formality),
+            output: self.output.or(base.output),
+            profile,
+            lang,
+        }
+    }
+
+    /// Fill in the arguments which weren't given on the command line, using
+    /// the profile they name, if any. The formatting for each language is
+    /// returned, to be applied once the target language is known.
+    pub fn apply(
+        mut self,
+        args: &mut TranslateArgs,
+        engine: &mut EngineArgs,
+    ) -> anyhow::Result<Languages> {
+        // Before the settings, which the command line's style guide is used
+        // over.
+        args.apply_profile_rules();
+        if let Some(name) = &args.profile {
+            let profile = self
+                .profile
+                .remove(name)
+                .with_context(|| format!("There is no profile named '{name}' in settings"))?;
+            tracing::debug!("Using profile {name}");
+            self = profile.over(self);
+        }
+        if engine.libretranslate_instances == [LIBRETRANSLATE_INSTANCE]
+            && let Some(instances) = self.libretranslate_instance
+        {
+            engine.libretranslate_instances = match instances {
+                Instances::One(instance) => vec![instance],
+                Instances::Many(instances) if instances.is_empty() => {
+                    anyhow::bail!("The libretranslate_instance setting lists no instances")
+                }
+                Instances::Many(instances) => instances,
+            };
+        }
+        if engine.balance == Balancing::default()
+            && let Some(balance) = self.balance
+        {
+            engine.balance = balance;
+        }
+        if engine.libretranslate_apikey.is_none() {
+            engine.libretranslate_apikey = self.libretranslate_apikey;
+        }
+        if args.language_from == "auto"
+            && let Some(language) = self.language_from
+            && !language.eq_ignore_ascii_case("auto")
+        {
+            args.language_from = resolve(&language)?;
+        }
+        if args.language_to.is_none()
+            && let Some(language) = self.language_to
+        {
+            args.language_to = Some(
+                languages::resolve_target(&language)
+                    .map_err(|_| anyhow::anyhow!("Unknown language '{language}' in settings"))?,
+            );
+        }
+        if args.chunk_size == CHUNK_SIZE
+            && let Some(chunk_size) = self.chunk_size
+        {
+            anyhow::ensure!(
+                chunk_size > 0,
+                "The chunk size in settings must be positive"
+            );
+            args.chunk_size = chunk_size;
+        }
+        if !(args.overwrite || args.no_clobber || args.backup) {
+            args.overwrite = self.overwrite.unwrap_or(false);
+            args.backup = self.backup.unwrap_or(false) && !args.overwrite;
+        }
+        args.glossary.extend(self.glossary);
+        args.preserve_honorifics |= self.preserve_honorifics.unwrap_or(false);
+        args.fix_case |= self.fix_case.unwrap_or(false);
+        args.localize_numbers |= self.localize_numbers.unwrap_or(false);
+        args.min_gap = args.min_gap.or(self.min_gap);
+        if matches!(args.ruby, RubyPolicy::Strip)
+            && let Some(ruby) = self.ruby
+        {
+            args.ruby = ruby;
+        }
+        args.formality = args.formality.or(self.formality);
+        if args.destination_file.is_none() && args.output_template.is_none() {
+            args.output_template = self.output;
+        }
+        Ok(Languages(self.lang))
+    }
+}
+
+impl Formatting {
+    /// Use this formatting, falling back to the base formatting for any not
+    /// given.
+    fn over(self, base: Self) -> Self {
+        Self {
+            max_line_length: self.max_line_length.or(base.max_line_length),
+            hyphenation: self.hyphenation.or(base.hyphenation),
+            max_cps: self.max_cps.or(base.max_cps),
+            space_punctuation: self.space_punctuation.or(base.space_punctuation),
+            smart_quotes: self.smart_quotes.or(base.smart_quotes),
+        }
+    }
+}
+
+impl Languages {
+    /// Fill in the formatting arguments which weren't given on the command
+    /// line with the formatting for a target language, or failing that, for
+    /// the language without its region, such as `pt` for `pt-br`.
+    pub fn apply(mut self, language: &str, args: &mut TranslateArgs) {
+        let base = language.split(['-', '_']).next().unwrap_or_default();
+        let Some(formatting) = self
+            .0
+            .keys()
+            .find(|code| code.eq_ignore_ascii_case(language))
+            .or_else(|| self.0.keys().find(|code| code.eq_ignore_ascii_case(base)))
+            .cloned()
+            .and_then(|code| self.0.remove(&code))
+        else {
+            return;
+        };
+        args.max_line_length = args.max_line_length.or(formatting.max_line_length);
+        args.hyphenation = args.hyphenation.take().or(formatting.hyphenation);
+        args.max_cps = args.max_cps.or(formatting.max_cps);
+        args.space_punctuation |= formatting.space_punctuation.unwrap_or(false);
+        args.smart_quotes |= formatting.smart_quotes.unwrap_or(false);
+    }
+}
+
+/// Resolve a language given in a settings file.
+fn resolve(language: &str) -> anyhow::Result<String> {
+    languages::resolve(language)
+        .map_err(|_| anyhow::anyhow!("Unknown language '{language}' in settings"))
+}
@@ -0,0 +1,31 @@
+//! Keeping MicroDVD control codes, such as `{y:i}`, out of the text engines
+//! are sent, and writing them as tags in translations, as those are written
+//! as SRT.
+
+use std::collections::HashMap;
+
+use subtitle_translate::microdvd;
+
+use crate::hooks::Hook;
+
+/// Removes control codes before lines are translated, and puts them back as
+/// tags afterwards.
+#[derive(Default)]
+pub struct ControlCodes {
+    /// The codes removed from each line of each cue, by cue, until their
+    /// translations are seen.
+    removed: HashMap<usize, Vec<Vec<String>>>,
+}
+
+impl Hook for ControlCodes {
+    fn pre_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let (text, codes) = microdvd::strip_codes(line);
+        self.removed.insert(cue, codes);
+        Ok(text)
+    }
+
+    fn post_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let codes = self.removed.remove(&cue).unwrap_or_default();
+        Ok(microdvd::to_tags(&microdvd::restore_codes(line, &codes)))
+    }
+}
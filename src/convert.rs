@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use subtitle_translate::{
+    GenericSubtitle, colors, microdvd,
+    substation::{self, Styles},
+    subtitle::{self, SubtitleFormat},
+};
+
+use crate::{
+    cli::ConvertArgs,
+    exit_code::{Failure, WithFailure},
+};
+
+/// Convert a subtitle file to another format without translating it.
+pub fn run(args: &ConvertArgs) -> anyhow::Result<()> {
+    if args.output.exists() && !args.overwrite {
+        anyhow::bail!(
+            "Destination file {} already exists, pass --overwrite to replace it",
+            args.output.display()
+        );
+    }
+
+    tracing::info!("Reading subtitles…");
+    let (format, mut subtitles) = subtitle::read_with_format(&args.input)
+        .context("Failed to read source subtitles")
+        .failure(Failure::Parse)?;
+    to_tags(
+        &args.input,
+        format,
+        SubtitleFormat::from_path(&args.output),
+        &mut subtitles,
+    )?;
+
+    tracing::info!("Writing converted subtitles…");
+    subtitle::write(&subtitles, &args.output)
+        .context("Failed to write destination subtitle file")
+        .failure(Failure::Write)
+}
+
+/// Write the formatting particular to the `source` file's `format` as the
+/// tags other formats use, if the subtitles are written out in another
+/// `output` format: MicroDVD control codes, WebVTT colour classes, and the
+/// styles and override blocks of ASS and SSA, whose styles are read from the
+/// source.
+pub fn to_tags(
+    source: &Path,
+    format: SubtitleFormat,
+    output: Option<SubtitleFormat>,
+    subtitles: &mut [GenericSubtitle],
+) -> anyhow::Result<()> {
+    if format == SubtitleFormat::MicroDvd && output != Some(SubtitleFormat::MicroDvd) {
+        for subtitle in subtitles.iter_mut() {
+            subtitle.text = microdvd::to_tags(&subtitle.text);
+        }
+    }
+    if format == SubtitleFormat::WebVtt && output != Some(SubtitleFormat::WebVtt) {
+        for subtitle in subtitles.iter_mut() {
+            subtitle.text = colors::from_webvtt(&subtitle.text);
+        }
+    }
+    let substation = [SubtitleFormat::Ass, SubtitleFormat::Ssa];
+    if substation.contains(&format) && !output.is_some_and(|output| substation.contains(&output)) {
+        let styles = Styles::read(source)
+            .context("Failed to read source styles")
+            .failure(Failure::Parse)?;
+        for subtitle in subtitles {
+            subtitle.text = substation::to_tags(&subtitle.text, subtitle.style.as_deref(), &styles);
+        }
+    }
+    Ok(())
+}
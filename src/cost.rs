@@ -0,0 +1,115 @@
+//! Estimating what translating would cost with commercial engines.
+//!
+//! Built-in prices can be overridden, and other engines added, in
+//! `pricing.toml` in the configuration directory, such as:
+//!
+//! ```toml
+//! [deepl]
+//! characters = 20.0
+//! ```
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::Context;
+use serde::Deserialize;
+use subtitle_translate::subtitle::GenericSubtitle;
+
+use crate::dirs;
+
+/// The prices engines charge, in US dollars per million characters or tokens,
+/// as listed by each when this was written.
+const BUILT_IN: &str = r"
+[deepl]
+characters = 25.0
+
+[google]
+characters = 20.0
+
+[azure]
+characters = 10.0
+
+[openai]
+input_tokens = 0.15
+output_tokens = 0.6
+";
+
+/// How much an engine charges, per million characters, or per million tokens
+/// of the text sent and received.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Price {
+    characters: Option<f64>,
+    input_tokens: Option<f64>,
+    output_tokens: Option<f64>,
+}
+
+/// How much text would be translated.
+#[derive(Clone, Copy, Default)]
+pub struct Usage {
+    pub characters: usize,
+    /// An estimate of the tokens the text would be split into, at about four
+    /// characters each.
+    pub tokens: usize,
+}
+
+impl Usage {
+    /// The text of the cues which would be sent to be translated.
+    pub fn of(subtitles: &[GenericSubtitle]) -> Self {
+        let characters = subtitles.iter().map(|s| s.text.chars().count()).sum();
+        Self {
+            characters,
+            tokens: characters.div_ceil(4),
+        }
+    }
+
+    /// Add the text of another file.
+    pub fn add(&mut self, other: Self) {
+        self.characters += other.characters;
+        self.tokens += other.tokens;
+    }
+}
+
+impl Price {
+    /// What translating some text would cost, assuming translations are about
+    /// as long as what they translate.
+    fn cost(&self, usage: Usage) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let (characters, tokens) = (usage.characters as f64, usage.tokens as f64);
+        (self.characters.unwrap_or(0.0) * characters
+            + self.input_tokens.unwrap_or(0.0) * tokens
+            + self.output_tokens.unwrap_or(0.0) * tokens)
+            / 1_000_000.0
+    }
+}
+
+/// The price of each engine: the built-in prices, with any in `pricing.toml`
+/// in the configuration directory taking precedence.
+pub fn pricing() -> anyhow::Result<BTreeMap<String, Price>> {
+    let mut pricing: BTreeMap<String, Price> =
+        toml::from_str(BUILT_IN).context("Failed to parse the built-in prices")?;
+    if let Some(dir) = dirs::config() {
+        let path = dir.join("pricing.toml");
+        if path.is_file() {
+            pricing.extend(read(&path)?);
+        }
+    }
+    Ok(pricing)
+}
+
+fn read(path: &Path) -> anyhow::Result<BTreeMap<String, Price>> {
+    tracing::debug!("Using prices from {}", path.display());
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Print what translating some text would cost with each engine.
+pub fn print_estimate(usage: Usage) -> anyhow::Result<()> {
+    println!("Characters:       {}", usage.characters);
+    println!("Tokens (approx.): {}", usage.tokens);
+    println!("Estimated cost:");
+    for (engine, price) in pricing()? {
+        println!("  {engine:<15} ${:.2}", price.cost(usage));
+    }
+    Ok(())
+}
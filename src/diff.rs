@@ -0,0 +1,85 @@
+use std::io::{IsTerminal, stdout};
+
+use anyhow::Context;
+use ratatui::crossterm::style::{Stylize, force_color_output};
+
+use subtitle_translate::{
+    align::{Pair, align},
+    subtitle::{self, GenericSubtitle, format_moment},
+};
+
+use crate::{
+    cli::DiffArgs,
+    exit_code::{Failure, WithFailure},
+};
+
+/// Compare two subtitle files, pairing their cues by time and printing those
+/// which differ, with the first file's in red and the second's in green.
+pub fn run(args: &DiffArgs) -> anyhow::Result<()> {
+    let read = |path| {
+        subtitle::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+            .failure(Failure::Parse)
+    };
+    let (left, right) = (read(&args.left)?, read(&args.right)?);
+
+    force_color_output(stdout().is_terminal());
+    let (mut changed, mut removed, mut added) = (0, 0, 0);
+    for pair in align(&left, &right) {
+        match pair {
+            Pair::Both(i, j) => {
+                let (a, b) = (&left[i], &right[j]);
+                let retimed = !args.ignore_timing && (a.start != b.start || a.end != b.end);
+                if a.text == b.text && !retimed {
+                    continue;
+                }
+                changed += 1;
+                println!("{}", format!("Cue {} / {}", i + 1, j + 1).bold());
+                if retimed {
+                    println!("{}", format!("- {}", timing(a)).red());
+                    println!("{}", format!("+ {}", timing(b)).green());
+                } else {
+                    println!("{}", timing(a).dim());
+                }
+                if a.text != b.text {
+                    print_text(&a.text, &b.text);
+                }
+            }
+            Pair::Left(i) => {
+                removed += 1;
+                println!("{}", format!("Cue {} / -", i + 1).bold());
+                println!("{}", format!("- {}", timing(&left[i])).red());
+                print_text(&left[i].text, "");
+            }
+            Pair::Right(j) => {
+                added += 1;
+                println!("{}", format!("Cue - / {}", j + 1).bold());
+                println!("{}", format!("+ {}", timing(&right[j])).green());
+                print_text("", &right[j].text);
+            }
+        }
+    }
+    println!("{changed} cue(s) changed, {removed} removed, {added} added");
+    Ok(())
+}
+
+/// A cue's timing, as written in SRT.
+fn timing(subtitle: &GenericSubtitle) -> String {
+    format!(
+        "{} --> {}",
+        format_moment(subtitle.start),
+        format_moment(subtitle.end)
+    )
+}
+
+/// Print the lines of the old text in red and the new text in green, leaving
+/// out lines they share.
+fn print_text(old: &str, new: &str) {
+    let (old_lines, new_lines): (Vec<_>, Vec<_>) = (old.lines().collect(), new.lines().collect());
+    for line in old_lines.iter().filter(|line| !new_lines.contains(line)) {
+        println!("{}", format!("- {line}").red());
+    }
+    for line in new_lines.iter().filter(|line| !old_lines.contains(line)) {
+        println!("{}", format!("+ {line}").green());
+    }
+}
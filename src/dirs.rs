@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+
+/// The platform's directories for this tool, if the home directory is known.
+fn project() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", "subtitle-translate")
+}
+
+/// Where configuration files are kept.
+pub fn config() -> Option<PathBuf> {
+    project().map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+/// Where cached data is kept.
+pub fn cache() -> Option<PathBuf> {
+    project().map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+/// Where persistent data is kept.
+pub fn data() -> Option<PathBuf> {
+    project().map(|dirs| dirs.data_dir().to_path_buf())
+}
@@ -0,0 +1,176 @@
+use std::{path::Path, time::Duration};
+
+use subtitle_translate::{api_types::Language, libretranslate::LibreTranslate};
+
+use crate::{
+    cli::{DoctorArgs, EngineArgs},
+    dirs,
+};
+
+/// Tracks the results of the checks as they are printed.
+#[derive(Default)]
+struct Checks {
+    passed: usize,
+    failed: usize,
+}
+
+impl Checks {
+    fn pass(&mut self, what: &str) {
+        self.passed += 1;
+        println!("[ ok ] {what}");
+    }
+
+    fn fail(&mut self, what: &str, fix: &str) {
+        self.failed += 1;
+        println!("[FAIL] {what}");
+        println!("       {fix}");
+    }
+}
+
+/// Check the environment, printing how to fix any problems found.
+pub async fn run(engine: &EngineArgs, args: &DoctorArgs) -> anyhow::Result<()> {
+    let mut checks = Checks::default();
+    for instance in &engine.libretranslate_instances {
+        check_instance(&mut checks, instance, engine, args).await;
+    }
+
+    match dirs::config() {
+        Some(dir) => check_writable(&mut checks, "Configuration", &dir),
+        None => checks.fail(
+            "Couldn't find a configuration directory",
+            "Make sure the HOME environment variable is set",
+        ),
+    }
+    match dirs::cache() {
+        Some(dir) => check_writable(&mut checks, "Cache", &dir),
+        None => checks.fail(
+            "Couldn't find a cache directory",
+            "Make sure the HOME environment variable is set",
+        ),
+    }
+
+    if checks.failed > 0 {
+        anyhow::bail!(
+            "{} of {} check(s) failed",
+            checks.failed,
+            checks.passed + checks.failed
+        );
+    }
+    println!("All {} checks passed", checks.passed);
+    Ok(())
+}
+
+/// Check an instance can be reached and translates the requested languages.
+async fn check_instance(
+    checks: &mut Checks,
+    instance: &str,
+    engine: &EngineArgs,
+    args: &DoctorArgs,
+) {
+    let translator =
+        LibreTranslate::new(instance.to_string(), engine.libretranslate_apikey.clone())
+            .timeout(Duration::from_secs(engine.timeout));
+    match translator.languages().await {
+        Ok(languages) => {
+            checks.pass(&format!(
+                "Reached {instance}, which supports {} languages",
+                languages.len()
+            ));
+            check_languages(checks, &languages, args);
+            check_api_key(checks, &translator, &languages, args).await;
+        }
+        Err(e) => checks.fail(
+            &format!("Couldn't reach {instance}: {e:#}"),
+            "Check that LibreTranslate is running, and pass the URL of its translation \
+             API with --libretranslate-instance",
+        ),
+    }
+}
+
+/// Check the instance supports the requested languages.
+fn check_languages(checks: &mut Checks, languages: &[Language], args: &DoctorArgs) {
+    const FIX: &str = "Run `subtitle-translate list-languages` to see the languages the instance \
+                       supports";
+    let source = languages.iter().find(|l| l.code == args.language_from);
+    if args.language_from != "auto" {
+        match source {
+            Some(language) => {
+                checks.pass(&format!("Source language {} is supported", language.name));
+            }
+            None => checks.fail(
+                &format!("Source language {} isn't supported", args.language_from),
+                FIX,
+            ),
+        }
+    }
+
+    if let Some(target) = &args.language_to {
+        let supported = match source {
+            Some(source) => source.targets.contains(target),
+            None => languages.iter().any(|l| &l.code == target),
+        };
+        if supported {
+            checks.pass(&format!("Target language {target} is supported"));
+        } else {
+            checks.fail(&format!("Target language {target} isn't supported"), FIX);
+        }
+    }
+}
+
+/// Check a translation can be made, which fails if an API key is needed but
+/// missing or invalid.
+async fn check_api_key(
+    checks: &mut Checks,
+    translator: &LibreTranslate,
+    languages: &[Language],
+    args: &DoctorArgs,
+) {
+    let Some(source) = languages
+        .iter()
+        .find(|l| l.code == args.language_from)
+        .or_else(|| languages.iter().find(|l| !l.targets.is_empty()))
+    else {
+        return;
+    };
+    let Some(target) = args
+        .language_to
+        .as_ref()
+        .filter(|target| source.targets.contains(target))
+        .or_else(|| source.targets.iter().find(|t| **t != source.code))
+    else {
+        return;
+    };
+
+    match translator
+        .translate("Hello".to_string(), &source.code, target, 0)
+        .await
+    {
+        Ok(_) => checks.pass("A test translation succeeded"),
+        Err(e) if e.to_string().to_lowercase().contains("key") => checks.fail(
+            &format!("The instance rejected the API key: {e:#}"),
+            "Pass a valid API key with --libretranslate-apikey",
+        ),
+        Err(e) => checks.fail(
+            &format!("A test translation failed: {e:#}"),
+            "Check the instance's logs for the cause",
+        ),
+    }
+}
+
+/// Check a directory can be created and written to.
+fn check_writable(checks: &mut Checks, name: &str, dir: &Path) {
+    let probe = dir.join(".doctor");
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&probe, b""))
+        .and_then(|()| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => checks.pass(&format!("{name} directory {} is writable", dir.display())),
+        Err(e) => checks.fail(
+            &format!("{name} directory {} isn't writable: {e}", dir.display()),
+            &format!(
+                "Make sure you have permission to write to {}",
+                dir.display()
+            ),
+        ),
+    }
+}
@@ -0,0 +1,116 @@
+//! HTML character references, such as `&amp;` and `&#39;`, which subtitle
+//! files are often written with but engines mangle.
+
+/// The named references decoded, and the characters they stand for.
+const NAMED: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+];
+
+/// Decode character references in a cue's text, so engines are sent the
+/// characters they stand for. `&lt;` and `&gt;` are left alone, as decoding
+/// them could make formatting tags of text.
+///
+/// ```
+/// use subtitle_translate::entities::decode;
+///
+/// assert_eq!(decode("Tom &amp; Jerry&#39;s &quot;show&quot;"), "Tom & Jerry's \"show\"");
+/// assert_eq!(decode("&lt;b&gt; &#x263A; &bogus;"), "&lt;b&gt; ☺ &bogus;");
+/// ```
+#[must_use]
+pub fn decode(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some((c, len)) = reference(rest) {
+            decoded.push(c);
+            rest = &rest[len..];
+        } else {
+            decoded.push('&');
+            rest = &rest[1..];
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// The character a reference at the start of `text` stands for, and the
+/// reference's length, if it is one which is decoded.
+fn reference(text: &str) -> Option<(char, usize)> {
+    let end = text.find(';').filter(|end| *end <= 12)?;
+    let name = &text[1..end];
+    let c = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        char::from_u32(code).filter(|c| !matches!(c, '<' | '>'))?
+    } else {
+        NAMED.iter().find(|(named, _)| *named == name)?.1
+    };
+    Some((c, end + 1))
+}
+
+/// Encode the characters WebVTT requires references for: ampersands which
+/// don't start a reference, and angle brackets which aren't part of tags.
+///
+/// ```
+/// use subtitle_translate::entities::encode_webvtt;
+///
+/// assert_eq!(encode_webvtt("<i>Tom & Jerry</i> -> &lt;3"), "<i>Tom &amp; Jerry</i> -&gt; &lt;3");
+/// ```
+#[must_use]
+pub fn encode_webvtt(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '&' if !is_reference(&text[idx..]) => encoded.push_str("&amp;"),
+            '<' if is_tag(&text[idx..]) => {
+                // Copy the whole tag.
+                encoded.push('<');
+                for (_, c) in chars.by_ref() {
+                    encoded.push(c);
+                    if c == '>' {
+                        break;
+                    }
+                }
+            }
+            '<' => encoded.push_str("&lt;"),
+            '>' => encoded.push_str("&gt;"),
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Whether `text` starts with a character reference, named or numbered.
+fn is_reference(text: &str) -> bool {
+    text.find(';').is_some_and(|end| {
+        let name = &text[1..end];
+        !name.is_empty()
+            && name.len() < 12
+            && name
+                .strip_prefix('#')
+                .unwrap_or(name)
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+/// Whether `text` starts with a formatting tag, such as `<i>`, `</b>` or
+/// `<c.yellow>`.
+fn is_tag(text: &str) -> bool {
+    let Some(end) = text.find('>') else {
+        return false;
+    };
+    let name = text[1..end].trim_start_matches('/');
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c.is_ascii_digit())
+        && !name.contains(['<', '\n'])
+}
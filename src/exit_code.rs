@@ -0,0 +1,79 @@
+use std::{error::Error, fmt};
+
+/// Describes the exit codes in the command's help.
+pub const HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Any other error
+  2  Invalid command line
+  3  The source subtitles could not be read or parsed
+  4  The translation instance could not be reached
+  5  One or more lines failed to translate
+  6  The output could not be written
+  7  Quality checks failed, in strict mode or when validating
+130  Interrupted, after saving what was translated";
+
+/// Why the process failed, which determines its exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Failure {
+    /// The source subtitles could not be read or parsed.
+    Parse = 3,
+    /// The translation instance could not be reached.
+    Unreachable = 4,
+    /// One or more lines failed to translate.
+    Translation = 5,
+    /// The output could not be written.
+    Write = 6,
+    /// Quality checks failed, in strict mode or when validating.
+    Qa = 7,
+    /// Interrupted with Ctrl-C, after saving what was translated.
+    Interrupted = 130,
+}
+
+/// An error tagged with the kind of failure it represents. It displays
+/// exactly as the error it wraps.
+#[derive(Debug)]
+struct Classified {
+    failure: Failure,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl Error for Classified {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+pub trait WithFailure<T> {
+    /// Tag an error with the kind of failure it represents.
+    fn failure(self, failure: Failure) -> anyhow::Result<T>;
+}
+
+impl<T> WithFailure<T> for anyhow::Result<T> {
+    fn failure(self, failure: Failure) -> anyhow::Result<T> {
+        self.map_err(|error| Classified { failure, error }.into())
+    }
+}
+
+/// Determine the exit code for an error.
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    error
+        .chain()
+        .find_map(|e| e.downcast_ref::<Classified>())
+        .map_or(1, |c| c.failure as u8)
+}
+
+/// Whether an error was caused by being unable to reach the instance at all.
+pub fn is_unreachable(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|e| e.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_connect() || e.is_timeout())
+}
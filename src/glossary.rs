@@ -0,0 +1,369 @@
+//! Glossaries of terms which must be translated a particular way.
+//!
+//! Named glossaries are kept as CSV files in the `glossaries` folder of the
+//! configuration directory, and can be imported from and exported to CSV,
+//! TSV and TBX files.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use subtitle_translate::languages;
+
+use crate::{
+    cli::{GlossaryCommand, TermLanguages},
+    dirs,
+    hooks::Hook,
+};
+
+/// Terms and the translations they must be given.
+#[derive(Default)]
+pub struct Glossary {
+    terms: BTreeMap<String, String>,
+}
+
+/// Manage the named glossaries.
+pub fn run(command: GlossaryCommand) -> anyhow::Result<()> {
+    match command {
+        GlossaryCommand::List { name: None } => {
+            for name in names()? {
+                println!("{name}");
+            }
+        }
+        GlossaryCommand::List { name: Some(name) } => {
+            let glossary = Glossary::open(&name)?;
+            let width = glossary.terms.keys().map(|t| t.chars().count()).max();
+            for (source, target) in &glossary.terms {
+                println!("{source:<width$}  {target}", width = width.unwrap_or(0));
+            }
+        }
+        GlossaryCommand::Add {
+            name,
+            source,
+            target,
+        } => {
+            if source.is_empty() {
+                anyhow::bail!("The term can't be empty");
+            }
+            let mut glossary = Glossary::open(&name)?;
+            glossary.terms.insert(source, target);
+            glossary.save(&name)?;
+        }
+        GlossaryCommand::Remove { name, source } => {
+            let mut glossary = Glossary::open(&name)?;
+            if glossary.terms.remove(&source).is_none() {
+                anyhow::bail!("Glossary '{name}' has no term '{source}'");
+            }
+            glossary.save(&name)?;
+        }
+        GlossaryCommand::Import {
+            name,
+            file,
+            languages,
+        } => {
+            let imported = Glossary::read(&file, &languages)?;
+            let mut glossary = Glossary::open(&name)?;
+            let count = imported.terms.len();
+            glossary.terms.extend(imported.terms);
+            glossary.save(&name)?;
+            eprintln!("Imported {count} term(s) into '{name}'");
+        }
+        GlossaryCommand::Export {
+            name,
+            file,
+            languages,
+        } => {
+            let glossary = Glossary::open(&name)?;
+            glossary.write(&file, &languages)?;
+        }
+    }
+    Ok(())
+}
+
+/// The names of the stored glossaries.
+fn names() -> anyhow::Result<Vec<String>> {
+    let dir = dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(&dir).context("Failed to read the glossaries")? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "csv")
+            && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+        {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Where the named glossaries are kept.
+fn dir() -> anyhow::Result<PathBuf> {
+    Ok(dirs::config()
+        .context("Couldn't find a configuration directory")?
+        .join("glossaries"))
+}
+
+/// The file a glossary given on the command line is loaded from, as
+/// [`Glossary::load`] finds it.
+pub fn file(name_or_path: &str) -> anyhow::Result<PathBuf> {
+    let path = Path::new(name_or_path);
+    if Format::from_path(path).is_some() {
+        return Ok(path.to_path_buf());
+    }
+    let path = self::path(name_or_path)?;
+    if !path.exists() {
+        anyhow::bail!("There is no glossary named '{name_or_path}'");
+    }
+    Ok(path)
+}
+
+/// Where a named glossary is kept.
+fn path(name: &str) -> anyhow::Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        anyhow::bail!("Invalid glossary name '{name}'");
+    }
+    Ok(dir()?.join(format!("{name}.csv")))
+}
+
+impl Glossary {
+    /// Open a named glossary, which is empty if it doesn't exist yet.
+    pub fn open(name: &str) -> anyhow::Result<Self> {
+        let path = path(name)?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::read_delimited(&path, b',')
+            .with_context(|| format!("Failed to read glossary '{name}'"))
+    }
+
+    /// Load a glossary given on the command line: a CSV, TSV or TBX file if it
+    /// has one of those extensions, and otherwise a named glossary, which must
+    /// exist.
+    pub fn load(name_or_path: &str, languages: &TermLanguages) -> anyhow::Result<Self> {
+        let path = file(name_or_path)?;
+        match Format::from_path(Path::new(name_or_path)) {
+            Some(_) => Self::read(&path, languages),
+            None => Self::open(name_or_path),
+        }
+    }
+
+    /// Each term and the translation it must be given.
+    pub fn terms(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.terms
+            .iter()
+            .map(|(source, target)| (source.as_str(), target.as_str()))
+    }
+
+    fn save(&self, name: &str) -> anyhow::Result<()> {
+        let path = path(name)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create the glossaries folder")?;
+        }
+        self.write_delimited(&path, b',')
+            .with_context(|| format!("Failed to save glossary '{name}'"))
+    }
+
+    /// Read a CSV, TSV or TBX file, depending on its extension.
+    fn read(path: &Path, languages: &TermLanguages) -> anyhow::Result<Self> {
+        let format = Format::from_path(path).context("Glossaries must be CSV, TSV or TBX files")?;
+        match format {
+            Format::Csv => Self::read_delimited(path, b','),
+            Format::Tsv => Self::read_delimited(path, b'\t'),
+            Format::Tbx => Self::read_tbx(path, languages),
+        }
+        .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// Write a CSV, TSV or TBX file, depending on its extension.
+    fn write(&self, path: &Path, languages: &TermLanguages) -> anyhow::Result<()> {
+        let format = Format::from_path(path).context("Glossaries must be CSV, TSV or TBX files")?;
+        match format {
+            Format::Csv => self.write_delimited(path, b','),
+            Format::Tsv => self.write_delimited(path, b'\t'),
+            Format::Tbx => self.write_tbx(path, languages),
+        }
+        .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Read the source and target terms from the first two columns, after a
+    /// header row.
+    fn read_delimited(path: &Path, delimiter: u8) -> anyhow::Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_path(path)?;
+        let mut terms = BTreeMap::new();
+        for record in reader.records() {
+            let record = record?;
+            if let (Some(source), Some(target)) = (record.get(0), record.get(1))
+                && !source.is_empty()
+            {
+                terms.insert(source.to_string(), target.to_string());
+            }
+        }
+        Ok(Self { terms })
+    }
+
+    fn write_delimited(&self, path: &Path, delimiter: u8) -> anyhow::Result<()> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_path(path)?;
+        writer.write_record(["source", "target"])?;
+        for (source, target) in &self.terms {
+            writer.write_record([source, target])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read the terms in the source and target languages from each entry of a
+    /// TBX file. If the target language isn't given, the first other language
+    /// in each entry is used. When the source language is detected, the
+    /// file's own language is used instead.
+    fn read_tbx(path: &Path, languages: &TermLanguages) -> anyhow::Result<Self> {
+        const XML: &str = "http://www.w3.org/XML/1998/namespace";
+        let text = std::fs::read_to_string(path)?;
+        let document = roxmltree::Document::parse(&text)?;
+        let from = if languages.from == "auto" {
+            document
+                .root_element()
+                .attribute((XML, "lang"))
+                .and_then(|language| languages::resolve(language).ok())
+                .context(
+                    "The file doesn't say which language its source terms are in, pass --language-from",
+                )?
+        } else {
+            resolve(&languages.from)
+        };
+        // Resolved whole, so regional variants such as `pt-BR` are told apart.
+        let to = languages.to.as_deref().map(resolve);
+        let mut terms = BTreeMap::new();
+        let entries = document
+            .descendants()
+            .filter(|node| node.has_tag_name("termEntry") || node.has_tag_name("conceptEntry"));
+        for entry in entries {
+            let mut source = None;
+            let mut target = None;
+            for lang_set in entry.children().filter(|node| node.has_tag_name("langSet")) {
+                let language = resolve(lang_set.attribute((XML, "lang")).unwrap_or_default());
+                let Some(term) = lang_set
+                    .descendants()
+                    .find(|node| node.has_tag_name("term"))
+                    .and_then(|node| node.text())
+                else {
+                    continue;
+                };
+                if language == from {
+                    source.get_or_insert(term);
+                } else if to.as_ref().is_none_or(|to| *to == language) {
+                    target.get_or_insert(term);
+                }
+            }
+            if let (Some(source), Some(target)) = (source, target) {
+                terms.insert(source.to_string(), target.to_string());
+            }
+        }
+        Ok(Self { terms })
+    }
+
+    fn write_tbx(&self, path: &Path, languages: &TermLanguages) -> anyhow::Result<()> {
+        let to = languages
+            .to
+            .as_deref()
+            .context("The target language is needed to write TBX, pass --to")?;
+        let mut tbx = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <martif type=\"TBX\" xml:lang=\"{}\">\n  <text>\n    <body>\n",
+            languages.from
+        );
+        for (idx, (source, target)) in self.terms.iter().enumerate() {
+            writeln!(tbx, "      <termEntry id=\"t{}\">", idx + 1)?;
+            for (language, term) in [(languages.from.as_str(), source), (to, target)] {
+                writeln!(
+                    tbx,
+                    "        <langSet xml:lang=\"{language}\"><tig><term>{}</term></tig></langSet>",
+                    escape(term)
+                )?;
+            }
+            tbx.push_str("      </termEntry>\n");
+        }
+        tbx.push_str("    </body>\n  </text>\n</martif>\n");
+        std::fs::write(path, tbx)?;
+        Ok(())
+    }
+}
+
+impl Hook for Glossary {
+    /// Put each term's translation in place of the term, so the translator
+    /// passes it through. Longer terms are replaced first, and only whole
+    /// words are.
+    fn pre_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|(source, _)| std::cmp::Reverse(source.len()));
+        let mut line = line.to_string();
+        for (source, target) in terms {
+            line = replace_words(&line, source, target);
+        }
+        Ok(line)
+    }
+
+    fn post_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        Ok(line.to_string())
+    }
+}
+
+/// Replace the occurrences of `from` in `text` which aren't part of a longer
+/// word.
+fn replace_words(text: &str, from: &str, to: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    let mut result = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (idx, _) in text.match_indices(from) {
+        let end = idx + from.len();
+        if is_word(text[..idx].chars().next_back()) || is_word(text[end..].chars().next()) {
+            continue;
+        }
+        result.push_str(&text[copied..idx]);
+        result.push_str(to);
+        copied = end;
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+/// The code of a language in a TBX file, or nothing if it is unknown.
+fn resolve(language: &str) -> String {
+    languages::resolve(language).unwrap_or_default()
+}
+
+/// Escape text for XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The file formats glossaries can be read from and written to.
+enum Format {
+    Csv,
+    Tsv,
+    Tbx,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            "tbx" => Some(Self::Tbx),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! The gRPC interface to the job server, described by
+//! `proto/subtitle_translate.proto`.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use futures::stream::{self, BoxStream, StreamExt};
+use tonic::{
+    Request, Response, Status,
+    transport::{self, server::TcpIncoming},
+};
+
+use crate::serve::{self, ApiError, Server, Submission};
+
+#[allow(clippy::pedantic)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/subtitle_translate.JobQueue.rs"));
+}
+
+use generated::job_queue_server::{JobQueue, JobQueueServer};
+
+/// How often a job is checked while streaming its progress.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubmitJobRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    pub contents: Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub format: String,
+    #[prost(string, tag = "3")]
+    pub output_format: String,
+    #[prost(string, tag = "4")]
+    pub language_from: String,
+    #[prost(string, tag = "5")]
+    pub language_to: String,
+    #[prost(int32, tag = "6")]
+    pub priority: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct JobId {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum JobStatus {
+    Queued = 0,
+    Running = 1,
+    Done = 2,
+    Failed = 3,
+    Cancelled = 4,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Job {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(enumeration = "JobStatus", tag = "2")]
+    pub status: i32,
+    #[prost(int32, tag = "3")]
+    pub priority: i32,
+    #[prost(string, tag = "4")]
+    pub language_from: String,
+    #[prost(string, tag = "5")]
+    pub language_to: String,
+    #[prost(string, tag = "6")]
+    pub format: String,
+    #[prost(string, tag = "7")]
+    pub output_format: String,
+    #[prost(uint64, tag = "8")]
+    pub cues: u64,
+    #[prost(uint64, tag = "9")]
+    pub done: u64,
+    #[prost(string, optional, tag = "10")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct JobResult {
+    #[prost(bytes = "vec", tag = "1")]
+    pub contents: Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub format: String,
+}
+
+impl From<serve::Job> for Job {
+    fn from(job: serve::Job) -> Self {
+        let status = match job.status {
+            serve::JobStatus::Queued => JobStatus::Queued,
+            serve::JobStatus::Running => JobStatus::Running,
+            serve::JobStatus::Done => JobStatus::Done,
+            serve::JobStatus::Failed => JobStatus::Failed,
+            serve::JobStatus::Cancelled => JobStatus::Cancelled,
+        };
+        Self {
+            id: job.id,
+            status: status.into(),
+            priority: job.priority,
+            language_from: job.language_from,
+            language_to: job.language_to,
+            format: job.format,
+            output_format: job.output_format,
+            cues: job.cues as u64,
+            done: job.done as u64,
+            error: job.error,
+        }
+    }
+}
+
+/// Listen on the address, so a failure to bind stops the server starting
+/// rather than only being logged.
+pub fn bind(address: SocketAddr) -> anyhow::Result<TcpIncoming> {
+    let incoming = TcpIncoming::bind(address)
+        .with_context(|| format!("Failed to listen for gRPC on {address}"))?;
+    tracing::info!("Serving gRPC on {address}");
+    Ok(incoming)
+}
+
+/// Serve the gRPC interface to the same queue as the REST API.
+pub async fn serve(server: Arc<Server>, incoming: TcpIncoming) {
+    let result = transport::Server::builder()
+        .add_service(JobQueueServer::new(Service { server }))
+        .serve_with_incoming(incoming)
+        .await;
+    if let Err(e) = result {
+        tracing::error!("The gRPC server stopped: {e}");
+    }
+}
+
+struct Service {
+    server: Arc<Server>,
+}
+
+#[tonic::async_trait]
+impl JobQueue for Service {
+    type StreamProgressStream = BoxStream<'static, Result<Job, Status>>;
+
+    async fn submit_job(
+        &self,
+        request: Request<SubmitJobRequest>,
+    ) -> Result<Response<Job>, Status> {
+        let request = request.into_inner();
+        let submission = Submission {
+            from: Some(request.language_from),
+            to: request.language_to,
+            format: if request.format.is_empty() {
+                "srt".to_string()
+            } else {
+                request.format
+            },
+            output: Some(request.output_format),
+            priority: request.priority,
+        };
+        let job = self
+            .server
+            .queue(&submission, &request.contents)
+            .map_err(status)?;
+        Ok(Response::new(job.into()))
+    }
+
+    async fn get_status(&self, request: Request<JobId>) -> Result<Response<Job>, Status> {
+        let job = self.server.job(request.into_inner().id).map_err(status)?;
+        Ok(Response::new(job.into()))
+    }
+
+    /// Send the job whenever its progress changes, ending once it is done,
+    /// has failed or was cancelled.
+    async fn stream_progress(
+        &self,
+        request: Request<JobId>,
+    ) -> Result<Response<Self::StreamProgressStream>, Status> {
+        let id = request.into_inner().id;
+        self.server.job(id).map_err(status)?;
+        let server = Arc::clone(&self.server);
+        let updates = stream::unfold((None, false), move |(last, ended)| {
+            let server = Arc::clone(&server);
+            async move {
+                if ended {
+                    return None;
+                }
+                loop {
+                    let job = match server.job(id) {
+                        Ok(job) => job,
+                        Err(e) => return Some((Err(status(e)), (last, true))),
+                    };
+                    let progress = (job.status, job.done);
+                    if last != Some(progress) {
+                        let ended = matches!(
+                            job.status,
+                            serve::JobStatus::Done
+                                | serve::JobStatus::Failed
+                                | serve::JobStatus::Cancelled
+                        );
+                        return Some((Ok(job.into()), (Some(progress), ended)));
+                    }
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        });
+        Ok(Response::new(updates.boxed()))
+    }
+
+    async fn cancel_job(&self, request: Request<JobId>) -> Result<Response<Job>, Status> {
+        let job = self
+            .server
+            .cancel(request.into_inner().id)
+            .map_err(status)?;
+        Ok(Response::new(job.into()))
+    }
+
+    async fn get_result(&self, request: Request<JobId>) -> Result<Response<JobResult>, Status> {
+        let (job, contents) = self
+            .server
+            .result(request.into_inner().id)
+            .map_err(status)?;
+        Ok(Response::new(JobResult {
+            contents,
+            format: job.output_format,
+        }))
+    }
+}
+
+/// Convert an error from the REST API into the matching gRPC status.
+fn status((code, message): ApiError) -> Status {
+    match code.as_u16() {
+        400 => Status::invalid_argument(message),
+        404 => Status::not_found(message),
+        409 => Status::failed_precondition(message),
+        _ => Status::internal(message),
+    }
+}
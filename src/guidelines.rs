@@ -0,0 +1,49 @@
+//! The limits set by the style guidelines of broadcasters and streaming
+//! services, so subtitles can be checked against and made to follow one
+//! without setting each limit.
+
+use crate::qa::Limits;
+
+/// A style guide's limits on line length, lines, reading speed, duration and
+/// gaps between cues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Guidelines {
+    /// Netflix's timed text style guide: 42 characters a line, 20 characters
+    /// a second, 5/6s shown and 2 frames between cues
+    Netflix,
+    /// The BBC's subtitle guidelines: 37 characters a line, 17 characters a
+    /// second, 1s shown and a frame between cues
+    Bbc,
+    /// The EBU's teletext subtitles: 37 characters a line, 15 characters a
+    /// second, 1s shown and a frame between cues
+    Ebu,
+}
+
+impl Guidelines {
+    /// The limits the guidelines set, each allowing two lines a cue, with
+    /// the default minimum confidence.
+    ///
+    /// ```
+    /// use subtitle_translate::guidelines::Guidelines;
+    ///
+    /// let limits = Guidelines::Bbc.limits();
+    /// assert_eq!((limits.max_line_length, limits.max_lines), (37, Some(2)));
+    /// ```
+    #[must_use]
+    pub fn limits(self) -> Limits {
+        let (max_line_length, max_cps, min_duration, min_gap) = match self {
+            Self::Netflix => (42, 20.0, 833, 83),
+            Self::Bbc => (37, 17.0, 1000, 40),
+            Self::Ebu => (37, 15.0, 1000, 40),
+        };
+        Limits {
+            max_line_length,
+            max_cps,
+            max_lines: Some(2),
+            min_duration: Some(min_duration),
+            min_gap: Some(min_gap),
+            ..Limits::default()
+        }
+    }
+}
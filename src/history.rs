@@ -0,0 +1,198 @@
+//! The history of translations, so what was translated when, and how, can be
+//! looked up later.
+//!
+//! Each run of `translate` is added to `history.jsonl` in the data directory,
+//! as a line of JSON.
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use subtitle_translate::summary::Summary;
+
+use crate::{
+    cli::{EngineArgs, HistoryCommand, TranslateArgs},
+    dirs,
+};
+
+/// A run of `translate`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// When the run finished, in seconds since the Unix epoch.
+    time: u64,
+    source: PathBuf,
+    destination: PathBuf,
+    language_from: String,
+    language_to: String,
+    /// The engine's instance URL.
+    engine: String,
+    #[serde(default)]
+    profile: Option<String>,
+    #[serde(default)]
+    glossary: Vec<String>,
+    chunk_size: usize,
+    translated: usize,
+    skipped: usize,
+    failed: usize,
+    characters: usize,
+}
+
+impl Entry {
+    /// An entry for translating a file, before it has been translated.
+    pub fn new(
+        args: &TranslateArgs,
+        engine: &EngineArgs,
+        target: &str,
+        destination: &Path,
+    ) -> Self {
+        Self {
+            time: 0,
+            source: std::path::absolute(&args.source_file)
+                .unwrap_or_else(|_| args.source_file.clone()),
+            destination: std::path::absolute(destination)
+                .unwrap_or_else(|_| destination.to_path_buf()),
+            language_from: args.language_from.clone(),
+            language_to: target.to_string(),
+            engine: engine.instances(),
+            profile: args.profile.clone(),
+            glossary: args.glossary.clone(),
+            chunk_size: args.chunk_size,
+            translated: 0,
+            skipped: 0,
+            failed: 0,
+            characters: 0,
+        }
+    }
+
+    /// Add the entry to the history, with how the run went. Failing to is
+    /// only warned about, as the translation itself was made.
+    pub fn record(&self, summary: &Summary) {
+        let entry = Self {
+            time: now(),
+            translated: summary.translated,
+            skipped: summary.skipped,
+            failed: summary.failed,
+            characters: summary.characters,
+            ..self.clone()
+        };
+        if let Err(e) = append(&entry) {
+            tracing::warn!("Failed to record the run in the history: {e:#}");
+        }
+    }
+}
+
+/// Look through the history.
+pub fn run(command: &HistoryCommand) -> anyhow::Result<()> {
+    let entries = read()?;
+    match command {
+        HistoryCommand::List { limit } => {
+            let skip = entries.len().saturating_sub(*limit);
+            for (idx, entry) in entries.iter().enumerate().skip(skip) {
+                println!(
+                    "{:>4}  {}  {} -> {}  {}",
+                    idx + 1,
+                    format_time(entry.time),
+                    entry.language_from,
+                    entry.language_to,
+                    entry.source.display()
+                );
+            }
+        }
+        HistoryCommand::Show { id } => {
+            let entry = id
+                .checked_sub(1)
+                .and_then(|idx| entries.get(idx))
+                .with_context(|| format!("There is no run {id} in the history"))?;
+            println!("Time:         {}", format_time(entry.time));
+            println!("Source:       {}", entry.source.display());
+            println!("Destination:  {}", entry.destination.display());
+            println!(
+                "Languages:    {} -> {}",
+                entry.language_from, entry.language_to
+            );
+            println!("Engine:       {}", entry.engine);
+            if let Some(profile) = &entry.profile {
+                println!("Profile:      {profile}");
+            }
+            if !entry.glossary.is_empty() {
+                println!("Glossaries:   {}", entry.glossary.join(", "));
+            }
+            println!("Chunk size:   {}", entry.chunk_size);
+            println!("Translated:   {}", entry.translated);
+            println!("Skipped:      {}", entry.skipped);
+            println!("Failed:       {}", entry.failed);
+            println!("Characters:   {}", entry.characters);
+        }
+    }
+    Ok(())
+}
+
+fn path() -> anyhow::Result<PathBuf> {
+    Ok(dirs::data()
+        .context("Couldn't find a data directory")?
+        .join("history.jsonl"))
+}
+
+fn append(entry: &Entry) -> anyhow::Result<()> {
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Every entry in the history, oldest first.
+fn read() -> anyhow::Result<Vec<Entry>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse {}", path.display()))
+        })
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// A time in seconds since the Unix epoch, as a UTC date and time.
+fn format_time(secs: u64) -> String {
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    // Howard Hinnant's algorithm for the civil date of a day count.
+    let z = days + 719_468;
+    let (era, day_of_era) = (z / 146_097, z % 146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02} UTC",
+        rest / 3600,
+        rest % 3600 / 60
+    )
+}
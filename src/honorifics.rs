@@ -0,0 +1,141 @@
+//! Keeping Japanese and Korean honorifics attached to names, as fansubs do,
+//! rather than letting the translator drop them or turn them into titles.
+
+use crate::{glossary::Glossary, hooks::Hook};
+
+/// Japanese honorifics and their romanisations.
+const JAPANESE: &[(&str, &str)] = &[
+    ("さん", "san"),
+    ("くん", "kun"),
+    ("君", "kun"),
+    ("ちゃん", "chan"),
+    ("たん", "tan"),
+    ("さま", "sama"),
+    ("様", "sama"),
+    ("殿", "dono"),
+    ("先輩", "senpai"),
+    ("せんぱい", "senpai"),
+    ("先生", "sensei"),
+    ("せんせい", "sensei"),
+    ("氏", "shi"),
+];
+
+/// Korean honorifics and terms of address, and their romanisations.
+const KOREAN: &[(&str, &str)] = &[
+    ("씨", "ssi"),
+    ("님", "nim"),
+    ("오빠", "oppa"),
+    ("언니", "unnie"),
+    ("형", "hyung"),
+    ("누나", "noona"),
+    ("선배", "sunbae"),
+    ("선생님", "seonsaengnim"),
+];
+
+/// Korean terms of address which are kept even without a name.
+const KOREAN_ADDRESS: &[&str] = &["오빠", "언니", "누나"];
+
+/// Puts each name from the glossaries which has an honorific attached in the
+/// line as the name's translation and the romanised honorific, such as
+/// `Tanaka-san`, so the translator passes it through.
+pub struct Honorifics {
+    korean: bool,
+    /// Each name and its translation, longest first.
+    names: Vec<(String, String)>,
+    /// Each honorific and its romanisation, longest first.
+    suffixes: Vec<(&'static str, &'static str)>,
+}
+
+impl Honorifics {
+    /// Preserve honorifics in a source language, if it has them, attached to
+    /// the names in the glossaries.
+    pub fn new(language: &str, glossaries: &[Glossary]) -> Option<Self> {
+        let (korean, suffixes) = match language {
+            "ja" => (false, JAPANESE),
+            "ko" => (true, KOREAN),
+            _ => return None,
+        };
+        let mut names: Vec<_> = glossaries
+            .iter()
+            .flat_map(Glossary::terms)
+            .map(|(source, target)| (source.to_string(), target.to_string()))
+            .collect();
+        names.sort_by_key(|(source, _)| std::cmp::Reverse(source.len()));
+        let mut suffixes = suffixes.to_vec();
+        suffixes.sort_by_key(|(honorific, _)| std::cmp::Reverse(honorific.len()));
+        Some(Self {
+            korean,
+            names,
+            suffixes,
+        })
+    }
+
+    /// The honorific at the start of some text, and its length, allowing a
+    /// space before it in Korean.
+    fn honorific_at(&self, text: &str) -> Option<(usize, &'static str)> {
+        let (space, text) = match text.strip_prefix(' ') {
+            Some(rest) if self.korean => (1, rest),
+            _ => (0, text),
+        };
+        self.suffixes
+            .iter()
+            .find(|(honorific, _)| text.starts_with(honorific))
+            .map(|(honorific, romanised)| (space + honorific.len(), *romanised))
+    }
+}
+
+impl Hook for Honorifics {
+    fn pre_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        let mut result = String::with_capacity(line.len());
+        let mut rest = line;
+        'outer: while let Some(c) = rest.chars().next() {
+            // Korean puts spaces between words, so names must start one.
+            let at_word = !self.korean
+                || !result
+                    .chars()
+                    .next_back()
+                    .is_some_and(char::is_alphanumeric);
+            if at_word {
+                for (name, translation) in &self.names {
+                    if let Some(after) = rest.strip_prefix(name.as_str())
+                        && let Some((length, romanised)) = self.honorific_at(after)
+                    {
+                        result.push_str(translation);
+                        result.push('-');
+                        result.push_str(romanised);
+                        rest = &after[length..];
+                        continue 'outer;
+                    }
+                }
+                if self.korean
+                    && let Some(term) = KOREAN_ADDRESS.iter().find(|term| rest.starts_with(**term))
+                    && !rest[term.len()..]
+                        .starts_with(|c: char| c.is_alphanumeric() && !is_particle(c))
+                    && let Some((_, romanised)) = self.honorific_at(term)
+                {
+                    let mut letters = romanised.chars();
+                    result.extend(letters.next().map(|c| c.to_ascii_uppercase()));
+                    result.push_str(letters.as_str());
+                    rest = &rest[term.len()..];
+                    continue;
+                }
+            }
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        Ok(result)
+    }
+
+    fn post_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        Ok(line.to_string())
+    }
+}
+
+/// Whether a Hangul syllable is one of the common particles which follow a
+/// term of address.
+fn is_particle(c: char) -> bool {
+    matches!(
+        c,
+        '가' | '는' | '도' | '야' | '아' | '랑' | '한' | '를' | '의'
+    )
+}
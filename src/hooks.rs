@@ -0,0 +1,117 @@
+//! Hooks which transform each line before it is translated and after.
+
+use anyhow::Context;
+use subtitle_translate::subtitle::{self, GenericSubtitle, SubtitleFormat};
+
+use crate::{
+    cli::{TermLanguages, TranslateArgs},
+    color_tags::ColorTags,
+    control_codes::ControlCodes,
+    glossary::Glossary,
+    honorifics::Honorifics,
+    ruby::Ruby,
+    symbols::Symbols,
+};
+
+/// Transforms lines before they are sent to be translated, and the
+/// translations which come back. Each is given with its cue's number, from 1,
+/// so anything kept from a line is found again for its translation even if
+/// other cues' translations are skipped.
+pub trait Hook {
+    fn pre_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String>;
+
+    fn post_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String>;
+
+    /// Change a whole cue once it has been translated. Cues are numbered from
+    /// 1.
+    fn on_cue(&mut self, _cue: usize, _subtitle: &mut GenericSubtitle) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// The hooks to run, in the order they were loaded.
+#[derive(Default)]
+pub struct Hooks(Vec<Box<dyn Hook>>);
+
+impl Hooks {
+    /// Load the hooks asked for on the command line, and any installed in the
+    /// configuration directory.
+    pub fn load(args: &TranslateArgs) -> anyhow::Result<Self> {
+        let mut hooks = Self(vec![]);
+        if let Some(SubtitleFormat::MicroDvd) = SubtitleFormat::detect(&args.source_file) {
+            hooks.0.push(Box::new(ControlCodes::default()));
+        }
+        hooks.0.push(Box::new(ColorTags::default()));
+        hooks.0.push(Box::new(Ruby::new(args.ruby)));
+        if !args.no_protect_symbols {
+            hooks.0.push(Box::new(Symbols::default()));
+        }
+        let languages = TermLanguages {
+            from: args.language_from.clone(),
+            to: args.language_to.clone(),
+        };
+        let glossaries = args
+            .glossary
+            .iter()
+            .map(|glossary| Glossary::load(glossary, &languages))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if args.preserve_honorifics {
+            // Before the glossaries, which would translate the names alone.
+            if let Some(honorifics) = Honorifics::new(&args.language_from, &glossaries) {
+                hooks.0.push(Box::new(honorifics));
+            } else {
+                tracing::warn!(
+                    "Honorifics are only preserved when translating from Japanese or Korean"
+                );
+            }
+        }
+        for glossary in glossaries {
+            hooks.0.push(Box::new(glossary));
+        }
+        #[cfg(feature = "plugins")]
+        for plugin in crate::plugin::load(&args.plugin)? {
+            hooks.0.push(Box::new(plugin));
+        }
+        #[cfg(feature = "lua")]
+        for path in &args.script {
+            hooks.0.push(Box::new(crate::script::Script::load(path)?));
+        }
+        Ok(hooks)
+    }
+
+    /// Run every hook's `pre_translate` over the subtitles' text.
+    pub fn pre_translate(&mut self, subtitles: &mut [GenericSubtitle]) -> anyhow::Result<()> {
+        self.apply(subtitles, |hook, cue, line| hook.pre_translate(cue, line))
+    }
+
+    /// Run every hook's `post_translate` over the subtitles' text, then its
+    /// `on_cue` over each whole cue.
+    pub fn post_translate(&mut self, subtitles: &mut [GenericSubtitle]) -> anyhow::Result<()> {
+        self.apply(subtitles, |hook, cue, line| hook.post_translate(cue, line))?;
+        for (idx, subtitle) in subtitles.iter_mut().enumerate() {
+            for hook in &mut self.0 {
+                hook.on_cue(idx + 1, subtitle)
+                    .with_context(|| format!("A hook failed on cue {}", idx + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pass each line with any visible text through every hook in turn.
+    fn apply(
+        &mut self,
+        subtitles: &mut [GenericSubtitle],
+        run: impl Fn(&mut dyn Hook, usize, &str) -> anyhow::Result<String>,
+    ) -> anyhow::Result<()> {
+        for (idx, subtitle) in subtitles.iter_mut().enumerate() {
+            if subtitle::is_blank(&subtitle.text) {
+                continue;
+            }
+            for hook in &mut self.0 {
+                subtitle.text = run(hook.as_mut(), idx + 1, &subtitle.text)
+                    .with_context(|| format!("A hook failed on cue {}", idx + 1))?;
+            }
+        }
+        Ok(())
+    }
+}
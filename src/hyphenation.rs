@@ -0,0 +1,105 @@
+//! Hyphenating words with TeX hyphenation patterns, as the hyph-utf8 project
+//! publishes for many languages, using Liang's algorithm.
+
+use std::collections::HashMap;
+
+/// The fewest characters left before or after a hyphen.
+const MIN_PART: usize = 2;
+
+/// Hyphenation patterns for a language.
+#[derive(Clone, Default)]
+pub struct Patterns {
+    /// The weight of each point between the letters of each pattern, by its
+    /// letters.
+    patterns: HashMap<String, Vec<u32>>,
+    longest: usize,
+}
+
+impl Patterns {
+    /// Parse patterns written as TeX writes them, such as `.ab3c` or `4ft1`,
+    /// separated by whitespace. Comments, starting with `%`, and TeX commands
+    /// are ignored.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut patterns = Self::default();
+        for line in text.lines() {
+            let line = line.split('%').next().unwrap_or_default();
+            for pattern in line.split_whitespace() {
+                if pattern.contains(['\\', '{', '}', '-']) {
+                    continue;
+                }
+                let mut letters = String::new();
+                let mut weights = vec![0];
+                for c in pattern.chars() {
+                    if let Some(weight) = c.to_digit(10) {
+                        let last = weights.len() - 1;
+                        weights[last] = weight;
+                    } else {
+                        letters.extend(c.to_lowercase());
+                        weights.push(0);
+                    }
+                }
+                if !letters.is_empty() {
+                    patterns.longest = patterns.longest.max(weights.len() - 1);
+                    patterns.patterns.insert(letters, weights);
+                }
+            }
+        }
+        patterns
+    }
+
+    /// Whether there are no patterns.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Where a word can be hyphenated, as the number of characters before
+    /// each hyphen. Punctuation around the word is never split.
+    ///
+    /// ```
+    /// use subtitle_translate::hyphenation::Patterns;
+    ///
+    /// let patterns = Patterns::parse("1ba 1sch 1fahrt");
+    /// assert_eq!(patterns.breaks("Dampfschifffahrt"), [5, 11]);
+    /// assert_eq!(patterns.breaks("„Dampfschifffahrt!“"), [6, 12]);
+    /// ```
+    #[must_use]
+    pub fn breaks(&self, word: &str) -> Vec<usize> {
+        let chars: Vec<char> = word.chars().collect();
+        let start = chars
+            .iter()
+            .position(|c| c.is_alphabetic())
+            .unwrap_or(chars.len());
+        let end = chars
+            .iter()
+            .rposition(|c| c.is_alphabetic())
+            .map_or(start, |end| end + 1);
+        if end - start < MIN_PART * 2 || chars[start..end].iter().any(|c| !c.is_alphabetic()) {
+            return Vec::new();
+        }
+        let mut letters = vec!['.'];
+        letters.extend(chars[start..end].iter().flat_map(|c| c.to_lowercase()));
+        letters.push('.');
+        if letters.len() != end - start + 2 {
+            // Lowercasing changed the number of characters.
+            return Vec::new();
+        }
+        let mut points = vec![0; letters.len() + 1];
+        for from in 0..letters.len() {
+            for to in from + 1..=letters.len().min(from + self.longest) {
+                let key: String = letters[from..to].iter().collect();
+                if let Some(weights) = self.patterns.get(&key) {
+                    for (offset, weight) in weights.iter().enumerate() {
+                        points[from + offset] = points[from + offset].max(*weight);
+                    }
+                }
+            }
+        }
+        // The point before the word's `n`th letter follows the leading dot.
+        (MIN_PART..=end - start - MIN_PART)
+            .filter(|n| points[n + 1] % 2 == 1)
+            .map(|n| start + n)
+            .collect()
+    }
+}
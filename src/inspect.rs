@@ -0,0 +1,70 @@
+use anyhow::Context;
+
+use subtitle_translate::{
+    stats::Statistics,
+    subtitle::{self, format_moment},
+};
+
+use crate::{
+    cli::{EngineArgs, InspectArgs},
+    exit_code::{Failure, WithFailure},
+};
+
+/// How many cues to send when detecting the language of a file.
+const DETECTION_SAMPLE: usize = 20;
+
+/// Print statistics about a subtitle file.
+pub async fn run(engine: &EngineArgs, args: InspectArgs) -> anyhow::Result<()> {
+    let (format, subtitles) = subtitle::read_with_format(&args.file)
+        .context("Failed to read subtitles")
+        .failure(Failure::Parse)?;
+    let encoding = subtitle::detect_encoding(&args.file).context("Failed to read subtitles")?;
+    let stats = Statistics::new(&subtitles);
+
+    println!("File:             {}", args.file.display());
+    println!("Format:           {format}");
+    println!("Encoding:         {}", encoding.name());
+    println!("Cues:             {}", stats.cues);
+    if let (Some(start), Some(end)) = (stats.first_start, stats.last_end) {
+        println!(
+            "Span:             {} --> {} ({})",
+            format_moment(start),
+            format_moment(end),
+            format_moment((end.as_ms() - start.as_ms()).into())
+        );
+    }
+    println!("Characters:       {}", stats.characters);
+    println!("Average CPS:      {:.1}", stats.average_cps);
+    if let Some((idx, cps)) = stats.max_cps {
+        println!("Maximum CPS:      {cps:.1} (cue {})", idx + 1);
+    }
+    if let Some((idx, length)) = stats.longest_line {
+        println!("Longest line:     {length} characters (cue {})", idx + 1);
+    }
+    println!("Overlapping cues: {}", stats.overlaps);
+
+    if !args.no_detect {
+        let sample = subtitles
+            .iter()
+            .map(|s| subtitle::visible_text(&s.text))
+            .filter(|t| !t.trim().is_empty())
+            .take(DETECTION_SAMPLE)
+            .collect::<Vec<_>>()
+            .join("\n");
+        match engine.translator().detect(sample).await {
+            Ok(detected) => match detected.first() {
+                Some(language) => println!(
+                    "Language:         {} ({:.0}% confidence)",
+                    language.language, language.confidence
+                ),
+                None => println!("Language:         unknown"),
+            },
+            Err(e) => {
+                tracing::warn!("Failed to detect language: {e}");
+                println!("Language:         unknown (detection failed)");
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::io::{self, BufRead, Write};
+
+use subtitle_translate::{
+    libretranslate::LibreTranslate,
+    subtitle::{GenericSubtitle, format_moment},
+};
+
+/// How many alternatives to request when the user asks for them.
+const ALTERNATIVES: u32 = 3;
+
+/// Walk through each translated line, showing it alongside the original and
+/// letting the user accept, edit, request alternatives for, or skip it.
+///
+/// Skipped lines keep their original text.
+pub async fn review(
+    translator: &LibreTranslate,
+    source: &str,
+    target: &str,
+    originals: &[GenericSubtitle],
+    translated: &mut [GenericSubtitle],
+) -> anyhow::Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let total = translated.len();
+
+    'lines: for (idx, (original, line)) in originals.iter().zip(translated.iter_mut()).enumerate() {
+        if original.text.is_empty() {
+            continue;
+        }
+
+        println!();
+        println!(
+            "[{}/{total}] {} --> {}",
+            idx + 1,
+            format_moment(original.start),
+            format_moment(original.end)
+        );
+        println!("  Original:    {}", original.text);
+        println!("  Translation: {}", line.text);
+
+        loop {
+            let answer = prompt(
+                &mut input,
+                "[a]ccept, [e]dit, [r]equest alternatives, [s]kip, accept [A]ll remaining? ",
+            )?;
+            match answer.as_str() {
+                "" | "a" => break,
+                "A" => break 'lines,
+                "s" => {
+                    line.text.clone_from(&original.text);
+                    break;
+                }
+                "e" => {
+                    let edited = prompt(&mut input, "New translation: ")?;
+                    if !edited.is_empty() {
+                        line.text = edited;
+                    }
+                    break;
+                }
+                "r" => {
+                    let translation = translator
+                        .translate(original.text.clone(), source, target, ALTERNATIVES)
+                        .await?;
+                    let mut options = vec![translation.translated_text];
+                    options.extend(translation.alternatives.unwrap_or_default());
+                    for (n, option) in options.iter().enumerate() {
+                        println!("  {}) {option}", n + 1);
+                    }
+                    let choice = prompt(&mut input, "Choose an option (blank to go back): ")?;
+                    if let Some(option) = choice
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|n| options.get(n))
+                    {
+                        line.text.clone_from(option);
+                        break;
+                    }
+                }
+                _ => println!("Unrecognised option {answer:?}"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a prompt and read a single trimmed line of input.
+fn prompt(input: &mut impl BufRead, message: &str) -> anyhow::Result<String> {
+    print!("{message}");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        anyhow::bail!("Input closed during interactive review");
+    }
+    Ok(line.trim().to_string())
+}
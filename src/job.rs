@@ -0,0 +1,271 @@
+//! Translating a whole subtitle file, for applications embedding the library
+//! which want to show how it is going as it goes.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::Context;
+
+use crate::{
+    GenericSubtitle, TranslationPipeline, Translator,
+    cancel::{CancellationToken, Cancelled},
+    pipeline::Event,
+    subtitle::{self, SubtitleFormat},
+    summary::Summary,
+};
+
+/// How far a job has got, given to its progress callback as each cue is
+/// translated or fails to be.
+pub struct Progress<'a> {
+    /// The cues translated or failed so far.
+    pub done: usize,
+    /// The cues with text to translate.
+    pub total: usize,
+    /// The cue which was just translated or failed, with its translation or
+    /// error.
+    pub event: &'a Event<'a>,
+}
+
+/// Where a job's subtitles come from.
+enum Source {
+    Path(PathBuf),
+    Text(String, SubtitleFormat),
+}
+
+/// A callback told of a job's progress.
+type OnProgress = Box<dyn Fn(&Progress<'_>) + Send + Sync>;
+
+/// What a finished job translated.
+pub struct Finished {
+    /// The translated subtitles, which failed cues are left untranslated in.
+    pub subtitles: Vec<GenericSubtitle>,
+    /// How each cue was translated.
+    pub summary: Summary,
+    /// Whether the job was cancelled before every cue was translated, in
+    /// which case the destination isn't written.
+    pub cancelled: bool,
+}
+
+/// The translation of a subtitle file with a [`Translator`], built with
+/// [`TranslationJob::builder`].
+///
+/// ```no_run
+/// # use subtitle_translate::job::TranslationJob;
+/// # use subtitle_translate::{Translator, api_types::Translation};
+/// # struct Engine;
+/// # impl Translator for Engine {
+/// #     async fn translate(&self, input: String, _: &str, _: &str, _: u32) -> anyhow::Result<Translation> {
+/// #         Ok(Translation { translated_text: input, alternatives: None, detected_language: None, cached: false })
+/// #     }
+/// # }
+/// # async fn run() -> anyhow::Result<()> {
+/// let finished = TranslationJob::builder()
+///     .source("film.srt")
+///     .destination("film.de.srt")
+///     .target("de")
+///     .engine(Engine)
+///     .on_progress(|progress| println!("{}/{}", progress.done, progress.total))
+///     .build()?
+///     .run()
+///     .await?;
+/// println!("{}", finished.summary);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TranslationJob<T> {
+    source: Source,
+    destination: Option<PathBuf>,
+    pipeline: TranslationPipeline<T>,
+    on_progress: Option<OnProgress>,
+}
+
+impl<T: Translator> TranslationJob<T> {
+    /// Start building a job.
+    #[must_use]
+    pub fn builder() -> TranslationJobBuilder<T> {
+        TranslationJobBuilder {
+            source: None,
+            destination: None,
+            engine: None,
+            source_language: "auto".to_string(),
+            target: None,
+            chunk_size: None,
+            on_progress: None,
+            cancellation: None,
+        }
+    }
+
+    /// Read the source, translate it and write the translation to the
+    /// destination, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the source can't be read or parsed, a line fails to
+    /// translate, as [`TranslationPipeline::translate`] does, or the
+    /// destination can't be written. A cancelled job isn't an error, and
+    /// returns what was translated before it was cancelled.
+    pub async fn run(&self) -> anyhow::Result<Finished> {
+        let mut subtitles = match &self.source {
+            Source::Path(path) => subtitle::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+            Source::Text(contents, format) => subtitle::parse(contents, *format)
+                .with_context(|| format!("Failed to parse the source as {format}"))?,
+        };
+        let mut summary = Summary::new();
+        let total = subtitles
+            .iter()
+            .filter(|subtitle| !subtitle::is_blank(&subtitle.text))
+            .count();
+        let done = AtomicUsize::new(0);
+        let result = self
+            .pipeline
+            .translate(&mut subtitles, &mut summary, |event| {
+                if let Some(on_progress) = &self.on_progress {
+                    on_progress(&Progress {
+                        done: done.fetch_add(1, Ordering::Relaxed) + 1,
+                        total,
+                        event,
+                    });
+                }
+            })
+            .await;
+        let cancelled = match result {
+            Err(e) if e.is::<Cancelled>() => true,
+            result => {
+                result?;
+                false
+            }
+        };
+        if let Some(destination) = self.destination.as_ref().filter(|_| !cancelled) {
+            subtitle::write(&subtitles, destination)
+                .with_context(|| format!("Failed to write {}", destination.display()))?;
+        }
+        Ok(Finished {
+            subtitles,
+            summary,
+            cancelled,
+        })
+    }
+}
+
+/// Builds a [`TranslationJob`]. A source, target language and engine are
+/// needed.
+pub struct TranslationJobBuilder<T> {
+    source: Option<Source>,
+    destination: Option<PathBuf>,
+    engine: Option<T>,
+    source_language: String,
+    target: Option<String>,
+    chunk_size: Option<usize>,
+    on_progress: Option<OnProgress>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl<T: Translator> TranslationJobBuilder<T> {
+    /// The subtitle file to translate, of any supported format.
+    #[must_use]
+    pub fn source(mut self, path: impl AsRef<Path>) -> Self {
+        self.source = Some(Source::Path(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Subtitles to translate which are already in memory, such as a file a
+    /// browser was given, rather than on disk. Without a destination, the
+    /// translation can be written out with [`subtitle::to_string`].
+    #[must_use]
+    pub fn source_text(mut self, contents: impl Into<String>, format: SubtitleFormat) -> Self {
+        self.source = Some(Source::Text(contents.into(), format));
+        self
+    }
+
+    /// Write the translation to a file, in the format its extension names.
+    /// Otherwise it is only returned.
+    #[must_use]
+    pub fn destination(mut self, path: impl AsRef<Path>) -> Self {
+        self.destination = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// The engine to translate with.
+    #[must_use]
+    pub fn engine(mut self, engine: T) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    /// Set the source language, rather than detecting it.
+    #[must_use]
+    pub fn source_language(mut self, language: impl Into<String>) -> Self {
+        self.source_language = language.into();
+        self
+    }
+
+    /// The language to translate into.
+    #[must_use]
+    pub fn target(mut self, language: impl Into<String>) -> Self {
+        self.target = Some(language.into());
+        self
+    }
+
+    /// Set how many lines are sent to the engine in parallel, as
+    /// [`TranslationPipeline::chunk_size`] does.
+    #[must_use]
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Be told of the job's progress as each cue is translated or fails to
+    /// be, such as to show a progress bar and each translation.
+    #[must_use]
+    pub fn on_progress(
+        mut self,
+        on_progress: impl Fn(&Progress<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Stop the job once the token is cancelled, as
+    /// [`TranslationPipeline::cancellation`] does.
+    #[must_use]
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Build the job.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no source, target language or engine was given, the chunk
+    /// size is 0, or the destination isn't of a format which can be written.
+    pub fn build(self) -> anyhow::Result<TranslationJob<T>> {
+        let source = self.source.context("No source was given")?;
+        let target = self.target.context("No target language was given")?;
+        let engine = self.engine.context("No engine was given")?;
+        if let Some(destination) = &self.destination {
+            anyhow::ensure!(
+                SubtitleFormat::from_path(destination).is_some(),
+                "Can't write subtitles to {}, as its format isn't supported",
+                destination.display()
+            );
+        }
+        let mut pipeline = TranslationPipeline::new(engine, target).source(self.source_language);
+        if let Some(chunk_size) = self.chunk_size {
+            anyhow::ensure!(chunk_size > 0, "The chunk size must be positive");
+            pipeline = pipeline.chunk_size(chunk_size);
+        }
+        if let Some(token) = self.cancellation {
+            pipeline = pipeline.cancellation(token);
+        }
+        Ok(TranslationJob {
+            source,
+            destination: self.destination,
+            pipeline,
+            on_progress: self.on_progress,
+        })
+    }
+}
@@ -0,0 +1,216 @@
+/// A language supported by LibreTranslate.
+pub struct KnownLanguage {
+    /// The code LibreTranslate uses for the language.
+    pub code: &'static str,
+    /// The English name of the language.
+    pub name: &'static str,
+    /// The ISO 639-2 codes for the language, terminological first.
+    pub alpha3: &'static [&'static str],
+}
+
+const fn language(
+    code: &'static str,
+    name: &'static str,
+    alpha3: &'static [&'static str],
+) -> KnownLanguage {
+    KnownLanguage { code, name, alpha3 }
+}
+
+/// Languages supported by LibreTranslate.
+pub const KNOWN_LANGUAGES: &[KnownLanguage] = &[
+    language("ar", "Arabic", &["ara"]),
+    language("az", "Azerbaijani", &["aze"]),
+    language("bg", "Bulgarian", &["bul"]),
+    language("bn", "Bengali", &["ben"]),
+    language("ca", "Catalan", &["cat"]),
+    language("cs", "Czech", &["ces", "cze"]),
+    language("da", "Danish", &["dan"]),
+    language("de", "German", &["deu", "ger"]),
+    language("el", "Greek", &["ell", "gre"]),
+    language("en", "English", &["eng"]),
+    language("eo", "Esperanto", &["epo"]),
+    language("es", "Spanish", &["spa"]),
+    language("et", "Estonian", &["est"]),
+    language("eu", "Basque", &["eus", "baq"]),
+    language("fa", "Persian", &["fas", "per"]),
+    language("fi", "Finnish", &["fin"]),
+    language("fr", "French", &["fra", "fre"]),
+    language("ga", "Irish", &["gle"]),
+    language("gl", "Galician", &["glg"]),
+    language("he", "Hebrew", &["heb"]),
+    language("hi", "Hindi", &["hin"]),
+    language("hu", "Hungarian", &["hun"]),
+    language("id", "Indonesian", &["ind"]),
+    language("it", "Italian", &["ita"]),
+    language("ja", "Japanese", &["jpn"]),
+    language("ko", "Korean", &["kor"]),
+    language("lt", "Lithuanian", &["lit"]),
+    language("lv", "Latvian", &["lav"]),
+    language("ms", "Malay", &["msa", "may"]),
+    language("nb", "Norwegian Bokmål", &["nob", "nor"]),
+    language("nl", "Dutch", &["nld", "dut"]),
+    language("pl", "Polish", &["pol"]),
+    language("pt", "Portuguese", &["por"]),
+    language("pb", "Portuguese (Brazil)", &[]),
+    language("ro", "Romanian", &["ron", "rum"]),
+    language("ru", "Russian", &["rus"]),
+    language("sk", "Slovak", &["slk", "slo"]),
+    language("sl", "Slovenian", &["slv"]),
+    language("sq", "Albanian", &["sqi", "alb"]),
+    language("sv", "Swedish", &["swe"]),
+    language("th", "Thai", &["tha"]),
+    language("tl", "Tagalog", &["tgl"]),
+    language("tr", "Turkish", &["tur"]),
+    language("uk", "Ukrainian", &["ukr"]),
+    language("ur", "Urdu", &["urd"]),
+    language("zh", "Chinese", &["zho", "chi"]),
+    language("zt", "Chinese (traditional)", &[]),
+];
+
+/// Older or alternative codes which LibreTranslate knows by another code.
+const ALIASES: &[(&str, &str)] = &[("no", "nb"), ("iw", "he"), ("in", "id"), ("fil", "tl")];
+
+/// Resolve a language code, ISO 639-2 code, BCP-47 tag or English name to the
+/// code LibreTranslate uses.
+///
+/// Unknown two letter codes are passed through unchanged so that languages
+/// added by newer instances still work.
+///
+/// # Errors
+///
+/// If the language can't be resolved, the closest known languages are
+/// returned instead.
+pub fn resolve(input: &str) -> Result<String, Vec<&'static KnownLanguage>> {
+    let input = input.trim().to_lowercase();
+    let mut subtags = input.split(['-', '_']);
+    let primary = subtags.next().unwrap_or_default();
+    let rest: Vec<_> = subtags.collect();
+
+    if primary == "zh" && rest.iter().any(|t| ["hant", "tw", "hk", "mo"].contains(t)) {
+        return Ok("zt".to_string());
+    }
+    if primary == "pt" && rest.contains(&"br") {
+        return Ok("pb".to_string());
+    }
+
+    let primary = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == primary)
+        .map_or(primary, |(_, code)| *code);
+    if let Some(known) = KNOWN_LANGUAGES.iter().find(|l| {
+        l.code == primary
+            || l.alpha3.contains(&primary)
+            || l.name.to_lowercase() == input
+            || short_name(l) == input
+    }) {
+        return Ok(known.code.to_string());
+    }
+
+    if primary.len() == 2 && primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Ok(primary.to_string());
+    }
+
+    Err(suggestions(&input))
+}
+
+/// Resolve a target language as [`resolve`] does, but keeping the region or
+/// script it is given with, as a BCP-47 tag. Engines and media players tell
+/// such variants apart, so they are kept for file names and left to each
+/// [`Translator`](crate::Translator) to map to its own codes.
+///
+/// ```
+/// use subtitle_translate::languages::resolve_target;
+///
+/// assert_eq!(resolve_target("pt_br").ok().as_deref(), Some("pt-BR"));
+/// assert_eq!(resolve_target("zh-hant").ok().as_deref(), Some("zh-Hant"));
+/// assert_eq!(resolve_target("German").ok().as_deref(), Some("de"));
+/// ```
+///
+/// # Errors
+///
+/// If the language can't be resolved, the closest known languages are
+/// returned instead.
+pub fn resolve_target(input: &str) -> Result<String, Vec<&'static KnownLanguage>> {
+    let code = resolve(input)?;
+    let qualifiers: Vec<String> = input
+        .trim()
+        .split(['-', '_'])
+        .skip(1)
+        .filter_map(|subtag| match subtag.len() {
+            // Scripts, such as `Hant`, are written in title case.
+            4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                let (first, rest) = subtag.split_at(1);
+                Some(first.to_ascii_uppercase() + &rest.to_ascii_lowercase())
+            }
+            2 | 3 if subtag.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                Some(subtag.to_ascii_uppercase())
+            }
+            _ => None,
+        })
+        .collect();
+    if qualifiers.is_empty() {
+        return Ok(code);
+    }
+    let primary = match code.as_str() {
+        "zt" => "zh",
+        "pb" => "pt",
+        code => code,
+    };
+    Ok(format!("{primary}-{}", qualifiers.join("-")))
+}
+
+/// The name of a language without any parenthesised qualifier, in lowercase.
+fn short_name(language: &KnownLanguage) -> String {
+    language
+        .name
+        .split(" (")
+        .next()
+        .unwrap_or_default()
+        .split(' ')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Find the known languages whose name or code is closest to the input.
+fn suggestions(input: &str) -> Vec<&'static KnownLanguage> {
+    let max_distance = if input.chars().count() > 4 { 3 } else { 2 };
+    let mut scored: Vec<_> = KNOWN_LANGUAGES
+        .iter()
+        .map(|l| {
+            let name = l.name.to_lowercase();
+            let distance = if name.starts_with(input) {
+                0
+            } else {
+                edit_distance(input, &name)
+                    .min(edit_distance(input, l.code))
+                    .min(
+                        l.alpha3
+                            .iter()
+                            .map(|c| edit_distance(input, c))
+                            .min()
+                            .unwrap_or(usize::MAX),
+                    )
+            };
+            (distance, l)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    scored.sort_by_key(|(distance, _)| *distance);
+    scored.into_iter().take(3).map(|(_, l)| l).collect()
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,316 @@
+//! Laying out translations: wrapping their lines to fit the screen, and
+//! spacing their punctuation as the typography of their language expects.
+
+use crate::{hyphenation::Patterns, stats};
+
+/// Punctuation French typography puts a space before.
+const SPACED_PUNCTUATION: [char; 4] = ['?', '!', ':', ';'];
+/// The space French typography puts before punctuation and inside
+/// guillemets, which players won't break a line at.
+const NARROW_NO_BREAK_SPACE: char = '\u{202f}';
+
+/// Rewrap a cue's text so no line is longer than `max` characters, if any is,
+/// balancing the lines' lengths. Dialogue, with each line starting with a
+/// dash, is wrapped a speaker at a time. Words longer than a line, as in
+/// languages written without spaces, are broken between characters.
+///
+/// ```
+/// use subtitle_translate::layout::wrap;
+///
+/// assert_eq!(wrap("Short enough", 42), "Short enough");
+/// assert_eq!(
+///     wrap("I never thought we would see each other again", 30),
+///     "I never thought we would\nsee each other again"
+/// );
+/// ```
+#[must_use]
+pub fn wrap(text: &str, max: usize) -> String {
+    wrap_hyphenated(text, max, &Patterns::default())
+}
+
+/// Rewrap a cue's text as [`wrap`] does, but hyphenate words longer than a
+/// line where the patterns allow, breaking them between characters only
+/// where they don't.
+///
+/// ```
+/// use subtitle_translate::{hyphenation::Patterns, layout::wrap_hyphenated};
+///
+/// let patterns = Patterns::parse("1sch 1fahrt 1ge 1sell");
+/// assert_eq!(
+///     wrap_hyphenated("Die Dampfschifffahrtsgesellschaft", 16, &patterns),
+///     "Die Dampfschiff-\nfahrtsgesell-\nschaft"
+/// );
+/// ```
+#[must_use]
+pub fn wrap_hyphenated(text: &str, max: usize, patterns: &Patterns) -> String {
+    let max = max.max(1);
+    if stats::longest_line(text) <= max {
+        return text.to_string();
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() > 1 && lines.iter().all(|line| line.trim_start().starts_with('-')) {
+        lines
+            .iter()
+            .map(|line| wrap_line(line, max, patterns))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        wrap_line(&lines.join(" "), max, patterns)
+    }
+}
+
+/// Join the lines of a cue into one, so engines translate the whole of what
+/// is said rather than each part of it alone. In dialogue, each speaker's
+/// lines, starting with a dash, are kept on a line of their own. Lines of
+/// languages written without spaces are joined without one.
+///
+/// ```
+/// use subtitle_translate::layout::join_lines;
+///
+/// assert_eq!(join_lines("I never thought\nwe'd meet again"), "I never thought we'd meet again");
+/// assert_eq!(
+///     join_lines("- Who's there?\n- Only me,\nyour friend"),
+///     "- Who's there?\n- Only me, your friend"
+/// );
+/// assert_eq!(join_lines("もう一度\n会えるなんて"), "もう一度会えるなんて");
+/// ```
+#[must_use]
+pub fn join_lines(text: &str) -> String {
+    let mut joined = String::with_capacity(text.len());
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if !joined.is_empty() {
+            if line.starts_with('-') {
+                joined.push('\n');
+            } else if !(joined.chars().next_back().is_some_and(is_unspaced)
+                || line.chars().next().is_some_and(is_unspaced))
+            {
+                joined.push(' ');
+            }
+        }
+        joined.push_str(line);
+    }
+    joined
+}
+
+/// Wrap the translation of a cue joined by [`join_lines`] into as many lines
+/// as the cue had, balancing their lengths. Translations which kept line
+/// breaks, as dialogue does, are left alone.
+///
+/// ```
+/// use subtitle_translate::layout::rewrap;
+///
+/// assert_eq!(
+///     rewrap("Je n'aurais jamais cru qu'on se reverrait", "I never thought\nwe'd meet again"),
+///     "Je n'aurais jamais cru\nqu'on se reverrait"
+/// );
+/// ```
+#[must_use]
+pub fn rewrap(translation: &str, original: &str) -> String {
+    let count = original
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count();
+    if count < 2 || translation.contains('\n') {
+        return translation.to_string();
+    }
+    let words: Vec<String> = translation.split_whitespace().map(str::to_string).collect();
+    let length = stats::characters(&words.join(" "));
+    (length.div_ceil(count)..=length)
+        .map(|width| fill(&words, width))
+        .find(|lines| lines.len() <= count)
+        .map_or_else(|| translation.to_string(), |lines| lines.join("\n"))
+}
+
+/// Whether a character is of a language written without spaces between
+/// words, such as Chinese or Japanese.
+fn is_unspaced(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' | '\u{ff00}'..='\u{ffef}')
+}
+
+/// Wrap one line into as few lines as it needs, the longest as short as it
+/// can be.
+fn wrap_line(line: &str, max: usize, patterns: &Patterns) -> String {
+    let mut words: Vec<String> = Vec::new();
+    for word in line.split_whitespace() {
+        if stats::characters(word) <= max {
+            words.push(word.to_string());
+        } else {
+            words.extend(split_word(word, max, patterns));
+        }
+    }
+    let length = stats::characters(&words.join(" "));
+    let count = length.div_ceil(max).max(1);
+    (length.div_ceil(count)..=max)
+        .map(|width| fill(&words, width))
+        .find(|lines| lines.len() <= count)
+        .unwrap_or_else(|| fill(&words, max))
+        .join("\n")
+}
+
+/// Split a word longer than a line into parts which fit, hyphenating it at
+/// the latest point the patterns allow for each part, or failing that,
+/// breaking it between characters.
+fn split_word(word: &str, max: usize, patterns: &Patterns) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let breaks = patterns.breaks(word);
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while chars.len() - start > max {
+        let end = breaks
+            .iter()
+            .copied()
+            .filter(|end| *end > start && end - start < max)
+            .max();
+        if let Some(end) = end {
+            parts.push(chars[start..end].iter().collect::<String>() + "-");
+            start = end;
+        } else {
+            parts.push(chars[start..start + max].iter().collect());
+            start += max;
+        }
+    }
+    parts.push(chars[start..].iter().collect());
+    parts
+}
+
+/// Fill lines with as many words as fit in `width` characters.
+fn fill(words: &[String], width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in words {
+        if !current.is_empty() && stats::characters(&current) + 1 + stats::characters(word) > width
+        {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+/// Put a narrow no-break space before `?`, `!`, `:` and `;` ending a word,
+/// and inside guillemets, as French typography asks, replacing any other
+/// space there. Colons in times and addresses are left alone.
+///
+/// ```
+/// use subtitle_translate::layout::space_punctuation;
+///
+/// assert_eq!(
+///     space_punctuation("Quoi ? Vraiment!"),
+///     "Quoi\u{202f}? Vraiment\u{202f}!"
+/// );
+/// assert_eq!(space_punctuation("Il est 12:30."), "Il est 12:30.");
+/// assert_eq!(space_punctuation("«Non»"), "«\u{202f}Non\u{202f}»");
+/// ```
+#[must_use]
+pub fn space_punctuation(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let is_space = |c: char| matches!(c, ' ' | '\u{a0}' | NARROW_NO_BREAK_SPACE);
+    let mut spaced = String::with_capacity(text.len());
+    let mut after_guillemet = false;
+    for (idx, &c) in chars.iter().enumerate() {
+        if after_guillemet && is_space(c) {
+            continue;
+        }
+        after_guillemet = false;
+        let ends_word = chars.get(idx + 1).is_none_or(|next| {
+            next.is_whitespace()
+                || SPACED_PUNCTUATION.contains(next)
+                || matches!(next, '<' | '{' | '"' | '»')
+        });
+        let punctuation = SPACED_PUNCTUATION.contains(&c);
+        if c == '»' || (punctuation && ends_word) {
+            spaced.truncate(spaced.trim_end_matches(is_space).len());
+            let doubled = |before: char| punctuation && SPACED_PUNCTUATION.contains(&before);
+            if spaced
+                .chars()
+                .next_back()
+                .is_some_and(|before| !(before.is_whitespace() || doubled(before)))
+            {
+                spaced.push(NARROW_NO_BREAK_SPACE);
+            }
+        }
+        spaced.push(c);
+        if c == '«' {
+            spaced.push(NARROW_NO_BREAK_SPACE);
+            after_guillemet = true;
+        }
+    }
+    spaced
+}
+
+/// Replace straight quotes and apostrophes with the typographic ones a
+/// language uses, such as `„…“` in German or `«…»` in French, and `’` for
+/// apostrophes. Languages without known conventions get English quotes.
+/// Quotes inside tags and override blocks are left alone.
+///
+/// ```
+/// use subtitle_translate::layout::smart_quotes;
+///
+/// assert_eq!(smart_quotes(r#"He said "don't go""#, "en"), "He said “don’t go”");
+/// assert_eq!(smart_quotes(r#"Er sagte "geh nicht""#, "de"), "Er sagte „geh nicht“");
+/// assert_eq!(smart_quotes(r#""C'est 'ça'""#, "fr"), "«C’est “ça”»");
+/// assert_eq!(
+///     smart_quotes(r##"<font color="#FF0000">"Run"</font>"##, "en"),
+///     r##"<font color="#FF0000">“Run”</font>"##
+/// );
+/// ```
+#[must_use]
+pub fn smart_quotes(text: &str, language: &str) -> String {
+    let base = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    // Double quotes, then single quotes, each opening then closing.
+    let [open, close, open_single, close_single] = match base.as_str() {
+        "de" | "cs" | "sk" | "sl" | "bg" | "lt" | "et" => ['„', '“', '‚', '‘'],
+        "pl" | "hu" | "ro" | "nl" => ['„', '”', '‚', '’'],
+        "fr" | "es" | "it" | "pt" | "pb" | "ca" | "gl" | "eu" | "nb" | "el" | "ar" | "fa" => {
+            ['«', '»', '“', '”']
+        }
+        "ru" | "uk" | "az" => ['«', '»', '„', '“'],
+        "sv" | "fi" => ['”', '”', '’', '’'],
+        "da" => ['»', '«', '›', '‹'],
+        "ja" | "zt" => ['「', '」', '『', '』'],
+        _ => ['“', '”', '‘', '’'],
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let mut quoted = String::with_capacity(text.len());
+    // The end of the tag or override block being passed over, whose quotes
+    // are left alone.
+    let mut closing = None;
+    for (idx, &c) in chars.iter().enumerate() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (Some(end), c) if c == end => closing = None,
+            _ => {}
+        }
+        if closing.is_some() {
+            quoted.push(c);
+            continue;
+        }
+        let before = idx.checked_sub(1).map(|before| chars[before]);
+        let after = chars.get(idx + 1).copied();
+        let opens =
+            before.is_none_or(|before| before.is_whitespace() || "([{<>-–—/".contains(before));
+        quoted.push(match c {
+            '"' if opens => open,
+            '"' => close,
+            // Apostrophes, within words or after a letter ending one.
+            '\'' if before.is_some_and(char::is_alphanumeric)
+                && after.is_none_or(|after| after.is_alphabetic() || after.is_whitespace()) =>
+            {
+                '’'
+            }
+            '\'' if opens => open_single,
+            '\'' => close_single,
+            c => c,
+        });
+    }
+    quoted
+}
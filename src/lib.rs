@@ -0,0 +1,88 @@
+//! Translate subtitle files using a LibreTranslate instance.
+//!
+//! Subtitles of any supported format are read, or parsed from memory, into a
+//! list of [`GenericSubtitle`]s, translated in place by a
+//! [`TranslationPipeline`], and written back out in whichever format is
+//! wanted. Engines other than
+//! LibreTranslate can be used by implementing [`Translator`]. A
+//! [`TranslationJob`] does all of this for a file, reporting its progress.
+//!
+//! Only [`libretranslate`] sends requests itself. Without the `cli` and
+//! `libretranslate` features, the library has no networking or async runtime
+//! of its own, so engines can be reached with any HTTP stack by implementing
+//! [`Translator`] over it.
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use subtitle_translate::{
+//!     TranslationPipeline, Translator, api_types::Translation, subtitle, summary::Summary,
+//! };
+//!
+//! /// An engine which shouts every line back, standing in for a real one.
+//! struct Shout;
+//!
+//! impl Translator for Shout {
+//!     async fn translate(
+//!         &self,
+//!         input: String,
+//!         _source: &str,
+//!         _target: &str,
+//!         _alternatives: u32,
+//!     ) -> anyhow::Result<Translation> {
+//!         Ok(Translation {
+//!             translated_text: input.to_uppercase(),
+//!             alternatives: None,
+//!             detected_language: None,
+//!             cached: false,
+//!         })
+//!     }
+//! }
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let translator = Shout;
+//! let mut subtitles = subtitle::read(Path::new("film.srt"))?;
+//! TranslationPipeline::new(translator, "de")
+//!     .translate(&mut subtitles, &mut Summary::new(), |_| {})
+//!     .await?;
+//! subtitle::write(&subtitles, Path::new("film.de.srt"))?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![deny(unsafe_code)]
+#![deny(clippy::pedantic)]
+
+pub mod align;
+pub mod api_types;
+pub mod cancel;
+pub mod casing;
+pub mod colors;
+pub mod confidence;
+pub mod entities;
+pub mod guidelines;
+pub mod hyphenation;
+pub mod job;
+pub mod languages;
+pub mod layout;
+#[cfg(feature = "libretranslate")]
+pub mod libretranslate;
+pub mod microdvd;
+pub mod numbers;
+pub mod pipeline;
+pub mod qa;
+pub mod repair;
+pub mod segment;
+pub mod stats;
+pub mod substation;
+pub mod subtitle;
+pub mod summary;
+pub mod timing;
+pub mod translator;
+
+pub use job::TranslationJob;
+#[cfg(feature = "libretranslate")]
+pub use libretranslate::LibreTranslate;
+pub use pipeline::TranslationPipeline;
+pub use subtitle::GenericSubtitle;
+pub use translator::Translator;
@@ -0,0 +1,438 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use web_time::Instant;
+
+use crate::{
+    Translator,
+    api_types::{DetectQuery, DetectedLanguage, Language, Query, Translation, TranslationResult},
+    languages,
+};
+
+/// A connection to one or more LibreTranslate instances, which requests are
+/// shared between.
+#[derive(Clone)]
+pub struct LibreTranslate {
+    client: Client,
+    instances: Arc<[Mutex<Health>]>,
+    balancing: Balancing,
+    /// Counts requests, to choose the instance whose turn it is.
+    turn: Arc<AtomicUsize>,
+    api_key: Option<String>,
+    timeout: Option<Duration>,
+    /// The failures in a row after which an instance is rested.
+    trip_after: usize,
+    /// How long an instance is rested for.
+    cool_down: Duration,
+}
+
+/// How requests are shared between several instances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Balancing {
+    /// Send to each instance in turn.
+    #[default]
+    RoundRobin,
+    /// Send to the instance with the fewest requests waiting on it, so faster
+    /// instances are sent more.
+    LeastInFlight,
+}
+
+/// How the requests sent to an instance have gone.
+#[derive(Clone, Debug, Default)]
+pub struct Health {
+    /// The URL of the instance's translation API.
+    pub instance: String,
+    pub requests: usize,
+    pub failures: usize,
+    /// The failures in a row of the instance itself, such as it not being
+    /// reached, timing out or returning a server error.
+    pub consecutive_failures: usize,
+    /// The requests waiting on a response.
+    pub in_flight: usize,
+    /// The total time taken by the requests which succeeded.
+    pub latency: Duration,
+    /// Until when the instance is rested, after failing too many times in a
+    /// row, rather than being sent requests.
+    pub resting_until: Option<Instant>,
+}
+
+impl Health {
+    /// The mean time taken by the requests which succeeded.
+    #[must_use]
+    pub fn mean_latency(&self) -> Option<Duration> {
+        let succeeded = u32::try_from(self.requests - self.failures).ok()?;
+        self.latency.checked_div(succeeded)
+    }
+}
+
+/// Counts a request as in flight until dropped, even if it is cancelled.
+struct InFlight<'a>(&'a Mutex<Health>);
+
+impl<'a> InFlight<'a> {
+    /// Start a request, which is the only one sent to a resting instance
+    /// until the cool-down ends again, to probe whether it is back.
+    fn start(health: &'a Mutex<Health>, cool_down: Duration) -> Self {
+        let in_flight = Self(health);
+        let mut health = in_flight.health();
+        health.requests += 1;
+        health.in_flight += 1;
+        if health.resting_until.is_some() {
+            tracing::debug!("Probing whether {} is back", health.instance);
+            health.resting_until = Some(Instant::now() + cool_down);
+        }
+        drop(health);
+        in_flight
+    }
+
+    fn health(&self) -> std::sync::MutexGuard<'_, Health> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.health().in_flight -= 1;
+    }
+}
+
+impl LibreTranslate {
+    #[must_use]
+    pub fn new(instance: String, api_key: Option<String>) -> Self {
+        Self::with_instances(vec![instance], api_key, Balancing::default())
+    }
+
+    /// Share requests between several instances, which should run the same
+    /// version of LibreTranslate so they translate alike.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no instances are given.
+    #[must_use]
+    pub fn with_instances(
+        instances: Vec<String>,
+        api_key: Option<String>,
+        balancing: Balancing,
+    ) -> Self {
+        assert!(!instances.is_empty(), "No LibreTranslate instances given");
+        Self {
+            client: Client::new(),
+            instances: instances
+                .into_iter()
+                .map(|instance| {
+                    Mutex::new(Health {
+                        instance,
+                        ..Health::default()
+                    })
+                })
+                .collect(),
+            balancing,
+            turn: Arc::new(AtomicUsize::new(0)),
+            api_key,
+            timeout: None,
+            trip_after: 3,
+            cool_down: Duration::from_secs(30),
+        }
+    }
+
+    /// Give up on requests which take longer than `timeout`, counting them as
+    /// failures of the instance.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Rest an instance for `cool_down` once `failures` requests to it in a
+    /// row have failed, sending to the other instances meanwhile, as well as
+    /// any request which failed on it. A single request is then sent to probe
+    /// whether it is back. By default this is after 3 failures, for 30
+    /// seconds. A single instance is never rested.
+    #[must_use]
+    pub fn circuit_breaker(mut self, failures: usize, cool_down: Duration) -> Self {
+        self.trip_after = failures.max(1);
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// How the requests sent to each instance have gone.
+    #[must_use]
+    pub fn health(&self) -> Vec<Health> {
+        self.instances
+            .iter()
+            .map(|health| {
+                health
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone()
+            })
+            .collect()
+    }
+
+    /// Translate a single piece of text, optionally requesting a number of
+    /// alternative translations. Languages may be given as BCP-47 tags, such
+    /// as `pt-BR`, which are sent as LibreTranslate's own codes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the instance can't be reached or returns an error.
+    pub async fn translate(
+        &self,
+        input: String,
+        source: &str,
+        target: &str,
+        alternatives: u32,
+    ) -> anyhow::Result<Translation> {
+        if input.is_empty() {
+            return Ok(Translation {
+                translated_text: String::new(),
+                alternatives: None,
+                detected_language: None,
+                cached: false,
+            });
+        }
+
+        let body = Query {
+            q: input,
+            source: code(source),
+            target: code(target),
+            alternatives,
+            api_key: self.api_key.clone(),
+            ..Default::default()
+        };
+        tracing::debug!("Sending: {body:?}");
+        let r: TranslationResult = self
+            .send(|client, instance| Ok(client.post(instance).json(&body)))
+            .await?;
+        tracing::debug!("Response: {r:?}");
+        match r {
+            TranslationResult::Err(e) => Err(anyhow::anyhow!(e.error)),
+            TranslationResult::Ok(r) => Ok(r),
+        }
+    }
+
+    /// Detect the language of some text, most likely first.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the instance can't be reached or returns an error.
+    pub async fn detect(&self, input: String) -> anyhow::Result<Vec<DetectedLanguage>> {
+        let body = DetectQuery {
+            q: input,
+            api_key: self.api_key.clone(),
+        };
+        self.send(|client, instance| Ok(client.post(endpoint(instance, "detect")?).json(&body)))
+            .await
+    }
+
+    /// Fetch the languages supported by the instance.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the instance can't be reached or returns an error.
+    pub async fn languages(&self) -> anyhow::Result<Vec<Language>> {
+        self.send(|client, instance| {
+            let url = endpoint(instance, "languages")?;
+            tracing::debug!("Fetching languages from {url}");
+            Ok(client.get(url))
+        })
+        .await
+    }
+
+    /// Send a request, built for the URL of an instance's translation API, to
+    /// the instance whose turn it is. If the instance fails, it is sent to
+    /// each other instance which isn't resting until one doesn't.
+    async fn send<R: DeserializeOwned>(
+        &self,
+        request: impl Fn(&Client, &str) -> anyhow::Result<RequestBuilder>,
+    ) -> anyhow::Result<R> {
+        let mut tried = Vec::new();
+        let mut chosen = self.choose(&tried).unwrap_or_default();
+        loop {
+            tried.push(chosen);
+            let result = self.send_to(chosen, &request).await;
+            match &result {
+                Err(e) if is_fault(e) => {}
+                _ => return result,
+            }
+            let Some(next) = self.choose(&tried) else {
+                return result;
+            };
+            chosen = next;
+        }
+    }
+
+    /// Send a request to an instance, keeping track of how it goes.
+    async fn send_to<R: DeserializeOwned>(
+        &self,
+        chosen: usize,
+        request: impl Fn(&Client, &str) -> anyhow::Result<RequestBuilder>,
+    ) -> anyhow::Result<R> {
+        let in_flight = InFlight::start(&self.instances[chosen], self.cool_down);
+        let instance = in_flight.health().instance.clone();
+        let started = Instant::now();
+        let result = async {
+            let mut request = request(&self.client, &instance)?;
+            if let Some(timeout) = self.timeout {
+                request = request.timeout(timeout);
+            }
+            let r = request.send().await?;
+            tracing::trace!("HTTP Response: {r:?}");
+            Ok(check_status(r).await?.json().await?)
+        }
+        .await;
+        let mut health = in_flight.health();
+        match &result {
+            Ok(_) => health.latency += started.elapsed(),
+            Err(_) => health.failures += 1,
+        }
+        match &result {
+            Err(e) if is_fault(e) => {
+                tracing::debug!("Request to {instance} failed");
+                health.consecutive_failures += 1;
+                if self.instances.len() > 1 && health.consecutive_failures >= self.trip_after {
+                    if health.resting_until.is_none() {
+                        tracing::warn!(
+                            "{instance} failed {} times in a row, so is rested for {}s",
+                            health.consecutive_failures,
+                            self.cool_down.as_secs()
+                        );
+                    }
+                    health.resting_until = Some(Instant::now() + self.cool_down);
+                }
+            }
+            // The instance answered, even if it was with an error.
+            _ => {
+                health.consecutive_failures = 0;
+                if health.resting_until.take().is_some() {
+                    tracing::info!("{instance} is back");
+                }
+            }
+        }
+        drop(health);
+        result
+    }
+
+    /// The instance to send the next request to, other than those already
+    /// tried, passing over those resting. If every instance is resting, the
+    /// first try goes to the one which will be back soonest rather than
+    /// failing without trying.
+    fn choose(&self, tried: &[usize]) -> Option<usize> {
+        let now = Instant::now();
+        let count = self.instances.len();
+        let turn = self.turn.fetch_add(1, Ordering::Relaxed) % count;
+        // Starting from the instance whose turn it is, so ties are shared.
+        let candidates: Vec<_> = (0..count)
+            .map(|offset| (turn + offset) % count)
+            .filter(|idx| !tried.contains(idx))
+            .map(|idx| {
+                let health = self.instances[idx]
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                (idx, health.in_flight, health.resting_until)
+            })
+            .collect();
+        let mut available = candidates
+            .iter()
+            .filter(|(_, _, resting_until)| resting_until.is_none_or(|until| until <= now));
+        let chosen = match self.balancing {
+            Balancing::RoundRobin => available.next(),
+            Balancing::LeastInFlight => available.min_by_key(|(_, in_flight, _)| *in_flight),
+        };
+        match chosen {
+            Some((idx, _, _)) => Some(*idx),
+            None if tried.is_empty() => candidates
+                .iter()
+                .min_by_key(|(_, _, resting_until)| *resting_until)
+                .map(|(idx, _, _)| *idx),
+            None => None,
+        }
+    }
+}
+
+/// Whether an error is the fault of the instance, rather than the request,
+/// such as it not being reached, timing out or returning a server error.
+fn is_fault(error: &anyhow::Error) -> bool {
+    error.chain().any(|e| {
+        e.is::<reqwest::Error>()
+            || e.downcast_ref::<StatusError>().is_some_and(|e| {
+                e.status.is_server_error() || e.status == StatusCode::TOO_MANY_REQUESTS
+            })
+    })
+}
+
+/// Get the URL of another endpoint on the same instance.
+fn endpoint(instance: &str, name: &str) -> anyhow::Result<Url> {
+    Url::parse(instance)
+        .and_then(|u| u.join(name))
+        .context("Invalid LibreTranslate instance URL")
+}
+
+impl Translator for LibreTranslate {
+    fn translate(
+        &self,
+        input: String,
+        source: &str,
+        target: &str,
+        alternatives: u32,
+    ) -> impl Future<Output = anyhow::Result<Translation>> + Send {
+        LibreTranslate::translate(self, input, source, target, alternatives)
+    }
+
+    /// LibreTranslate's own code, which drops any region or script, except
+    /// for Brazilian Portuguese and traditional Chinese.
+    fn language_code(&self, language: &str) -> String {
+        code(language)
+    }
+}
+
+/// LibreTranslate's code for a language, or the language as it is given if
+/// it can't be resolved, such as `auto`.
+fn code(language: &str) -> String {
+    languages::resolve(language).unwrap_or_else(|_| language.to_string())
+}
+
+/// The longest part of an error response's body put in the error.
+const MAX_ERROR_BODY: usize = 200;
+
+/// An error response, with the start of its body.
+#[derive(Debug)]
+struct StatusError {
+    status: StatusCode,
+    body: String,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP {}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Fail with the response's status and body if it is an error, so the reason
+/// can be seen even if the body isn't the JSON error LibreTranslate gives.
+async fn check_status(r: Response) -> anyhow::Result<Response> {
+    let status = r.status();
+    if status.is_success() {
+        return Ok(r);
+    }
+    let body = r.text().await.unwrap_or_default();
+    let mut body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some((end, _)) = body.char_indices().nth(MAX_ERROR_BODY) {
+        body.truncate(end);
+        body.push('…');
+    }
+    Err(StatusError { status, body }.into())
+}
@@ -0,0 +1,74 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// How many rotated log files are kept beside the current one.
+pub const KEPT_FILES: usize = 5;
+
+/// A log file which is moved aside once it would grow beyond a size, keeping
+/// the last few as `<name>.1` (the newest) to `<name>.5`.
+pub struct SizeRollingFile {
+    path: PathBuf,
+    max_size: u64,
+    file: File,
+    len: u64,
+}
+
+impl SizeRollingFile {
+    /// Open the log file for appending, rotating it once it reaches
+    /// `max_size` bytes.
+    pub fn open(path: &Path, max_size: u64) -> io::Result<Self> {
+        let file = open_append(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_size,
+            file,
+            len,
+        })
+    }
+
+    /// Shift the kept files up by one, dropping the oldest, and start a new
+    /// file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for n in (1..KEPT_FILES).rev() {
+            let from = numbered(&self.path, n);
+            if from.exists() {
+                std::fs::rename(&from, numbered(&self.path, n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, numbered(&self.path, 1))?;
+        self.file = open_append(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len > 0 && self.len + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// The path of the `n`th rotated file.
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
@@ -1,227 +1,211 @@
 #![deny(unsafe_code)]
 #![deny(clippy::pedantic)]
 
-#[allow(unused)]
-mod api_types;
-
-use std::{path::PathBuf, sync::Arc};
+mod batch;
+mod cache;
+mod checkpoint;
+mod cli;
+mod color_tags;
+mod config;
+mod control_codes;
+mod convert;
+mod cost;
+mod diff;
+mod dirs;
+mod doctor;
+mod exit_code;
+mod glossary;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod history;
+mod honorifics;
+mod hooks;
+mod inspect;
+mod interactive;
+mod log_file;
+mod merge;
+mod opensubtitles;
+mod parts;
+mod picker;
+#[cfg(feature = "plugins")]
+mod plugin;
+mod preview;
+mod progress;
+mod qa_report;
+mod report;
+mod retime;
+mod review;
+mod ruby;
+mod sample;
+mod scan;
+#[cfg(feature = "lua")]
+mod script;
+mod self_update;
+mod serve;
+mod sheet;
+mod submit;
+mod symbols;
+mod sync;
+mod translate;
+mod validate;
+mod whisper;
+
+use std::{path::Path, process::ExitCode};
 
 use anyhow::Context;
-use api_types::{Query, Translation, TranslationResult};
-use aspasia::{Moment, SubRipSubtitle, Subtitle, TimedSubtitleFile, subrip::SubRipEvent};
-use clap::Parser;
-use clap_verbosity_flag::Verbosity;
-use futures::future::TryJoinAll;
-use reqwest::Client;
-use tokio::sync::Mutex;
-
-#[derive(Parser)]
-struct Args {
-    /// The URL of the LibreTranslate instance's translation API
-    #[arg(short = 'L', long, default_value = "http://localhost:5000/translate")]
-    libretranslate_instance: String,
-
-    /// The API key for the LibreTranslate instance, if it is needed
-    #[arg(short = 'A', long)]
-    libretranslate_apikey: Option<String>,
-
-    /// Set the size of the chunk used for parallel processing
-    #[arg(short = 'C', long, default_value_t = 5)]
-    chunk_size: usize,
+use clap::CommandFactory;
+use cli::{Cli, Command, EngineArgs, LogFormat, LogRotation};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    Layer, Registry,
+    filter::LevelFilter,
+    fmt::{
+        self, MakeWriter,
+        format::{DefaultFields, Format},
+    },
+    prelude::*,
+};
 
-    /// The two letter code for the source language.
-    #[arg(short = 'f', long, default_value = "auto")]
-    language_from: String,
-
-    /// The source subtitle file
-    #[arg(index = 1)]
-    source_file: PathBuf,
-
-    /// The two letter code for the target language.
-    #[arg(index = 2)]
-    language_to: String,
+#[tokio::main]
+async fn main() -> ExitCode {
+    let (cli, compat) = Cli::parse_with_compat();
+    let _guard = match init_logging(&cli) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            return ExitCode::FAILURE;
+        }
+    };
 
-    /// The destination subtitle file
-    #[arg(index = 3)]
-    destination_file: PathBuf,
+    if compat {
+        tracing::warn!(
+            "Running without a subcommand is deprecated, use `subtitle-translate translate` instead"
+        );
+    }
 
-    #[command(flatten)]
-    verbose: Verbosity,
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(exit_code::exit_code(&e))
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-struct GenericSubtitle {
-    text: String,
-    start: Moment,
-    end: Moment,
-    coordinates: Option<String>,
-}
+/// Set up logging to the terminal and, if requested, a log file. The returned
+/// guard must be held until exit so buffered messages reach the file.
+fn init_logging(cli: &Cli) -> anyhow::Result<Option<WorkerGuard>> {
+    let mut layers = vec![log_layer(fmt::layer(), cli.log_format)];
+
+    let mut guard = None;
+    if let Some(path) = &cli.log_file {
+        let (writer, file_guard) = if let Some(megabytes) = cli.log_max_size {
+            let file = log_file::SizeRollingFile::open(path, megabytes.saturating_mul(1024 * 1024))
+                .context("Failed to open log file")?;
+            tracing_appender::non_blocking(file)
+        } else {
+            let rotation = match cli.log_rotation {
+                LogRotation::Never => Rotation::NEVER,
+                LogRotation::Hourly => Rotation::HOURLY,
+                LogRotation::Daily => Rotation::DAILY,
+            };
+            let file_name = path
+                .file_name()
+                .context("The log file must be a file name")?;
+            let appender = RollingFileAppender::builder()
+                .rotation(rotation)
+                .filename_prefix(file_name.to_string_lossy())
+                .build(path.parent().unwrap_or(Path::new(".")))
+                .context("Failed to open log file")?;
+            tracing_appender::non_blocking(appender)
+        };
+        let layer = fmt::layer().with_ansi(false).with_writer(writer);
+        layers.push(log_layer(layer, cli.log_format));
+        guard = Some(file_guard);
+    }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    tracing_subscriber::fmt()
-        .with_max_level(args.verbose)
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(LevelFilter::from(cli.verbose))
         .init();
+    Ok(guard)
+}
 
-    // Step 1: Read source subs
-    tracing::info!("Reading source subtitles…");
-    let subs =
-        TimedSubtitleFile::new(args.source_file).context("Failed to read source subtitles")?;
-    tracing::debug!("Read subtitles file");
-    let subtitles = Arc::new(Mutex::new(timed_subtitle_file_events_to_generic(subs)));
-
-    // Step 2: Translate line by line, asynchronously in batches
-    tracing::info!("Translating…");
-    let source = args.language_from.to_ascii_lowercase();
-    let target = args.language_to.to_ascii_lowercase();
-    let client = Client::new();
-    {
-        let subtitles = subtitles.clone();
-        let mut subs = subtitles.lock().await;
-        for (chunk_idx, chunk) in subs.chunks_mut(args.chunk_size).enumerate() {
-            let handles = chunk.iter().cloned().enumerate().map(|(idx, item)| {
-                let inst = args.libretranslate_instance.clone();
-                let key = args.libretranslate_apikey.clone();
-                let source = source.clone();
-                let target = target.clone();
-                let client = client.clone();
-                let input = item.text.clone();
-                let span = tracing::debug_span!("translation", chunk_idx = chunk_idx, idx = idx, input = input);
-                tokio::spawn(async move {
-                    let _ = span.enter();
-                    if input.is_empty() {
-                        return Ok(Translation {
-                            translated_text: String::new(),
-                            alternatives: None,
-                            detected_language: None,
-                        });
-                    }
-
-                    let body = Query {
-                        q: input,
-                        source: source.clone(),
-                        target: target.clone(),
-                        api_key: key.clone(),
-                        ..Default::default()
-                    };
-                    tracing::debug!("Sending: {}", serde_json::to_string(&body).unwrap());
-                    let res = client.post(inst).json(&body).send().await;
-                    match res {
-                        Ok(r) => {
-                            tracing::trace!("HTTP Response: {r:?}");
-                            let r = r.json::<TranslationResult>().await?;
-                            tracing::debug!("Response: {r:?}");
-                            match r {
-                                TranslationResult::Err(e) => Err(anyhow::anyhow!(e.error)),
-                                TranslationResult::Ok(r) => Ok(r),
-                            }
-                        }
-                        Err(e) => Err(anyhow::Error::from(e)),
-                    }
-                })
-            });
+/// Use the given format for a logging layer.
+fn log_layer<W>(
+    layer: fmt::Layer<Registry, DefaultFields, Format, W>,
+    format: LogFormat,
+) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().with_current_span(true).boxed(),
+    }
+}
 
-            let results = handles.collect::<TryJoinAll<_>>().await?;
-            for (idx, result) in results.into_iter().enumerate() {
-                let translation = result.context("Failed to translate line")?;
-                chunk[idx].text = translation.translated_text;
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let quiet = cli.verbose.is_silent();
+    match cli.command {
+        Command::Translate(args) => translate::run(&cli.engine, *args, quiet).await,
+        Command::Batch(args) => batch::run(&cli.engine, &args).await,
+        Command::ScanLibrary(args) => scan::run(&cli.engine, &args).await,
+        Command::Serve(args) => serve::run(&cli.engine, &args).await,
+        Command::Submit(args) => submit::run(&args).await,
+        Command::Review(args) => review::run(&cli.engine, args).await,
+        Command::Convert(args) => convert::run(&args),
+        Command::Diff(args) => diff::run(&args),
+        Command::Merge(args) => merge::run(&args),
+        Command::ExportCsv(args) => sheet::export(&args),
+        Command::ImportCsv(args) => sheet::import(&args),
+        Command::Timing(command) => retime::run(&command),
+        Command::Split(args) => parts::split(&args),
+        Command::Concat(args) => parts::concat(&args),
+        Command::Sample(args) => sample::run(&cli.engine, &args).await,
+        Command::Inspect(args) => inspect::run(&cli.engine, args).await,
+        Command::Validate(args) => validate::run(&args),
+        Command::Doctor(args) => doctor::run(&cli.engine, &args).await,
+        Command::Cache(command) => cache::run(command),
+        Command::History(command) => history::run(&command),
+        Command::Glossary(command) => glossary::run(command),
+        Command::ListLanguages => list_languages(&cli.engine).await,
+        Command::SelfUpdate(args) => self_update::run(&args).await,
+        Command::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+            Ok(())
+        }
+        Command::Mangen { out_dir } => {
+            if let Some(out_dir) = out_dir {
+                clap_mangen::generate_to(Cli::command(), out_dir)
+                    .context("Failed to write man pages")?;
+            } else {
+                clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())?;
             }
+            Ok(())
         }
     }
-
-    // Step 3: Write final file
-    tracing::info!("Writing translated subtitles…");
-    let real_target = {
-        let mut p = args.destination_file;
-        p.set_extension("srt");
-        p
-    };
-    tracing::debug!("Real destination is {real_target:?}");
-
-    tracing::debug!("Converting subtitles back into SRT events");
-    let mut events = vec![];
-    for (idx, subtitle) in subtitles.lock().await.iter().enumerate() {
-        events.push(SubRipEvent {
-            line_number: idx + 1,
-            text: subtitle.text.clone(),
-            start: subtitle.start,
-            end: subtitle.end,
-            coordinates: subtitle.coordinates.clone(),
-        });
-    }
-
-    let mut srt = SubRipSubtitle::from_events(events);
-    srt.renumber();
-    srt.export(real_target)
-        .context("Failed to write destination subtitle file")?;
-
-    Ok(())
 }
 
-fn timed_subtitle_file_events_to_generic(subs: TimedSubtitleFile) -> Vec<GenericSubtitle> {
-    let mut subtitles = vec![];
-    match subs {
-        TimedSubtitleFile::Ass(ass) => subtitles.append(
-            &mut ass
-                .events()
-                .iter()
-                .map(|ev| GenericSubtitle {
-                    text: ev.text.clone(),
-                    start: ev.start,
-                    end: ev.end,
-                    coordinates: None,
-                })
-                .collect(),
-        ),
-        TimedSubtitleFile::MicroDvd(dvd) => subtitles.append(
-            &mut dvd
-                .events()
-                .iter()
-                .map(|ev| GenericSubtitle {
-                    text: ev.text.clone(),
-                    start: ev.start,
-                    end: ev.end,
-                    coordinates: None,
-                })
-                .collect(),
-        ),
-        TimedSubtitleFile::Ssa(ssa) => subtitles.append(
-            &mut ssa
-                .events()
-                .iter()
-                .map(|ev| GenericSubtitle {
-                    text: ev.text.clone(),
-                    start: ev.start,
-                    end: ev.end,
-                    coordinates: None,
-                })
-                .collect(),
-        ),
-        TimedSubtitleFile::SubRip(srt) => subtitles.append(
-            &mut srt
-                .events()
-                .iter()
-                .map(|ev| GenericSubtitle {
-                    text: ev.text.clone(),
-                    start: ev.start,
-                    end: ev.end,
-                    coordinates: ev.coordinates.clone(),
-                })
-                .collect(),
-        ),
-        TimedSubtitleFile::WebVtt(vtt) => subtitles.append(
-            &mut vtt
-                .events()
-                .iter()
-                .map(|ev| GenericSubtitle {
-                    text: ev.text.clone(),
-                    start: ev.start,
-                    end: ev.end,
-                    coordinates: None,
-                })
-                .collect(),
-        ),
+async fn list_languages(engine: &EngineArgs) -> anyhow::Result<()> {
+    let languages = engine
+        .translator()
+        .languages()
+        .await
+        .context("Failed to fetch supported languages")?;
+    let name_width = languages.iter().map(|l| l.name.len()).max().unwrap_or(0);
+    for language in languages {
+        println!(
+            "{:<6}{:<name_width$}  {}",
+            language.code,
+            language.name,
+            language.targets.join(", ")
+        );
     }
-    subtitles
+    Ok(())
 }
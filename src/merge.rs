@@ -0,0 +1,55 @@
+use anyhow::Context;
+
+use subtitle_translate::{
+    align::{Pair, align},
+    subtitle::{self, GenericSubtitle},
+};
+
+use crate::{
+    cli::MergeArgs,
+    exit_code::{Failure, WithFailure},
+};
+
+/// Combine two subtitle files into one bilingual file, showing the text of
+/// cues shown at about the same time together, the first file's above the
+/// second's.
+pub fn run(args: &MergeArgs) -> anyhow::Result<()> {
+    if args.output.exists() && !args.overwrite {
+        anyhow::bail!(
+            "Destination file {} already exists, pass --overwrite to replace it",
+            args.output.display()
+        );
+    }
+
+    let read = |path| {
+        subtitle::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+            .failure(Failure::Parse)
+    };
+    let (top, bottom) = (read(&args.top)?, read(&args.bottom)?);
+
+    let merged: Vec<GenericSubtitle> = align(&top, &bottom)
+        .into_iter()
+        .map(|pair| match pair {
+            Pair::Both(i, j) => {
+                let mut subtitle = top[i].clone();
+                if !bottom[j].text.is_empty() {
+                    subtitle.text = format!("{}\n{}", subtitle.text, bottom[j].text);
+                }
+                subtitle
+            }
+            Pair::Left(i) => top[i].clone(),
+            Pair::Right(j) => bottom[j].clone(),
+        })
+        .collect();
+    tracing::info!(
+        "Merged {} and {} cue(s) into {}",
+        top.len(),
+        bottom.len(),
+        merged.len()
+    );
+
+    subtitle::write(&merged, &args.output)
+        .context("Failed to write destination subtitle file")
+        .failure(Failure::Write)
+}
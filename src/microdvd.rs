@@ -0,0 +1,153 @@
+//! MicroDVD control codes, such as `{y:i}` for italics or `{c:$0000FF}` for
+//! red, which apply to the line they are on, or to the whole cue if their
+//! letter is a capital.
+
+/// Remove the control codes from each line of a cue's text, so they aren't
+/// translated, returning the text and the codes taken from each line.
+///
+/// ```
+/// use subtitle_translate::microdvd::strip_codes;
+///
+/// let (text, codes) = strip_codes("{y:i}Hello\nthere{c:$0000FF}");
+/// assert_eq!(text, "Hello\nthere");
+/// assert_eq!(codes, [vec!["{y:i}"], vec!["{c:$0000FF}"]]);
+/// ```
+#[must_use]
+pub fn strip_codes(text: &str) -> (String, Vec<Vec<String>>) {
+    let mut codes = Vec::new();
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let mut line_codes = Vec::new();
+            let mut plain = String::with_capacity(line.len());
+            let mut rest = line;
+            while let Some(start) = rest.find('{') {
+                plain.push_str(&rest[..start]);
+                rest = &rest[start..];
+                if let Some(len) = code_len(rest) {
+                    line_codes.push(rest[..len].to_string());
+                    rest = &rest[len..];
+                } else {
+                    plain.push('{');
+                    rest = &rest[1..];
+                }
+            }
+            plain.push_str(rest);
+            if !line_codes.is_empty() {
+                plain = plain.trim().to_string();
+            }
+            codes.push(line_codes);
+            plain
+        })
+        .collect();
+    (lines.join("\n"), codes)
+}
+
+/// Put the codes taken by [`strip_codes`] back at the start of each line of
+/// the translated text. If the translation has a different number of lines,
+/// they are all put at the start of its first line.
+///
+/// ```
+/// use subtitle_translate::microdvd::restore_codes;
+///
+/// let codes = [vec!["{y:i}".to_string()], vec![]];
+/// assert_eq!(restore_codes("Hallo\nda", &codes), "{y:i}Hallo\nda");
+/// assert_eq!(restore_codes("Hallo da", &codes), "{y:i}Hallo da");
+/// ```
+#[must_use]
+pub fn restore_codes(text: &str, codes: &[Vec<String>]) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    if lines.len() == codes.len() {
+        return lines
+            .iter()
+            .zip(codes)
+            .map(|(line, codes)| codes.concat() + line)
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    let mut all: Vec<&String> = Vec::new();
+    for code in codes.iter().flatten() {
+        if !all.contains(&code) {
+            all.push(code);
+        }
+    }
+    all.into_iter().map(String::as_str).collect::<String>() + text
+}
+
+/// Write the control codes in a cue's text as the formatting tags other
+/// formats use, for italics, bold, underlining and colours. Codes with no
+/// tag, such as for fonts and positions, are dropped.
+///
+/// ```
+/// use subtitle_translate::microdvd::to_tags;
+///
+/// assert_eq!(to_tags("{y:i}Hello\nthere"), "<i>Hello</i>\nthere");
+/// assert_eq!(
+///     to_tags("{C:$0000FF}Stop!\nNow!"),
+///     "<font color=\"#FF0000\">Stop!</font>\n<font color=\"#FF0000\">Now!</font>"
+/// );
+/// ```
+#[must_use]
+pub fn to_tags(text: &str) -> String {
+    let (plain, codes) = strip_codes(text);
+    let whole_cue: Vec<&String> = codes
+        .iter()
+        .flatten()
+        .filter(|code| code[1..].starts_with(|c: char| c.is_ascii_uppercase()))
+        .collect();
+    plain
+        .split('\n')
+        .zip(&codes)
+        .map(|(line, line_codes)| {
+            let mut tags: Vec<(String, &str)> = Vec::new();
+            for code in whole_cue.iter().copied().chain(
+                line_codes
+                    .iter()
+                    .filter(|code| code[1..].starts_with(|c: char| c.is_ascii_lowercase())),
+            ) {
+                tags.extend(code_tags(code));
+            }
+            let open: String = tags.iter().map(|(open, _)| open.as_str()).collect();
+            let close: String = tags.iter().rev().map(|(_, close)| *close).collect();
+            format!("{open}{line}{close}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The length of the control code at the start of `text`, if there is one.
+fn code_len(text: &str) -> Option<usize> {
+    let mut chars = text.chars();
+    chars.next().filter(|c| *c == '{')?;
+    chars.next().filter(char::is_ascii_alphabetic)?;
+    chars.next().filter(|c| *c == ':')?;
+    let end = text.find('}')?;
+    (!text[1..end].contains('{')).then_some(end + 1)
+}
+
+/// The opening and closing tags a control code is written as.
+fn code_tags(code: &str) -> Vec<(String, &'static str)> {
+    let value = &code[3..code.len() - 1];
+    match code[1..2].to_ascii_lowercase().as_str() {
+        "y" => value
+            .chars()
+            .filter_map(|style| match style.to_ascii_lowercase() {
+                'i' => Some(("<i>".to_string(), "</i>")),
+                'b' => Some(("<b>".to_string(), "</b>")),
+                'u' => Some(("<u>".to_string(), "</u>")),
+                _ => None,
+            })
+            .collect(),
+        "c" => {
+            // Colours are written blue, green, red.
+            let bgr = value.trim_start_matches('$');
+            if bgr.len() == 6 && bgr.chars().all(|c| c.is_ascii_hexdigit()) {
+                let rgb = format!("{}{}{}", &bgr[4..6], &bgr[2..4], &bgr[0..2]);
+                vec![(format!("<font color=\"#{rgb}\">"), "</font>")]
+            } else {
+                vec![]
+            }
+        }
+        _ => vec![],
+    }
+}
@@ -0,0 +1,236 @@
+//! Writing numbers and units in translations the way the target language
+//! does, which engines are inconsistent about.
+
+/// How a language writes numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Convention {
+    decimal: char,
+    group: char,
+}
+
+/// An imperial unit, what it is converted to, and how.
+type Unit = (&'static str, &'static str, fn(f64) -> f64);
+
+const UNITS: &[Unit] = &[
+    ("mph", "km/h", |v| v * 1.609_344),
+    ("°F", "°C", |v| (v - 32.0) * 5.0 / 9.0),
+    ("lbs", "kg", |v| v * 0.453_592_37),
+    ("lb", "kg", |v| v * 0.453_592_37),
+    ("oz", "g", |v| v * 28.349_523),
+    ("ft", "m", |v| v * 0.3048),
+    ("gal", "l", |v| v * 3.785_411_8),
+];
+
+impl Convention {
+    fn for_language(language: &str) -> Self {
+        let base = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let (decimal, group) = match base.as_str() {
+            "fr" => (',', '\u{202F}'),
+            "bg" | "cs" | "et" | "fi" | "hu" | "lt" | "lv" | "nb" | "pl" | "ru" | "sk" | "sv"
+            | "uk" => (',', '\u{A0}'),
+            "az" | "ca" | "da" | "de" | "el" | "es" | "eu" | "gl" | "id" | "it" | "nl" | "pt"
+            | "ro" | "sl" | "sq" | "tr" => (',', '.'),
+            _ => ('.', ','),
+        };
+        Self { decimal, group }
+    }
+
+    /// Whether a character separates groups of digits in this convention.
+    fn is_group(self, c: char) -> bool {
+        c == self.group || (self.group.is_whitespace() && matches!(c, ' ' | '\u{A0}' | '\u{202F}'))
+    }
+}
+
+/// A number as written, without its separators.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Number {
+    integer: String,
+    fraction: Option<String>,
+    grouped: bool,
+}
+
+impl Number {
+    /// Read a number written in a convention.
+    fn parse(token: &str, convention: Convention) -> Option<Self> {
+        let (integer, fraction) = match token.split_once(convention.decimal) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (token, None),
+        };
+        let groups: Vec<&str> = integer.split(|c| convention.is_group(c)).collect();
+        let well_grouped = groups.iter().enumerate().all(|(idx, group)| {
+            let digits = group.chars().all(|c| c.is_ascii_digit());
+            digits
+                && if idx == 0 {
+                    !group.is_empty() && (groups.len() == 1 || group.len() <= 3)
+                } else {
+                    group.len() == 3
+                }
+        });
+        let fraction_ok =
+            fraction.is_none_or(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()));
+        (well_grouped && fraction_ok).then(|| Self {
+            integer: groups.concat(),
+            fraction: fraction.map(str::to_string),
+            grouped: groups.len() > 1,
+        })
+    }
+
+    /// The value, ignoring how it was grouped.
+    fn value(&self) -> (&str, Option<&str>) {
+        (&self.integer, self.fraction.as_deref())
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        let fraction = self.fraction.as_deref().unwrap_or("0");
+        format!("{}.{fraction}", self.integer).parse().ok()
+    }
+
+    /// Write the number in a convention.
+    fn format(&self, convention: Convention) -> String {
+        let mut written = String::new();
+        if self.grouped {
+            for (idx, digit) in self.integer.chars().enumerate() {
+                if idx > 0 && (self.integer.len() - idx).is_multiple_of(3) {
+                    written.push(convention.group);
+                }
+                written.push(digit);
+            }
+        } else {
+            written.push_str(&self.integer);
+        }
+        if let Some(fraction) = &self.fraction {
+            written.push(convention.decimal);
+            written.push_str(fraction);
+        }
+        written
+    }
+
+    /// A measurement, rounded to a sensible precision.
+    fn measurement(value: f64) -> Self {
+        let written = if value.abs() >= 10.0 {
+            format!("{value:.0}")
+        } else {
+            format!("{value:.1}")
+        };
+        let (integer, fraction) = match written.split_once('.') {
+            Some((integer, "0")) => (integer, None),
+            Some((integer, fraction)) => (integer, Some(fraction.to_string())),
+            None => (written.as_str(), None),
+        };
+        Self {
+            grouped: integer.trim_start_matches('-').len() > 4,
+            integer: integer.to_string(),
+            fraction,
+        }
+    }
+}
+
+/// Write the numbers in a translation as the target language does, using the
+/// original line to tell which way an ambiguous number such as `1.500` was
+/// meant. Imperial units are converted to metric ones, unless the target
+/// language is English.
+///
+/// ```
+/// use subtitle_translate::numbers::localize;
+///
+/// assert_eq!(localize("It's 1.5 miles, 1,500 yards.", "en", "Es ist 1.5 Meilen, 1,500 Yards.", "de"), "Es ist 1,5 Meilen, 1.500 Yards.");
+/// assert_eq!(localize("Going 60 mph", "en", "À 60 mph", "fr"), "À 97 km/h");
+/// ```
+#[must_use]
+pub fn localize(original: &str, source: &str, translation: &str, target: &str) -> String {
+    let source_convention = if source == "auto" {
+        Convention::for_language("en")
+    } else {
+        Convention::for_language(source)
+    };
+    let target_convention = Convention::for_language(target);
+    let convert_units = !target.to_ascii_lowercase().starts_with("en");
+    let originals: Vec<Number> = tokens(original)
+        .filter_map(|(start, end)| Number::parse(&original[start..end], source_convention))
+        .collect();
+
+    let mut result = String::with_capacity(translation.len());
+    let mut copied = 0;
+    for (start, end) in tokens(translation) {
+        let token = &translation[start..end];
+        let as_source = Number::parse(token, source_convention);
+        let as_target = Number::parse(token, target_convention);
+        let in_original = |number: &Number| originals.iter().any(|o| o.value() == number.value());
+        let number = match (as_source, as_target) {
+            (Some(a), Some(b)) if a.value() == b.value() => Some(a),
+            (Some(a), Some(b)) => match (in_original(&a), in_original(&b)) {
+                (true, false) => Some(a),
+                (false, true) => Some(b),
+                _ => None,
+            },
+            (a, b) => a.or(b),
+        };
+        let Some(number) = number else {
+            continue;
+        };
+        result.push_str(&translation[copied..start]);
+        copied = end;
+
+        let rest = &translation[end..];
+        let spaced = rest.strip_prefix(' ').unwrap_or(rest);
+        let unit = UNITS.iter().find(|(unit, _, _)| {
+            spaced.starts_with(unit) && !spaced[unit.len()..].starts_with(char::is_alphanumeric)
+        });
+        if convert_units
+            && let Some((unit, metric, convert)) = unit
+            && let Some(value) = number.as_f64()
+        {
+            let converted = Number::measurement(convert(value));
+            result.push_str(&converted.format(target_convention));
+            result.push_str(&rest[..rest.len() - spaced.len()]);
+            result.push_str(metric);
+            copied = end + (rest.len() - spaced.len()) + unit.len();
+        } else {
+            result.push_str(&number.format(target_convention));
+        }
+    }
+    result.push_str(&translation[copied..]);
+    result
+}
+
+/// The byte ranges of the numbers in some text: runs of digits, possibly
+/// separated by single separators.
+fn tokens(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut idx = 0;
+    std::iter::from_fn(move || {
+        while idx < chars.len() {
+            let (start, c) = chars[idx];
+            let starts_word = idx == 0 || !chars[idx - 1].1.is_alphanumeric();
+            if !c.is_ascii_digit() || !starts_word {
+                idx += 1;
+                continue;
+            }
+            let mut last = idx;
+            let mut next = idx + 1;
+            while next < chars.len() {
+                let c = chars[next].1;
+                if c.is_ascii_digit() {
+                    last = next;
+                } else if !(is_separator(c)
+                    && chars.get(next + 1).is_some_and(|(_, c)| c.is_ascii_digit()))
+                {
+                    break;
+                }
+                next += 1;
+            }
+            idx = last + 1;
+            let end = chars.get(idx).map_or(text.len(), |(end, _)| *end);
+            return Some((start, end));
+        }
+        None
+    })
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '.' | ',' | ' ' | '\u{A0}' | '\u{202F}' | '\'')
+}
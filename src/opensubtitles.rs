@@ -0,0 +1,141 @@
+//! Fetching subtitles from OpenSubtitles, for videos which have none.
+
+use std::{
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::Context;
+use reqwest::{Client, header::HeaderMap};
+use serde::Deserialize;
+use serde_json::json;
+
+const API: &str = "https://api.opensubtitles.com/api/v1";
+
+/// How much of each end of a video is hashed.
+const HASH_CHUNK: u64 = 64 * 1024;
+
+pub struct OpenSubtitles {
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct Search {
+    data: Vec<SearchResult>,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    attributes: Attributes,
+}
+
+#[derive(Deserialize)]
+struct Attributes {
+    #[serde(default)]
+    moviehash_match: bool,
+    files: Vec<File>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    file_id: u64,
+}
+
+#[derive(Deserialize)]
+struct Download {
+    link: String,
+}
+
+impl OpenSubtitles {
+    pub fn new(api_key: &str) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Api-Key", api_key.parse().context("Invalid API key")?);
+        let client = Client::builder()
+            .user_agent(concat!("subtitle-translate v", env!("CARGO_PKG_VERSION")))
+            .default_headers(headers)
+            .build()?;
+        Ok(Self { client })
+    }
+
+    /// Download the subtitles in a language for a video, found by the video's
+    /// hash and, if it is given, its IMDb ID. Subtitles matching the hash are
+    /// preferred, as their timing will match the video.
+    pub async fn fetch(
+        &self,
+        video: &Path,
+        imdb: Option<&str>,
+        language: &str,
+    ) -> anyhow::Result<String> {
+        let mut query = vec![("languages", language.to_string())];
+        if video.exists() {
+            let hash = movie_hash(video).context("Failed to hash the video")?;
+            query.push(("moviehash", format!("{hash:016x}")));
+        }
+        if let Some(imdb) = imdb {
+            let id = imdb.trim_start_matches("tt").trim_start_matches('0');
+            query.push(("imdb_id", id.to_string()));
+        }
+        query.sort();
+
+        let search: Search = self
+            .client
+            .get(format!("{API}/subtitles"))
+            .query(&query)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context("Failed to search OpenSubtitles")?
+            .json()
+            .await
+            .context("Failed to read OpenSubtitles' search results")?;
+        let file_id = search
+            .data
+            .iter()
+            .find(|result| result.attributes.moviehash_match)
+            .or_else(|| search.data.first())
+            .and_then(|result| result.attributes.files.first())
+            .map(|file| file.file_id)
+            .context("OpenSubtitles has no subtitles for the video in that language")?;
+        tracing::info!("Downloading file {file_id} from OpenSubtitles");
+
+        let download: Download = self
+            .client
+            .post(format!("{API}/download"))
+            .json(&json!({ "file_id": file_id }))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context("Failed to request the subtitles from OpenSubtitles")?
+            .json()
+            .await?;
+        self.client
+            .get(&download.link)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context("Failed to download the subtitles")?
+            .text()
+            .await
+            .context("Failed to download the subtitles")
+    }
+}
+
+/// Calculate OpenSubtitles' hash of a video: its size plus the sum of the
+/// 64-bit little-endian words in its first and last 64 KiB.
+fn movie_hash(path: &Path) -> std::io::Result<u64> {
+    let mut file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hash = size;
+    let mut buffer = Vec::new();
+    for start in [0, size.saturating_sub(HASH_CHUNK)] {
+        buffer.clear();
+        file.seek(SeekFrom::Start(start))?;
+        (&mut file).take(HASH_CHUNK).read_to_end(&mut buffer)?;
+        for word in buffer.chunks(8) {
+            let mut bytes = [0; 8];
+            bytes[..word.len()].copy_from_slice(word);
+            hash = hash.wrapping_add(u64::from_le_bytes(bytes));
+        }
+    }
+    Ok(hash)
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use subtitle_translate::{subtitle, timing};
+
+use crate::{
+    cli::{ConcatArgs, SplitArgs},
+    exit_code::{Failure, WithFailure},
+};
+
+/// Cut a subtitle file into parts at the given times, writing each beside it
+/// as `<name>.part<n>.<extension>`.
+pub fn split(args: &SplitArgs) -> anyhow::Result<()> {
+    let mut cuts = args.at.clone();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let subtitles = subtitle::read(&args.file)
+        .context("Failed to read subtitles")
+        .failure(Failure::Parse)?;
+    let parts = timing::split(&subtitles, &cuts);
+    let paths: Vec<PathBuf> = (1..=parts.len())
+        .map(|part| part_path(&args.file, part))
+        .collect();
+    if !args.overwrite
+        && let Some(path) = paths.iter().find(|path| path.exists())
+    {
+        anyhow::bail!(
+            "Destination file {} already exists, pass --overwrite to replace it",
+            path.display()
+        );
+    }
+
+    for (part, path) in parts.iter().zip(&paths) {
+        subtitle::write(part, path)
+            .with_context(|| format!("Failed to write {}", path.display()))
+            .failure(Failure::Write)?;
+        tracing::info!("Wrote {} cue(s) to {path:?}", part.len());
+    }
+    Ok(())
+}
+
+/// The path a part of a subtitle file is written to.
+fn part_path(path: &Path, part: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{stem}.part{part}");
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+/// Join subtitle files for the parts of a video into one.
+pub fn concat(args: &ConcatArgs) -> anyhow::Result<()> {
+    if args.output.exists() && !args.overwrite {
+        anyhow::bail!(
+            "Destination file {} already exists, pass --overwrite to replace it",
+            args.output.display()
+        );
+    }
+    if args.offset.len() >= args.files.len() {
+        anyhow::bail!("There are more offsets than parts after the first");
+    }
+
+    let parts = args
+        .files
+        .iter()
+        .map(|path| {
+            subtitle::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+                .failure(Failure::Parse)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let (subtitles, shortened) = timing::concat(parts, &args.offset);
+    if shortened > 0 {
+        tracing::warn!("Shortened {shortened} cue(s) running into the next part");
+    }
+
+    subtitle::write(&subtitles, &args.output)
+        .context("Failed to write destination subtitle file")
+        .failure(Failure::Write)
+}
@@ -0,0 +1,119 @@
+use ratatui::{
+    DefaultTerminal, Frame, TerminalOptions, Viewport,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Position},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{List, ListState},
+};
+
+use subtitle_translate::api_types::Language;
+
+/// How many lines of the terminal the picker takes up.
+const HEIGHT: u16 = 12;
+
+/// The prompt shown before the search text.
+const PROMPT: &str = "Target language: ";
+
+/// Ask the user to choose a language, narrowing the list down as they type.
+/// Returns `None` if they cancel.
+pub fn pick_language(languages: &[Language]) -> anyhow::Result<Option<String>> {
+    let mut terminal = ratatui::init_with_options(TerminalOptions {
+        viewport: Viewport::Inline(HEIGHT),
+    });
+    let result = run(&mut terminal, languages);
+    ratatui::restore();
+    println!();
+    result
+}
+
+fn run(terminal: &mut DefaultTerminal, languages: &[Language]) -> anyhow::Result<Option<String>> {
+    let mut query = String::new();
+    let mut list = ListState::default().with_selected(Some(0));
+    loop {
+        let matches = search(languages, &query);
+        terminal.draw(|frame| draw(frame, &query, &matches, &mut list))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(None);
+            }
+            KeyCode::Enter => {
+                let selected = list.selected().unwrap_or(0);
+                if let Some(language) = matches.get(selected.min(matches.len().saturating_sub(1))) {
+                    return Ok(Some(language.code.clone()));
+                }
+            }
+            KeyCode::Up => list.select_previous(),
+            KeyCode::Down => list.select_next(),
+            KeyCode::Backspace => {
+                query.pop();
+                list.select_first();
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                list.select_first();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, query: &str, matches: &[&Language], list: &mut ListState) {
+    let [input, results] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(frame.area());
+
+    frame.render_widget(Line::from(vec![PROMPT.bold(), query.into()]), input);
+    let cursor = u16::try_from(PROMPT.len() + query.chars().count()).unwrap_or(u16::MAX);
+    frame.set_cursor_position(Position::new(input.x.saturating_add(cursor), input.y));
+
+    let items = matches
+        .iter()
+        .map(|language| format!("{:<6}{}", language.code, language.name));
+    frame.render_stateful_widget(
+        List::new(items).highlight_style(Style::new().reversed()),
+        results,
+        list,
+    );
+}
+
+/// Find the languages matching a search, best matches first.
+///
+/// Exact codes match best, then names starting with the search, then names
+/// containing it, and finally names containing its letters in order.
+fn search<'a>(languages: &'a [Language], query: &str) -> Vec<&'a Language> {
+    let query = query.to_lowercase();
+    let mut scored: Vec<_> = languages
+        .iter()
+        .filter_map(|language| {
+            let name = language.name.to_lowercase();
+            let score = if language.code == query {
+                0
+            } else if name.starts_with(&query) {
+                1
+            } else if name.contains(&query) {
+                2
+            } else if is_subsequence(&query, &name) {
+                3
+            } else {
+                return None;
+            };
+            Some((score, language))
+        })
+        .collect();
+    scored.sort_by_key(|(score, _)| *score);
+    scored.into_iter().map(|(_, language)| language).collect()
+}
+
+/// Whether the characters of `needle` appear in order within `haystack`.
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
@@ -0,0 +1,338 @@
+//! The translation of a file's lines.
+
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use futures::future::{self, Either, join_all};
+use serde::Serialize;
+use tracing::Instrument;
+use web_time::Instant;
+
+use crate::{
+    api_types::Translation,
+    cancel::{CancellationToken, Cancelled},
+    layout,
+    subtitle::{self, GenericSubtitle},
+    summary::Summary,
+    translator::{Hints, Translator},
+};
+
+/// Something which happened during a translation, for reporting progress.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    /// Translation of a file has begun.
+    Started {
+        source: &'a Path,
+        destination: &'a Path,
+        language_from: &'a str,
+        language_to: &'a str,
+        cues: usize,
+    },
+    /// A cue was translated, into `translation`. Cues are numbered from 1.
+    LineDone {
+        cue: usize,
+        elapsed_ms: u128,
+        translation: &'a str,
+    },
+    /// A cue failed to translate.
+    LineFailed { cue: usize, error: String },
+    /// Translation of a file has ended, successfully or not.
+    Finished {
+        success: bool,
+        translated: usize,
+        skipped: usize,
+        failed: usize,
+    },
+}
+
+/// Translates subtitles with a [`Translator`], a chunk of lines at a time.
+///
+/// ```no_run
+/// # use subtitle_translate::{TranslationPipeline, summary::Summary};
+/// # use subtitle_translate::{Translator, api_types::Translation};
+/// # struct Engine;
+/// # impl Translator for Engine {
+/// #     async fn translate(&self, input: String, _: &str, _: &str, _: u32) -> anyhow::Result<Translation> {
+/// #         Ok(Translation { translated_text: input, alternatives: None, detected_language: None, cached: false })
+/// #     }
+/// # }
+/// # async fn run(mut subtitles: Vec<subtitle_translate::GenericSubtitle>) -> anyhow::Result<()> {
+/// let pipeline = TranslationPipeline::new(Engine, "de")
+///     .source("en")
+///     .chunk_size(10);
+/// pipeline
+///     .translate(&mut subtitles, &mut Summary::new(), |_| {})
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TranslationPipeline<T> {
+    translator: T,
+    source: String,
+    target: String,
+    chunk_size: usize,
+    hints: Hints,
+    alternatives: u32,
+    keep_line_breaks: bool,
+    continue_on_error: bool,
+    cancellation: Option<CancellationToken>,
+    resumed: BTreeMap<usize, String>,
+}
+
+impl<T: Translator> TranslationPipeline<T> {
+    /// Create a pipeline translating into the `target` language, detecting
+    /// the source language and sending 5 lines at a time.
+    pub fn new(translator: T, target: impl Into<String>) -> Self {
+        let target = translator.language_code(&target.into());
+        Self {
+            translator,
+            source: "auto".to_string(),
+            target,
+            chunk_size: 5,
+            hints: Hints::default(),
+            alternatives: 0,
+            keep_line_breaks: false,
+            continue_on_error: false,
+            cancellation: None,
+            resumed: BTreeMap::new(),
+        }
+    }
+
+    /// Set the source language, rather than detecting it.
+    #[must_use]
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
+    /// Set how many lines are sent to the translator in parallel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    #[must_use]
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Ask the translator to address and refer to people as the hints say,
+    /// if it can.
+    #[must_use]
+    pub fn hints(mut self, hints: Hints) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    /// Request a number of alternative translations of each line, which are
+    /// recorded in the summary as the translator returns them.
+    #[must_use]
+    pub fn alternatives(mut self, alternatives: u32) -> Self {
+        self.alternatives = alternatives;
+        self
+    }
+
+    /// Send each line of a cue to the translator as it is, rather than joining
+    /// them into one with [`layout::join_lines`] and wrapping the translation
+    /// with [`layout::rewrap`].
+    #[must_use]
+    pub fn keep_line_breaks(mut self, keep: bool) -> Self {
+        self.keep_line_breaks = keep;
+        self
+    }
+
+    /// Carry on translating after lines fail, rather than stopping after the
+    /// chunk they were in. Failed lines are left as they were.
+    #[must_use]
+    pub fn continue_on_error(mut self, continue_on_error: bool) -> Self {
+        self.continue_on_error = continue_on_error;
+        self
+    }
+
+    /// Stop translating once the token is cancelled, dropping the requests in
+    /// flight.
+    #[must_use]
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Use the translations of cues, by their index, made by an earlier run
+    /// which didn't finish, rather than sending them to the translator again.
+    #[must_use]
+    pub fn resume(mut self, translations: BTreeMap<usize, String>) -> Self {
+        self.resumed = translations;
+        self
+    }
+
+    /// The translator lines are sent to.
+    #[must_use]
+    pub fn translator(&self) -> &T {
+        &self.translator
+    }
+
+    /// Translate every subtitle's text in place. Lines without any visible
+    /// text, as [`subtitle::is_blank`] decides, are skipped.
+    ///
+    /// The lines in a chunk are translated concurrently on the current task,
+    /// so this works with any async runtime.
+    ///
+    /// Each line's outcome is recorded in `summary`, and reported to
+    /// `on_event` as it completes.
+    ///
+    /// # Errors
+    ///
+    /// Translation stops after the first chunk in which any line fails,
+    /// returning that line's error. Lines translated before then are kept.
+    /// If the pipeline continues on errors, every line is attempted before
+    /// the first error is returned.
+    ///
+    /// If the pipeline's token is cancelled, translation stops at once with
+    /// [`Cancelled`]. Lines translated before then are kept, and those in
+    /// flight are left as they were.
+    pub async fn translate(
+        &self,
+        subtitles: &mut [GenericSubtitle],
+        summary: &mut Summary,
+        on_event: impl Fn(&Event<'_>),
+    ) -> anyhow::Result<()> {
+        let chunk_size = self.chunk_size;
+        let mut first_error = None;
+        for (chunk_idx, chunk) in subtitles.chunks_mut(chunk_size).enumerate() {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                return Err(Cancelled.into());
+            }
+            let translations = chunk.iter().enumerate().map(|(idx, item)| {
+                let input = if self.keep_line_breaks {
+                    item.text.clone()
+                } else {
+                    layout::join_lines(&item.text)
+                };
+                let line = chunk_idx * chunk_size + idx + 1;
+                let span = tracing::debug_span!(
+                    "translation",
+                    chunk_idx = chunk_idx,
+                    idx = idx,
+                    line = line,
+                    input = input
+                );
+                self.translate_line(input, line).instrument(span)
+            });
+
+            let results = self.unless_cancelled(join_all(translations)).await?;
+            for (idx, (line, (result, elapsed))) in chunk.iter_mut().zip(results).enumerate() {
+                let cue = chunk_idx * chunk_size + idx + 1;
+                let Some(result) = result else {
+                    summary.record_skip();
+                    continue;
+                };
+                match result {
+                    Ok(translation) => {
+                        summary.record(&line.text, &translation, elapsed);
+                        line.text = if self.keep_line_breaks {
+                            translation.translated_text
+                        } else {
+                            layout::rewrap(&translation.translated_text, &line.text)
+                        };
+                        on_event(&Event::LineDone {
+                            cue,
+                            elapsed_ms: elapsed.as_millis(),
+                            translation: &line.text,
+                        });
+                    }
+                    Err(e) => {
+                        on_event(&Event::LineFailed {
+                            cue,
+                            error: format!("{e:#}"),
+                        });
+                        summary.record_failure(&line.text, &e, elapsed);
+                        first_error.get_or_insert_with(|| {
+                            e.context(format!(
+                                "Failed to translate cue {cue} at {}: \"{}\"",
+                                subtitle::format_moment(line.start),
+                                snippet(&line.text)
+                            ))
+                        });
+                    }
+                }
+            }
+
+            if !self.continue_on_error
+                && let Some(e) = first_error.take()
+            {
+                return Err(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Translate the input of a line, numbered from 1, unless it is blank,
+    /// returning the result and how long it took.
+    async fn translate_line(
+        &self,
+        input: String,
+        line: usize,
+    ) -> (Option<anyhow::Result<Translation>>, Duration) {
+        if subtitle::is_blank(&input) {
+            return (None, Duration::ZERO);
+        }
+        if let Some(translation) = self.resumed.get(&(line - 1)) {
+            let translation = Translation {
+                translated_text: translation.clone(),
+                alternatives: None,
+                detected_language: None,
+                cached: false,
+            };
+            return (Some(Ok(translation)), Duration::ZERO);
+        }
+        let started = Instant::now();
+        let result = self
+            .translator
+            .translate_with_hints(
+                input,
+                &self.source,
+                &self.target,
+                self.alternatives,
+                self.hints,
+            )
+            .await;
+        let elapsed = started.elapsed();
+        let elapsed_ms = elapsed.as_millis();
+        match &result {
+            Ok(_) => tracing::info!(line, elapsed_ms, "Translated line"),
+            Err(e) => {
+                tracing::error!(line, elapsed_ms, error = %e, "Failed to translate line");
+            }
+        }
+        (Some(result), elapsed)
+    }
+
+    /// Wait for a future, unless the pipeline's token is cancelled first, in
+    /// which case the future is dropped.
+    async fn unless_cancelled<F: Future>(&self, future: F) -> Result<F::Output, Cancelled> {
+        let Some(token) = &self.cancellation else {
+            return Ok(future.await);
+        };
+        match future::select(std::pin::pin!(future), std::pin::pin!(token.cancelled())).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(_) => Err(Cancelled),
+        }
+    }
+}
+
+/// The longest part of a cue's text given in errors.
+const MAX_SNIPPET: usize = 40;
+
+/// The start of a cue's text, on one line, to identify it in errors.
+fn snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(MAX_SNIPPET) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
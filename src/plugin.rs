@@ -0,0 +1,112 @@
+//! WebAssembly plugins, which can clean up lines or apply terminology without
+//! changing this tool.
+//!
+//! A plugin is a core WebAssembly module which imports nothing and exports:
+//!
+//! - `memory`, its linear memory.
+//! - `alloc(len: i32) -> i32`, which returns space for `len` bytes of input.
+//! - `pre_translate(ptr: i32, len: i32) -> i64` and/or
+//!   `post_translate(ptr: i32, len: i32) -> i64`, which are given a UTF-8
+//!   line and return where its replacement is, as `ptr << 32 | len`.
+//!
+//! Either hook may be left out, in which case lines pass through unchanged.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc};
+
+use crate::{dirs, hooks::Hook};
+
+/// A hook taking a line and returning its replacement.
+type LineFunc = TypedFunc<(u32, u32), u64>;
+
+/// A loaded plugin, with its own instance and memory.
+pub struct Plugin {
+    name: String,
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<u32, u32>,
+    pre_translate: Option<LineFunc>,
+    post_translate: Option<LineFunc>,
+}
+
+/// Load the given plugins, followed by any `.wasm` files in the `plugins`
+/// folder of the configuration directory.
+pub fn load(paths: &[PathBuf]) -> anyhow::Result<Vec<Plugin>> {
+    let mut paths = paths.to_vec();
+    if let Some(dir) = dirs::config().map(|dir| dir.join("plugins"))
+        && dir.is_dir()
+    {
+        let mut installed = std::fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read plugins from {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        installed.retain(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "wasm")
+        });
+        installed.sort();
+        paths.extend(installed);
+    }
+
+    let engine = Engine::default();
+    paths
+        .iter()
+        .map(|path| {
+            Plugin::load(&engine, path)
+                .with_context(|| format!("Failed to load plugin {}", path.display()))
+        })
+        .collect()
+}
+
+impl Plugin {
+    fn load(engine: &Engine, path: &Path) -> anyhow::Result<Self> {
+        let module = Module::from_file(engine, path)?;
+        let mut store = Store::new(engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("The plugin doesn't export its memory")?;
+        let alloc = instance.get_typed_func(&mut store, "alloc")?;
+        let pre_translate = instance.get_typed_func(&mut store, "pre_translate").ok();
+        let post_translate = instance.get_typed_func(&mut store, "post_translate").ok();
+        tracing::info!("Loaded plugin {}", path.display());
+        Ok(Self {
+            name: path.display().to_string(),
+            store,
+            memory,
+            alloc,
+            pre_translate,
+            post_translate,
+        })
+    }
+
+    /// Pass a line to one of the plugin's hooks.
+    fn call(&mut self, hook: Option<LineFunc>, line: &str) -> anyhow::Result<String> {
+        let Some(hook) = hook else {
+            return Ok(line.to_string());
+        };
+        let len = u32::try_from(line.len()).context("The line is too long")?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as usize, line.as_bytes())?;
+        let packed = hook.call(&mut self.store, (ptr, len))?;
+
+        let output_ptr = usize::try_from(packed >> 32)?;
+        let mut output = vec![0; usize::try_from(packed & u64::from(u32::MAX))?];
+        self.memory.read(&self.store, output_ptr, &mut output)?;
+        String::from_utf8(output)
+            .with_context(|| format!("Plugin {} returned invalid UTF-8", self.name))
+    }
+}
+
+impl Hook for Plugin {
+    fn pre_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        self.call(self.pre_translate.clone(), line)
+    }
+
+    fn post_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        self.call(self.post_translate.clone(), line)
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    fmt::Write as _,
+    io::{IsTerminal, stdout},
+    path::Path,
+};
+
+use ratatui::crossterm::style::{Stylize, force_color_output};
+
+use subtitle_translate::subtitle::{GenericSubtitle, format_moment};
+
+/// The width used when the terminal size can't be determined.
+const DEFAULT_WIDTH: usize = 100;
+
+/// The width of the timestamp column, including padding.
+const TIME_WIDTH: usize = 14;
+
+/// Print each cue's timestamp, original and translation in aligned columns.
+pub fn print_terminal(originals: &[GenericSubtitle], translated: &[GenericSubtitle]) {
+    let width = ratatui::crossterm::terminal::size()
+        .map_or(DEFAULT_WIDTH, |(cols, _)| usize::from(cols))
+        .max(TIME_WIDTH + 20);
+    let column = (width - TIME_WIDTH - 3) / 2;
+
+    println!(
+        "{:<TIME_WIDTH$}{:<column$} | Translation",
+        "Start", "Original"
+    );
+    println!("{}", "-".repeat(width));
+    for (original, line) in originals.iter().zip(translated) {
+        let left = wrap(&original.text, column);
+        let right = wrap(&line.text, column);
+        for row in 0..left.len().max(right.len()).max(1) {
+            let time = if row == 0 {
+                format_moment(original.start)
+            } else {
+                String::new()
+            };
+            println!(
+                "{time:<TIME_WIDTH$}{:<column$} | {}",
+                left.get(row).map_or("", String::as_str),
+                right.get(row).map_or("", String::as_str),
+            );
+        }
+    }
+}
+
+/// Print the cues whose text differs from a previous translation, with the
+/// previous text in red and the new text in green.
+pub fn print_diff(previous: &[GenericSubtitle], translated: &[GenericSubtitle]) {
+    force_color_output(stdout().is_terminal());
+    let mut changed = 0;
+    for idx in 0..previous.len().max(translated.len()) {
+        let old = previous.get(idx).map(|line| line.text.as_str());
+        let new = translated.get(idx);
+        if old == new.map(|line| line.text.as_str()) {
+            continue;
+        }
+        changed += 1;
+
+        let start = new.or(previous.get(idx)).map(|line| line.start);
+        println!(
+            "{} ({})",
+            format!("Cue {}", idx + 1).bold(),
+            start.map(format_moment).unwrap_or_default()
+        );
+        for line in old.unwrap_or_default().lines() {
+            println!("{}", format!("- {line}").red());
+        }
+        for line in new.map_or("", |line| line.text.as_str()).lines() {
+            println!("{}", format!("+ {line}").green());
+        }
+    }
+    println!("{changed} cue(s) changed");
+}
+
+/// Write each cue's timestamp, original and translation as an HTML table.
+pub fn write_html(
+    originals: &[GenericSubtitle],
+    translated: &[GenericSubtitle],
+    path: &Path,
+) -> anyhow::Result<()> {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Translation preview</title>\n\
+         <style>table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:4px 8px;vertical-align:top}</style>\n\
+         </head>\n<body>\n<table>\n<tr><th>#</th><th>Start</th><th>End</th><th>Original</th><th>Translation</th></tr>\n",
+    );
+    for (idx, (original, line)) in originals.iter().zip(translated).enumerate() {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            idx + 1,
+            format_moment(original.start),
+            format_moment(original.end),
+            escape_html(&original.text),
+            escape_html(&line.text),
+        )?;
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    std::fs::write(path, html)?;
+    Ok(())
+}
+
+/// Wrap text into lines of at most `width` characters, breaking on spaces
+/// where possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for source_line in text.lines() {
+        let mut current = String::new();
+        for word in source_line.split_whitespace() {
+            let mut word = word.to_string();
+            while word.chars().count() > width {
+                if !current.is_empty() {
+                    lines.push(std::mem::take(&mut current));
+                }
+                let rest = word.split_off(
+                    word.char_indices()
+                        .nth(width)
+                        .map_or(word.len(), |(i, _)| i),
+                );
+                lines.push(word);
+                word = rest;
+            }
+            if current.is_empty() {
+                current = word;
+            } else if current.chars().count() + 1 + word.chars().count() <= width {
+                current.push(' ');
+                current.push_str(&word);
+            } else {
+                lines.push(std::mem::replace(&mut current, word));
+            }
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+    }
+    lines
+}
+
+/// Escape text for inclusion in HTML, keeping line breaks.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
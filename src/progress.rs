@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use subtitle_translate::{pipeline::Event, summary::Summary};
+
+use crate::cli::ProgressFormat;
+
+/// Reports progress as it happens, for frontends to follow.
+#[derive(Clone, Copy)]
+pub struct Progress {
+    format: Option<ProgressFormat>,
+}
+
+impl Progress {
+    pub fn new(format: Option<ProgressFormat>) -> Self {
+        Self { format }
+    }
+
+    /// Whether progress is being written to stdout, so nothing else should be.
+    pub fn uses_stdout(self) -> bool {
+        self.format.is_some()
+    }
+
+    pub fn emit(self, event: &Event<'_>) {
+        match self.format {
+            Some(ProgressFormat::Json) => match serde_json::to_string(event) {
+                Ok(json) => println!("{json}"),
+                Err(e) => tracing::warn!("Failed to serialise progress event: {e}"),
+            },
+            None => {}
+        }
+    }
+
+    /// Report that translation has begun.
+    pub fn started(
+        self,
+        source: &Path,
+        destination: &Path,
+        language_from: &str,
+        language_to: &str,
+        cues: usize,
+    ) {
+        self.emit(&Event::Started {
+            source,
+            destination,
+            language_from,
+            language_to,
+            cues,
+        });
+    }
+
+    /// Report that the run has finished.
+    pub fn finished(self, summary: &Summary, success: bool) {
+        self.emit(&Event::Finished {
+            success,
+            translated: summary.translated,
+            skipped: summary.skipped,
+            failed: summary.failed,
+        });
+    }
+}
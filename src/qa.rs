@@ -0,0 +1,257 @@
+use std::fmt;
+
+use crate::{
+    confidence, stats,
+    subtitle::{GenericSubtitle, format_moment},
+};
+
+/// The limits subtitles are checked against.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// The longest a line may be, in characters.
+    pub max_line_length: usize,
+    /// The fastest a cue may need to be read, in characters per second.
+    pub max_cps: f64,
+    /// The lowest confidence score a translation may have, from 0 to 100.
+    pub min_confidence: f64,
+    /// The most lines a cue may have, if there is a limit.
+    pub max_lines: Option<usize>,
+    /// The shortest time a cue may be shown for, in milliseconds, if there is
+    /// a limit.
+    pub min_duration: Option<u32>,
+    /// The shortest gap there may be between a cue and the next, in
+    /// milliseconds, if there is a limit. Cues which follow on without a gap
+    /// are allowed.
+    pub min_gap: Option<u32>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_line_length: 42,
+            max_cps: 21.0,
+            min_confidence: 50.0,
+            max_lines: None,
+            min_duration: None,
+            min_gap: None,
+        }
+    }
+}
+
+/// Something wrong with a cue.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// The cue starts before the cue preceding it.
+    OutOfOrder,
+    /// The cue starts before the given earlier cue has ended.
+    Overlap { with: usize },
+    /// The cue ends at or before the time it starts.
+    ZeroDuration,
+    /// A line in the cue is too long.
+    LineTooLong { length: usize, max: usize },
+    /// The cue has too many lines.
+    TooManyLines { lines: usize, max: usize },
+    /// The cue is shown too briefly.
+    TooShort { duration_ms: i64, min: u32 },
+    /// The cue ends too soon before the next cue starts.
+    GapTooShort { gap_ms: i64, min: u32 },
+    /// The cue is displayed too briefly for its length.
+    TooFast { cps: f64, max: f64 },
+    /// The cue contains characters which shouldn't appear in subtitles.
+    IllegalCharacters { characters: Vec<char> },
+    /// The translation is the same as the original.
+    Untranslated,
+    /// The translation is much shorter or longer than the original.
+    LengthRatio { ratio: f64 },
+    /// The translation's confidence score is too low.
+    LowConfidence { score: f64, min: f64 },
+}
+
+impl Problem {
+    /// A short name for the kind of problem, for reports.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::OutOfOrder => "out-of-order",
+            Self::Overlap { .. } => "overlap",
+            Self::ZeroDuration => "zero-duration",
+            Self::LineTooLong { .. } => "line-too-long",
+            Self::TooManyLines { .. } => "too-many-lines",
+            Self::TooShort { .. } => "too-short",
+            Self::GapTooShort { .. } => "gap-too-short",
+            Self::TooFast { .. } => "too-fast",
+            Self::IllegalCharacters { .. } => "illegal-characters",
+            Self::Untranslated => "untranslated",
+            Self::LengthRatio { .. } => "length-ratio",
+            Self::LowConfidence { .. } => "low-confidence",
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfOrder => write!(f, "starts before the previous cue"),
+            Self::Overlap { with } => write!(f, "overlaps cue {}", with + 1),
+            Self::ZeroDuration => write!(f, "has no duration"),
+            Self::LineTooLong { length, max } => {
+                write!(f, "line too long ({length} > {max} characters)")
+            }
+            Self::TooManyLines { lines, max } => write!(f, "too many lines ({lines} > {max})"),
+            Self::TooShort { duration_ms, min } => {
+                write!(f, "shown too briefly ({duration_ms}ms < {min}ms)")
+            }
+            Self::GapTooShort { gap_ms, min } => {
+                write!(f, "too close to the next cue ({gap_ms}ms < {min}ms)")
+            }
+            Self::TooFast { cps, max } => {
+                write!(
+                    f,
+                    "too fast to read ({cps:.1} > {max:.1} characters per second)"
+                )
+            }
+            Self::IllegalCharacters { characters } => {
+                write!(f, "contains illegal characters {characters:?}")
+            }
+            Self::Untranslated => write!(f, "left untranslated"),
+            Self::LengthRatio { ratio } => {
+                write!(f, "translation is {ratio:.1} times as long as the original")
+            }
+            Self::LowConfidence { score, min } => {
+                write!(f, "low confidence in translation ({score:.0} < {min:.0})")
+            }
+        }
+    }
+}
+
+/// A problem found with a specific cue.
+#[derive(Clone, Debug)]
+pub struct Issue {
+    /// The index of the cue.
+    pub cue: usize,
+    pub problem: Problem,
+}
+
+impl Issue {
+    /// Describe the issue, locating the cue by number and start time.
+    #[must_use]
+    pub fn describe(&self, subtitles: &[GenericSubtitle]) -> String {
+        match subtitles.get(self.cue) {
+            Some(subtitle) => format!(
+                "cue {} ({}): {}",
+                self.cue + 1,
+                format_moment(subtitle.start),
+                self.problem
+            ),
+            None => format!("cue {}: {}", self.cue + 1, self.problem),
+        }
+    }
+}
+
+/// Check subtitles for timing, length and content problems.
+#[must_use]
+pub fn check(subtitles: &[GenericSubtitle], limits: Limits) -> Vec<Issue> {
+    let mut issues = vec![];
+    let mut latest_end: Option<(usize, i64)> = None;
+
+    for (cue, subtitle) in subtitles.iter().enumerate() {
+        let mut report = |problem| issues.push(Issue { cue, problem });
+
+        if cue > 0 && subtitle.start < subtitles[cue - 1].start {
+            report(Problem::OutOfOrder);
+        }
+        if let Some((with, end)) = latest_end
+            && subtitle.start.as_ms() < end
+        {
+            report(Problem::Overlap { with });
+        }
+        if subtitle.duration_ms() <= 0 {
+            report(Problem::ZeroDuration);
+        } else if let Some(min) = limits.min_duration
+            && subtitle.duration_ms() < i64::from(min)
+        {
+            report(Problem::TooShort {
+                duration_ms: subtitle.duration_ms(),
+                min,
+            });
+        }
+        if let Some(min) = limits.min_gap
+            && let Some(next) = subtitles.get(cue + 1)
+        {
+            let gap_ms = next.start.as_ms() - subtitle.end.as_ms();
+            if gap_ms > 0 && gap_ms < i64::from(min) {
+                report(Problem::GapTooShort { gap_ms, min });
+            }
+        }
+
+        let length = stats::longest_line(&subtitle.text);
+        if length > limits.max_line_length {
+            report(Problem::LineTooLong {
+                length,
+                max: limits.max_line_length,
+            });
+        }
+        let lines = stats::lines(&subtitle.text);
+        if let Some(max) = limits.max_lines
+            && lines > max
+        {
+            report(Problem::TooManyLines { lines, max });
+        }
+        if let Some(cps) = stats::cps(subtitle)
+            && cps > limits.max_cps
+        {
+            report(Problem::TooFast {
+                cps,
+                max: limits.max_cps,
+            });
+        }
+
+        let mut illegal: Vec<char> = subtitle.text.chars().filter(|c| is_illegal(*c)).collect();
+        illegal.dedup();
+        if !illegal.is_empty() {
+            report(Problem::IllegalCharacters {
+                characters: illegal,
+            });
+        }
+
+        if latest_end.is_none_or(|(_, end)| subtitle.end.as_ms() > end) {
+            latest_end = Some((cue, subtitle.end.as_ms()));
+        }
+    }
+
+    issues
+}
+
+/// Check a translation against the line it was translated from, for signs it
+/// is wrong. `detection_confidence` is how confident the engine was of the
+/// source language, if it detected it.
+#[must_use]
+pub fn check_translation(
+    source: &str,
+    translation: &str,
+    detection_confidence: Option<f64>,
+    limits: Limits,
+) -> Vec<Problem> {
+    let mut problems = vec![];
+    if confidence::is_untranslated(source, translation) {
+        problems.push(Problem::Untranslated);
+    }
+    if let Some(ratio) = confidence::length_ratio(source, translation)
+        && !confidence::LENGTH_RATIO.contains(&ratio)
+    {
+        problems.push(Problem::LengthRatio { ratio });
+    }
+    let score = confidence::score(source, translation, detection_confidence);
+    if score < limits.min_confidence {
+        problems.push(Problem::LowConfidence {
+            score,
+            min: limits.min_confidence,
+        });
+    }
+    problems
+}
+
+/// Whether a character should never appear in subtitle text.
+fn is_illegal(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t') || c == '\u{FFFD}' || c == '\u{FEFF}'
+}
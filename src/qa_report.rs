@@ -0,0 +1,123 @@
+//! The QA report, which flags the cues of a translation an editor should look
+//! at before it is delivered.
+
+use std::{collections::BTreeMap, fmt, fs::File, path::Path};
+
+use serde::Serialize;
+use subtitle_translate::{
+    confidence,
+    qa::{self, Limits, Problem},
+    subtitle::{GenericSubtitle, format_moment},
+    summary::Summary,
+};
+
+/// The problems found with a translation.
+#[derive(Serialize)]
+pub struct QaReport {
+    cues: usize,
+    flagged: usize,
+    /// How many times each kind of problem was found.
+    problems: BTreeMap<&'static str, usize>,
+    /// The cues with problems, in order.
+    flags: Vec<Flagged>,
+}
+
+/// A cue with problems.
+#[derive(Serialize)]
+struct Flagged {
+    cue: usize,
+    start: String,
+    end: String,
+    source: String,
+    translation: String,
+    /// The translation's confidence score, if it was translated.
+    confidence: Option<f64>,
+    problems: Vec<Flag>,
+}
+
+#[derive(Serialize)]
+struct Flag {
+    kind: &'static str,
+    message: String,
+}
+
+impl QaReport {
+    /// Check each translated cue's timing, length, reading speed, and
+    /// translation against its source.
+    pub fn new(
+        sources: &[String],
+        translated: &[GenericSubtitle],
+        summary: &Summary,
+        limits: Limits,
+    ) -> Self {
+        let mut problems: Vec<Vec<Problem>> = vec![vec![]; translated.len()];
+        for issue in qa::check(translated, limits) {
+            problems[issue.cue].push(issue.problem);
+        }
+
+        let mut report = Self {
+            cues: translated.len(),
+            flagged: 0,
+            problems: BTreeMap::new(),
+            flags: vec![],
+        };
+        for (cue, (subtitle, mut problems)) in translated.iter().zip(problems).enumerate() {
+            let source = sources.get(cue).map_or("", String::as_str);
+            let outcome = summary.outcome(cue);
+            let detection = outcome.detection_confidence.map(f64::from);
+            let confidence = outcome.status.is_translated().then(|| {
+                problems.extend(qa::check_translation(
+                    source,
+                    &subtitle.text,
+                    detection,
+                    limits,
+                ));
+                confidence::score(source, &subtitle.text, detection)
+            });
+            if problems.is_empty() {
+                continue;
+            }
+            for problem in &problems {
+                *report.problems.entry(problem.kind()).or_default() += 1;
+            }
+            report.flagged += 1;
+            report.flags.push(Flagged {
+                cue: cue + 1,
+                start: format_moment(subtitle.start),
+                end: format_moment(subtitle.end),
+                source: source.to_string(),
+                translation: subtitle.text.clone(),
+                confidence,
+                problems: problems
+                    .iter()
+                    .map(|problem| Flag {
+                        kind: problem.kind(),
+                        message: problem.to_string(),
+                    })
+                    .collect(),
+            });
+        }
+        report
+    }
+
+    /// How many cues have problems.
+    pub fn flagged(&self) -> usize {
+        self.flagged
+    }
+
+    /// Write the report as JSON.
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, self)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for QaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "QA: {} of {} cue(s) flagged", self.flagged, self.cues)?;
+        for (kind, count) in &self.problems {
+            writeln!(f, "  {kind:<20} {count}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,420 @@
+//! Repairs of cues whose timing would break players once written out.
+
+use std::fmt;
+
+use aspasia::Moment;
+
+use crate::{
+    segment, stats,
+    subtitle::{GenericSubtitle, format_moment},
+};
+
+/// How to repair cues which overlap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OverlapStrategy {
+    /// End each cue when the next one starts
+    Trim,
+    /// Join overlapping cues into one, showing all of their text
+    Merge,
+}
+
+/// A change made to a cue to repair it.
+#[derive(Clone, Debug)]
+pub struct Repair {
+    /// The index of the cue, once the repair was made.
+    pub cue: usize,
+    /// When the cue starts, once it was repaired.
+    pub start: Moment,
+    pub fix: Fix,
+}
+
+/// What was done to repair a cue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fix {
+    /// The cue was ended earlier, so it no longer overlaps the next.
+    Trimmed { by_ms: i64 },
+    /// Cues which overlapped the cue were merged into it.
+    Merged { cues: usize },
+    /// The cue ended at or before it started, so was given a duration.
+    Extended { to_ms: i64 },
+    /// Short cues following the cue were joined onto it.
+    Joined { cues: usize },
+    /// The cue was ended earlier, to leave a gap before the next.
+    Spaced { by_ms: i64 },
+    /// The cue was ended later, to be shown for long enough.
+    Lengthened { by_ms: i64 },
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Trimmed { by_ms } => {
+                write!(f, "shortened by {by_ms}ms to end before the next cue")
+            }
+            Self::Merged { cues } => write!(f, "merged with {cues} overlapping cue(s)"),
+            Self::Extended { to_ms } => write!(f, "had no duration, now shown for {to_ms}ms"),
+            Self::Joined { cues } => write!(f, "joined with {cues} short cue(s) following it"),
+            Self::Spaced { by_ms } => {
+                write!(
+                    f,
+                    "shortened by {by_ms}ms to leave a gap before the next cue"
+                )
+            }
+            Self::Lengthened { by_ms } => {
+                write!(f, "lengthened by {by_ms}ms to be shown for long enough")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cue {} ({}): {}",
+            self.cue + 1,
+            format_moment(self.start),
+            self.fix
+        )
+    }
+}
+
+/// Put cues in the order they start, keeping the order of cues which start
+/// together, returning how many were moved.
+///
+/// ```
+/// # use subtitle_translate::{GenericSubtitle, repair::*};
+/// # let cue = |text: &str, start: i64, end: i64| GenericSubtitle {
+/// #     text: text.to_string(),
+/// #     start: start.into(),
+/// #     end: end.into(),
+/// #     coordinates: None,
+/// #     style: None,
+/// # };
+/// let mut cues = vec![cue("b", 2000, 3000), cue("a", 0, 1000), cue("c", 2000, 2500)];
+/// assert_eq!(sort(&mut cues), 2);
+/// let texts: Vec<_> = cues.iter().map(|cue| cue.text.as_str()).collect();
+/// assert_eq!(texts, ["a", "b", "c"]);
+/// ```
+pub fn sort(subtitles: &mut [GenericSubtitle]) -> usize {
+    let mut order: Vec<usize> = (0..subtitles.len()).collect();
+    order.sort_by_key(|idx| subtitles[*idx].start.as_ms());
+    let moved = order
+        .iter()
+        .enumerate()
+        .filter(|(idx, from)| idx != *from)
+        .count();
+    subtitles.sort_by_key(|subtitle| subtitle.start.as_ms());
+    moved
+}
+
+/// Repair cues which start before the cue preceding them has ended. Cues are
+/// merged rather than trimmed if they start together, as trimming would leave
+/// nothing of the earlier one.
+///
+/// ```
+/// # use subtitle_translate::{GenericSubtitle, repair::*};
+/// # let cue = |text: &str, start: i64, end: i64| GenericSubtitle {
+/// #     text: text.to_string(),
+/// #     start: start.into(),
+/// #     end: end.into(),
+/// #     coordinates: None,
+/// #     style: None,
+/// # };
+/// let mut cues = vec![cue("a", 0, 2000), cue("b", 1500, 3000)];
+/// let repairs = fix_overlaps(&mut cues, OverlapStrategy::Trim);
+/// assert_eq!(cues[0].end.as_ms(), 1500);
+/// assert_eq!(repairs[0].fix, Fix::Trimmed { by_ms: 500 });
+///
+/// // Cues starting together are merged even when trimming.
+/// let mut cues = vec![cue("a", 0, 2000), cue("b", 0, 1000), cue("c", 2500, 3000)];
+/// let repairs = fix_overlaps(&mut cues, OverlapStrategy::Trim);
+/// assert_eq!(cues.len(), 2);
+/// assert_eq!((cues[0].text.as_str(), cues[0].end.as_ms()), ("a\nb", 2000));
+/// assert_eq!(repairs[0].fix, Fix::Merged { cues: 1 });
+/// ```
+pub fn fix_overlaps(
+    subtitles: &mut Vec<GenericSubtitle>,
+    strategy: OverlapStrategy,
+) -> Vec<Repair> {
+    let mut repairs: Vec<Repair> = vec![];
+    let mut fixed: Vec<GenericSubtitle> = Vec::with_capacity(subtitles.len());
+    for subtitle in subtitles.drain(..) {
+        let cue = fixed.len().saturating_sub(1);
+        if let Some(previous) = fixed.last_mut()
+            && subtitle.start.as_ms() < previous.end.as_ms()
+        {
+            if strategy == OverlapStrategy::Trim && subtitle.start.as_ms() > previous.start.as_ms()
+            {
+                let by_ms = previous.end.as_ms() - subtitle.start.as_ms();
+                previous.end = subtitle.start;
+                repairs.push(Repair {
+                    cue,
+                    start: previous.start,
+                    fix: Fix::Trimmed { by_ms },
+                });
+            } else {
+                merge(previous, subtitle);
+                match repairs.last_mut() {
+                    Some(Repair {
+                        cue: merged,
+                        fix: Fix::Merged { cues },
+                        ..
+                    }) if *merged == cue => *cues += 1,
+                    _ => repairs.push(Repair {
+                        cue,
+                        start: previous.start,
+                        fix: Fix::Merged { cues: 1 },
+                    }),
+                }
+                continue;
+            }
+        }
+        fixed.push(subtitle);
+    }
+    *subtitles = fixed;
+    repairs
+}
+
+/// Repair cues which end at or before they start, showing them for `min_ms`,
+/// or for as long as reading them at `cps` characters per second takes if
+/// that is longer. They aren't extended past the start of the next cue,
+/// unless it starts at the same time.
+///
+/// ```
+/// # use subtitle_translate::{GenericSubtitle, repair::*};
+/// # let cue = |text: &str, start: i64, end: i64| GenericSubtitle {
+/// #     text: text.to_string(),
+/// #     start: start.into(),
+/// #     end: end.into(),
+/// #     coordinates: None,
+/// #     style: None,
+/// # };
+/// let mut cues = vec![
+///     cue("a", 1000, 1000),
+///     cue("b", 1500, 3000),
+///     cue("c", 4000, 3500),
+///     cue("d", 4000, 5000),
+/// ];
+/// let repairs = fix_durations(&mut cues, 2000, None);
+/// assert_eq!(cues[0].end.as_ms(), 1500);
+/// assert_eq!(cues[2].end.as_ms(), 6000);
+/// assert_eq!(repairs[0].fix, Fix::Extended { to_ms: 500 });
+/// assert_eq!(repairs.len(), 2);
+/// ```
+pub fn fix_durations(
+    subtitles: &mut [GenericSubtitle],
+    min_ms: i64,
+    cps: Option<f64>,
+) -> Vec<Repair> {
+    let mut repairs = vec![];
+    for cue in 0..subtitles.len() {
+        let subtitle = &subtitles[cue];
+        if subtitle.duration_ms() > 0 {
+            continue;
+        }
+        let mut duration = min_ms;
+        if let Some(cps) = cps.filter(|cps| *cps > 0.0) {
+            #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+            let reading = (stats::characters(&subtitle.text) as f64 / cps * 1000.0).ceil() as i64;
+            duration = duration.max(reading);
+        }
+        let start = subtitle.start.as_ms();
+        let mut end = start + duration;
+        if let Some(next) = subtitles
+            .get(cue + 1)
+            .map(|next| next.start.as_ms())
+            .filter(|next| *next > start)
+        {
+            end = end.min(next);
+        }
+        subtitles[cue].end = Moment::from(end);
+        repairs.push(Repair {
+            cue,
+            start: subtitles[cue].start,
+            fix: Fix::Extended { to_ms: end - start },
+        });
+    }
+    repairs
+}
+
+/// End cues earlier where needed, so there are at least `gap_ms` between each
+/// and the next. Cues which would be left with no duration are left alone.
+///
+/// ```
+/// # use subtitle_translate::{GenericSubtitle, repair::*};
+/// # let cue = |text: &str, start: i64, end: i64| GenericSubtitle {
+/// #     text: text.to_string(),
+/// #     start: start.into(),
+/// #     end: end.into(),
+/// #     coordinates: None,
+/// #     style: None,
+/// # };
+/// let mut cues = vec![cue("a", 0, 1000), cue("b", 1000, 1050), cue("c", 1100, 2000)];
+/// let repairs = enforce_gap(&mut cues, 100);
+/// assert_eq!(cues[0].end.as_ms(), 900);
+/// assert_eq!(cues[1].end.as_ms(), 1050);
+/// assert_eq!(repairs.len(), 1);
+/// assert_eq!(repairs[0].fix, Fix::Spaced { by_ms: 100 });
+/// ```
+pub fn enforce_gap(subtitles: &mut [GenericSubtitle], gap_ms: i64) -> Vec<Repair> {
+    let mut repairs = vec![];
+    for cue in 1..subtitles.len() {
+        let next_start = subtitles[cue].start.as_ms();
+        let subtitle = &mut subtitles[cue - 1];
+        let end = subtitle.end.as_ms().min(next_start - gap_ms);
+        if end < subtitle.end.as_ms()
+            && end > subtitle.start.as_ms()
+            && next_start >= subtitle.start.as_ms()
+        {
+            repairs.push(Repair {
+                cue: cue - 1,
+                start: subtitle.start,
+                fix: Fix::Spaced {
+                    by_ms: subtitle.end.as_ms() - end,
+                },
+            });
+            subtitle.end = Moment::from(end);
+        }
+    }
+    repairs
+}
+
+/// End cues shown for less than `min_ms` later, as far as leaving `gap_ms`
+/// before the next cue allows. Cues with no duration are left alone.
+///
+/// ```
+/// # use subtitle_translate::{GenericSubtitle, repair::*};
+/// # let cue = |text: &str, start: i64, end: i64| GenericSubtitle {
+/// #     text: text.to_string(),
+/// #     start: start.into(),
+/// #     end: end.into(),
+/// #     coordinates: None,
+/// #     style: None,
+/// # };
+/// let mut cues = vec![cue("a", 0, 500), cue("b", 1200, 1300), cue("c", 1350, 1350)];
+/// let repairs = enforce_duration(&mut cues, 1000, 100);
+/// assert_eq!(cues[0].end.as_ms(), 1000);
+/// // Only as far as the gap before the next cue.
+/// assert_eq!(cues[1].end.as_ms(), 1300);
+/// assert_eq!(cues[2].end.as_ms(), 1350);
+/// assert_eq!(repairs.len(), 1);
+/// assert_eq!(repairs[0].fix, Fix::Lengthened { by_ms: 500 });
+/// ```
+pub fn enforce_duration(
+    subtitles: &mut [GenericSubtitle],
+    min_ms: i64,
+    gap_ms: i64,
+) -> Vec<Repair> {
+    let mut repairs = vec![];
+    for cue in 0..subtitles.len() {
+        let next_start = subtitles.get(cue + 1).map(|next| next.start.as_ms());
+        let subtitle = &mut subtitles[cue];
+        let start = subtitle.start.as_ms();
+        let mut end = start + min_ms;
+        if let Some(next_start) = next_start.filter(|next_start| *next_start >= start) {
+            end = end.min(next_start - gap_ms);
+        }
+        if end > subtitle.end.as_ms() && subtitle.duration_ms() > 0 {
+            repairs.push(Repair {
+                cue,
+                start: subtitle.start,
+                fix: Fix::Lengthened {
+                    by_ms: end - subtitle.end.as_ms(),
+                },
+            });
+            subtitle.end = Moment::from(end);
+        }
+    }
+    repairs
+}
+
+/// The most characters cues are joined into, so text without punctuation
+/// isn't joined into one enormous cue.
+const MAX_JOINED_CHARACTERS: usize = 84;
+
+/// Join consecutive cues each shorter than `max_ms`, with no more than
+/// `max_ms` between them, into one until a sentence ends, as judged by the
+/// rules for the `language`. This makes word-by-word captions readable, and
+/// gives the translator whole sentences.
+///
+/// ```
+/// # use subtitle_translate::{GenericSubtitle, repair::*};
+/// # let cue = |text: &str, start: i64, end: i64| GenericSubtitle {
+/// #     text: text.to_string(),
+/// #     start: start.into(),
+/// #     end: end.into(),
+/// #     coordinates: None,
+/// #     style: None,
+/// # };
+/// let mut cues = vec![
+///     cue("the", 0, 300),
+///     cue("cat", 300, 600),
+///     cue("sat.", 600, 900),
+///     cue("It", 900, 1200),
+///     cue("purred for a while", 1200, 4000),
+/// ];
+/// let repairs = merge_short(&mut cues, 1000, "en");
+/// let texts: Vec<_> = cues.iter().map(|cue| cue.text.as_str()).collect();
+/// // Long cues aren't joined onto.
+/// assert_eq!(texts, ["the cat sat.", "It", "purred for a while"]);
+/// assert_eq!(cues[0].end.as_ms(), 900);
+/// assert_eq!(repairs[0].fix, Fix::Joined { cues: 2 });
+/// ```
+pub fn merge_short(
+    subtitles: &mut Vec<GenericSubtitle>,
+    max_ms: i64,
+    language: &str,
+) -> Vec<Repair> {
+    let mut repairs = vec![];
+    let mut joined: Vec<GenericSubtitle> = Vec::with_capacity(subtitles.len());
+    // Whether the last cue joined onto is short, and so may be joined to.
+    let mut open = false;
+    for subtitle in subtitles.drain(..) {
+        let short = subtitle.duration_ms() < max_ms;
+        let cue = joined.len().saturating_sub(1);
+        if let Some(previous) = joined.last_mut()
+            && open
+            && short
+            && subtitle.start.as_ms() - previous.end.as_ms() <= max_ms
+            && !segment::ends_sentence(&previous.text, language)
+            && stats::characters(&previous.text) + stats::characters(&subtitle.text)
+                < MAX_JOINED_CHARACTERS
+        {
+            if !previous.text.is_empty() && !subtitle.text.is_empty() {
+                previous.text.push(' ');
+            }
+            previous.text.push_str(&subtitle.text);
+            previous.end = subtitle.end;
+            match repairs.last_mut() {
+                Some(Repair {
+                    cue: into,
+                    fix: Fix::Joined { cues },
+                    ..
+                }) if *into == cue => *cues += 1,
+                _ => repairs.push(Repair {
+                    cue,
+                    start: previous.start,
+                    fix: Fix::Joined { cues: 1 },
+                }),
+            }
+            continue;
+        }
+        open = short;
+        joined.push(subtitle);
+    }
+    *subtitles = joined;
+    repairs
+}
+
+/// Merge a cue into the one before it, which then lasts as long as both.
+fn merge(into: &mut GenericSubtitle, subtitle: GenericSubtitle) {
+    if into.text.is_empty() {
+        into.text = subtitle.text;
+    } else if !subtitle.text.is_empty() {
+        into.text.push('\n');
+        into.text.push_str(&subtitle.text);
+    }
+    into.end = Moment::from(into.end.as_ms().max(subtitle.end.as_ms()));
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::BufReader,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use subtitle_translate::{
+    qa::{self, Limits},
+    subtitle::{GenericSubtitle, format_moment},
+    summary::{Status, Summary},
+};
+
+/// The report of a whole run.
+#[derive(Serialize)]
+struct Report<'a> {
+    translated: usize,
+    cached: usize,
+    skipped: usize,
+    failed: usize,
+    characters: usize,
+    languages: &'a BTreeMap<String, usize>,
+    recovered: Vec<String>,
+    repairs: Vec<String>,
+    lines: Vec<Line<'a>>,
+}
+
+/// The report of a single cue.
+#[derive(Serialize)]
+struct Line<'a> {
+    cue: usize,
+    start: String,
+    end: String,
+    status: Status,
+    source: &'a str,
+    translation: &'a str,
+    alternatives: Vec<String>,
+    qa: Vec<String>,
+    elapsed_ms: Option<u128>,
+    error: Option<String>,
+}
+
+/// A single cue flattened for CSV, which can't hold lists.
+#[derive(Serialize)]
+struct CsvLine<'a> {
+    cue: usize,
+    start: &'a str,
+    end: &'a str,
+    status: Status,
+    source: &'a str,
+    translation: &'a str,
+    alternatives: String,
+    qa: String,
+    elapsed_ms: Option<u128>,
+    error: Option<&'a str>,
+}
+
+/// A cue which failed to translate.
+#[derive(Serialize)]
+struct ErrorLine<'a> {
+    cue: usize,
+    start: String,
+    end: String,
+    source: &'a str,
+    error: String,
+}
+
+/// A cue listed in an errors file, of which only its number is needed.
+#[derive(Deserialize)]
+struct ListedCue {
+    cue: usize,
+}
+
+/// Write a report of how each cue was translated, as CSV if the path ends in
+/// `.csv` and as JSON otherwise. Cues are checked against `limits`.
+pub fn write(
+    path: &Path,
+    originals: &[GenericSubtitle],
+    translated: &[GenericSubtitle],
+    summary: &Summary,
+    limits: Limits,
+) -> anyhow::Result<()> {
+    let mut problems: Vec<Vec<String>> = vec![vec![]; translated.len()];
+    for issue in qa::check(translated, limits) {
+        problems[issue.cue].push(issue.problem.to_string());
+    }
+
+    let lines: Vec<_> = originals
+        .iter()
+        .zip(translated)
+        .zip(problems)
+        .enumerate()
+        .map(|(cue, ((original, translation), qa))| {
+            let outcome = summary.outcome(cue);
+            Line {
+                cue: cue + 1,
+                start: format_moment(original.start),
+                end: format_moment(original.end),
+                status: outcome.status,
+                source: &original.text,
+                translation: if outcome.status.is_translated() {
+                    &translation.text
+                } else {
+                    ""
+                },
+                alternatives: outcome.alternatives,
+                qa,
+                elapsed_ms: outcome.elapsed.map(|elapsed| elapsed.as_millis()),
+                error: outcome.error,
+            }
+        })
+        .collect();
+
+    if is_csv(path) {
+        let mut writer = csv::Writer::from_path(path)?;
+        for line in &lines {
+            writer.serialize(CsvLine {
+                cue: line.cue,
+                start: &line.start,
+                end: &line.end,
+                status: line.status,
+                source: line.source,
+                translation: line.translation,
+                alternatives: line.alternatives.join(" | "),
+                qa: line.qa.join("; "),
+                elapsed_ms: line.elapsed_ms,
+                error: line.error.as_deref(),
+            })?;
+        }
+        writer.flush()?;
+    } else {
+        let report = Report {
+            translated: summary.translated,
+            cached: summary.cached,
+            skipped: summary.skipped,
+            failed: summary.failed,
+            characters: summary.characters,
+            languages: &summary.languages,
+            recovered: summary.recovered.iter().map(ToString::to_string).collect(),
+            repairs: summary.repairs.iter().map(ToString::to_string).collect(),
+            lines,
+        };
+        serde_json::to_writer_pretty(File::create(path)?, &report)?;
+    }
+    Ok(())
+}
+
+/// Write the cues which failed to translate, with their timing, text and
+/// error, as CSV if the path ends in `.csv` and as JSON otherwise.
+pub fn write_errors(
+    path: &Path,
+    originals: &[GenericSubtitle],
+    summary: &Summary,
+) -> anyhow::Result<()> {
+    let lines: Vec<_> = originals
+        .iter()
+        .enumerate()
+        .filter_map(|(cue, original)| {
+            let outcome = summary.outcome(cue);
+            (outcome.status == Status::Failed).then(|| ErrorLine {
+                cue: cue + 1,
+                start: format_moment(original.start),
+                end: format_moment(original.end),
+                source: &original.text,
+                error: outcome.error.unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    if is_csv(path) {
+        let mut writer = csv::Writer::from_path(path)?;
+        for line in &lines {
+            writer.serialize(line)?;
+        }
+        writer.flush()?;
+    } else {
+        serde_json::to_writer_pretty(File::create(path)?, &lines)?;
+    }
+    Ok(())
+}
+
+/// Read the numbers of the cues listed in a file written by
+/// [`write_errors`], which are numbered from 1.
+pub fn read_cues(path: &Path) -> anyhow::Result<BTreeSet<usize>> {
+    let listed: Vec<ListedCue> = if is_csv(path) {
+        csv::Reader::from_path(path)?
+            .deserialize()
+            .collect::<Result<_, _>>()?
+    } else {
+        serde_json::from_reader(BufReader::new(File::open(path)?))?
+    };
+    Ok(listed.into_iter().map(|listed| listed.cue).collect())
+}
+
+/// Whether a report is written as CSV, by its extension.
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+}
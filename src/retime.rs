@@ -0,0 +1,54 @@
+use anyhow::Context;
+
+use subtitle_translate::{subtitle, timing};
+
+use crate::{
+    cli::TimingCommand,
+    exit_code::{Failure, WithFailure},
+};
+
+/// Change when the cues of a subtitle file are shown.
+pub fn run(command: &TimingCommand) -> anyhow::Result<()> {
+    let files = match command {
+        TimingCommand::Shift { files, .. }
+        | TimingCommand::Scale { files, .. }
+        | TimingCommand::Retime { files, .. } => files,
+    };
+    let output = files.output.as_ref().unwrap_or(&files.input);
+    if output != &files.input && output.exists() && !files.overwrite {
+        anyhow::bail!(
+            "Destination file {} already exists, pass --overwrite to replace it",
+            output.display()
+        );
+    }
+    if files.output.is_none() && !files.overwrite {
+        anyhow::bail!("Pass --output, or --overwrite to change the file in place");
+    }
+
+    let mut subtitles = subtitle::read(&files.input)
+        .context("Failed to read subtitles")
+        .failure(Failure::Parse)?;
+    match command {
+        TimingCommand::Shift { offset_ms, .. } => {
+            let cues = subtitles.len();
+            timing::shift(&mut subtitles, *offset_ms);
+            if subtitles.len() < cues {
+                tracing::warn!(
+                    "Dropped {} cue(s) moved to before the start",
+                    cues - subtitles.len()
+                );
+            }
+        }
+        TimingCommand::Scale { factor, .. } => timing::scale(&mut subtitles, *factor),
+        TimingCommand::Retime {
+            cps, min_duration, ..
+        } => {
+            let changed = timing::retime(&mut subtitles, *cps, i64::from(*min_duration));
+            tracing::info!("Retimed {changed} cue(s)");
+        }
+    }
+
+    subtitle::write(&subtitles, output)
+        .context("Failed to write subtitles")
+        .failure(Failure::Write)
+}
@@ -0,0 +1,404 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Cell, LineGauge, Paragraph, Row, Table, TableState, Wrap},
+};
+
+use subtitle_translate::{
+    libretranslate::LibreTranslate,
+    subtitle::{self, GenericSubtitle, format_moment},
+};
+
+use crate::{
+    cli::{EngineArgs, ReviewArgs},
+    exit_code::{Failure, WithFailure},
+};
+
+/// Lines longer than this many characters are flagged for review.
+const MAX_LINE_LENGTH: usize = 42;
+
+/// The marker used for line breaks while editing a cue.
+const LINE_BREAK_MARKER: char = '|';
+
+/// Open the full-screen review interface for a translated subtitle file.
+pub async fn run(engine: &EngineArgs, args: ReviewArgs) -> anyhow::Result<()> {
+    let translated = subtitle::read(&args.file)
+        .context("Failed to read subtitles to review")
+        .failure(Failure::Parse)?;
+    let originals = match &args.source {
+        Some(path) => subtitle::read(path)
+            .context("Failed to read source subtitles")
+            .failure(Failure::Parse)?,
+        None => vec![],
+    };
+    if !originals.is_empty() && originals.len() != translated.len() {
+        tracing::warn!(
+            "Source has {} cues but the translation has {}, lines may not match up",
+            originals.len(),
+            translated.len()
+        );
+    }
+
+    let mut app = App {
+        path: args.file,
+        translator: engine.translator(),
+        language_from: args.language_from.to_ascii_lowercase(),
+        language_to: args.language_to.map(|l| l.to_ascii_lowercase()),
+        originals,
+        translated,
+        table: TableState::default().with_selected(0),
+        editing: None,
+        status: String::new(),
+        dirty: false,
+        confirm_quit: false,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal).await;
+    ratatui::restore();
+    result
+}
+
+/// An in-progress edit of the selected cue.
+struct Edit {
+    buffer: Vec<char>,
+    cursor: usize,
+}
+
+struct App {
+    path: PathBuf,
+    translator: LibreTranslate,
+    language_from: String,
+    language_to: Option<String>,
+    originals: Vec<GenericSubtitle>,
+    translated: Vec<GenericSubtitle>,
+    table: TableState,
+    editing: Option<Edit>,
+    status: String,
+    dirty: bool,
+    confirm_quit: bool,
+}
+
+impl App {
+    async fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if self.editing.is_some() {
+                self.handle_edit_key(key.code);
+                continue;
+            }
+
+            let confirm_quit = std::mem::take(&mut self.confirm_quit);
+            self.status.clear();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    if !self.dirty || confirm_quit {
+                        return Ok(());
+                    }
+                    self.status = "Unsaved changes, press q again to quit".to_string();
+                    self.confirm_quit = true;
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+                KeyCode::PageUp => self.table.scroll_up_by(10),
+                KeyCode::PageDown => self.table.scroll_down_by(10),
+                KeyCode::Home | KeyCode::Char('g') => self.table.select_first(),
+                KeyCode::End | KeyCode::Char('G') => self.table.select_last(),
+                KeyCode::Char('n') => self.jump_to_flagged(true),
+                KeyCode::Char('N' | 'p') => self.jump_to_flagged(false),
+                KeyCode::Enter | KeyCode::Char('e') => self.start_edit(),
+                KeyCode::Char('r') => {
+                    self.status = "Translating…".to_string();
+                    terminal.draw(|frame| self.draw(frame))?;
+                    self.retranslate().await;
+                }
+                KeyCode::Char('s') => self.save(),
+                _ => {}
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.table
+            .selected()
+            .map(|idx| idx.min(self.translated.len().saturating_sub(1)))
+            .filter(|_| !self.translated.is_empty())
+    }
+
+    /// Describe why a cue needs attention, if it does.
+    fn flag(&self, idx: usize) -> Option<&'static str> {
+        let line = &self.translated[idx];
+        if let Some(original) = self.originals.get(idx) {
+            if !original.text.is_empty() && line.text.trim().is_empty() {
+                return Some("empty translation");
+            }
+            if !original.text.is_empty() && original.text == line.text {
+                return Some("identical to source");
+            }
+        }
+        if line
+            .text
+            .lines()
+            .any(|l| l.chars().count() > MAX_LINE_LENGTH)
+        {
+            return Some("line too long");
+        }
+        None
+    }
+
+    fn jump_to_flagged(&mut self, forwards: bool) {
+        let len = self.translated.len();
+        let Some(current) = self.selected() else {
+            return;
+        };
+        let found = (1..len)
+            .map(|offset| {
+                if forwards {
+                    (current + offset) % len
+                } else {
+                    (current + len - offset) % len
+                }
+            })
+            .find(|&idx| self.flag(idx).is_some());
+        match found {
+            Some(idx) => self.table.select(Some(idx)),
+            None => self.status = "No other flagged lines".to_string(),
+        }
+    }
+
+    fn start_edit(&mut self) {
+        let Some(idx) = self.selected() else {
+            return;
+        };
+        let buffer: Vec<char> = self.translated[idx]
+            .text
+            .chars()
+            .map(|c| if c == '\n' { LINE_BREAK_MARKER } else { c })
+            .collect();
+        self.editing = Some(Edit {
+            cursor: buffer.len(),
+            buffer,
+        });
+    }
+
+    fn handle_edit_key(&mut self, code: KeyCode) {
+        let Some(edit) = self.editing.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.editing = None,
+            KeyCode::Enter => {
+                let text = edit
+                    .buffer
+                    .iter()
+                    .map(|&c| if c == LINE_BREAK_MARKER { '\n' } else { c })
+                    .collect();
+                if let Some(idx) = self.selected() {
+                    self.translated[idx].text = text;
+                    self.dirty = true;
+                }
+                self.editing = None;
+            }
+            KeyCode::Char(c) => {
+                edit.buffer.insert(edit.cursor, c);
+                edit.cursor += 1;
+            }
+            KeyCode::Backspace if edit.cursor > 0 => {
+                edit.cursor -= 1;
+                edit.buffer.remove(edit.cursor);
+            }
+            KeyCode::Delete if edit.cursor < edit.buffer.len() => {
+                edit.buffer.remove(edit.cursor);
+            }
+            KeyCode::Left => edit.cursor = edit.cursor.saturating_sub(1),
+            KeyCode::Right => edit.cursor = (edit.cursor + 1).min(edit.buffer.len()),
+            KeyCode::Home => edit.cursor = 0,
+            KeyCode::End => edit.cursor = edit.buffer.len(),
+            _ => {}
+        }
+    }
+
+    async fn retranslate(&mut self) {
+        let Some(idx) = self.selected() else {
+            return;
+        };
+        let Some(original) = self.originals.get(idx) else {
+            self.status = "Re-translating needs the source file (--source)".to_string();
+            return;
+        };
+        let Some(target) = &self.language_to else {
+            self.status = "Re-translating needs a target language (--language-to)".to_string();
+            return;
+        };
+
+        match self
+            .translator
+            .translate(original.text.clone(), &self.language_from, target, 0)
+            .await
+        {
+            Ok(translation) => {
+                self.translated[idx].text = translation.translated_text;
+                self.dirty = true;
+                self.status = "Re-translated line".to_string();
+            }
+            Err(e) => self.status = format!("Failed to translate line: {e}"),
+        }
+    }
+
+    fn save(&mut self) {
+        match subtitle::write_srt(&self.translated, &self.path) {
+            Ok(()) => {
+                self.dirty = false;
+                self.status = format!("Saved {}", self.path.display());
+            }
+            Err(e) => self.status = format!("Failed to save: {e}"),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [table_area, detail_area, timeline_area, status_area] = Layout::vertical([
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let rows = self.translated.iter().enumerate().map(|(idx, line)| {
+            let original = self
+                .originals
+                .get(idx)
+                .map(|o| single_line(&o.text))
+                .unwrap_or_default();
+            let flagged = self.flag(idx).is_some();
+            let row = Row::new([
+                Cell::from(if flagged { "!" } else { " " }),
+                Cell::from((idx + 1).to_string()),
+                Cell::from(format_moment(line.start)),
+                Cell::from(original),
+                Cell::from(single_line(&line.text)),
+            ]);
+            if flagged { row.yellow() } else { row }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(1),
+                Constraint::Length(5),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["", "#", "Start", "Source", "Translation"]).bold())
+        .block(Block::bordered().title(self.path.display().to_string()))
+        .row_highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        if let Some(idx) = self.selected() {
+            self.draw_detail(frame, detail_area, idx);
+            self.draw_timeline(frame, timeline_area, idx);
+        } else {
+            frame.render_widget(Paragraph::new("No cues to review"), detail_area);
+        }
+
+        let status = if !self.status.is_empty() {
+            Line::from(self.status.as_str())
+        } else if self.editing.is_some() {
+            Line::from(format!(
+                "Editing: enter to confirm, esc to cancel, {LINE_BREAK_MARKER} for a line break"
+            ))
+        } else {
+            Line::from(
+                "↑/↓ move  n/N next/previous flagged  e edit  r re-translate  s save  q quit",
+            )
+        };
+        frame.render_widget(Paragraph::new(status).dim(), status_area);
+    }
+
+    /// Draw the source and translation of the selected cue side by side.
+    fn draw_detail(&self, frame: &mut Frame, area: Rect, idx: usize) {
+        let detail_block = Block::bordered().title(match self.flag(idx) {
+            Some(flag) => format!("Cue {} ({flag})", idx + 1),
+            None => format!("Cue {}", idx + 1),
+        });
+        let inner = detail_block.inner(area);
+        frame.render_widget(detail_block, area);
+        let [source_area, translation_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(inner);
+        let source = self
+            .originals
+            .get(idx)
+            .map(|o| o.text.clone())
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(source).wrap(Wrap { trim: false }),
+            source_area,
+        );
+        if let Some(edit) = &self.editing {
+            let text: String = edit.buffer.iter().collect();
+            frame.render_widget(
+                Paragraph::new(text).wrap(Wrap { trim: false }).cyan(),
+                translation_area,
+            );
+            let width = usize::from(translation_area.width.max(1));
+            let (row, col) = (edit.cursor / width, edit.cursor % width);
+            frame.set_cursor_position(Position::new(
+                translation_area.x + u16::try_from(col).unwrap_or(u16::MAX),
+                translation_area.y + u16::try_from(row).unwrap_or(u16::MAX),
+            ));
+        } else {
+            frame.render_widget(
+                Paragraph::new(self.translated[idx].text.clone()).wrap(Wrap { trim: false }),
+                translation_area,
+            );
+        }
+    }
+
+    /// Draw where the selected cue sits in the running time of the file.
+    fn draw_timeline(&self, frame: &mut Frame, area: Rect, idx: usize) {
+        let position = self.translated[idx].start.as_ms();
+        let duration = self
+            .translated
+            .iter()
+            .map(|l| l.end.as_ms())
+            .max()
+            .unwrap_or_default();
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = if duration > 0 {
+            (position as f64 / duration as f64).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        frame.render_widget(
+            LineGauge::default()
+                .filled_style(Style::new().cyan())
+                .label(format!(
+                    "{} / {}",
+                    format_moment(self.translated[idx].start),
+                    format_moment(duration.into())
+                ))
+                .ratio(ratio),
+            area,
+        );
+    }
+}
+
+/// Squash a multi-line cue onto one line for the table view.
+fn single_line(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join(" / ")
+}
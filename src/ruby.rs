@@ -0,0 +1,143 @@
+//! Ruby annotations, such as furigana, which give the reading of the text
+//! they are attached to and would be translated as gibberish if left in.
+//!
+//! Both HTML ruby, `<ruby>漢字<rt>かんじ</rt></ruby>`, and the furigana in
+//! karaoke lines understood by Aegisub's karaoke templater, `{\k20}漢字|かんじ`,
+//! are recognised.
+
+use std::collections::HashMap;
+
+use crate::{cli::RubyPolicy, hooks::Hook};
+
+/// Removes ruby annotations before lines are translated, leaving the text they
+/// annotate, and adds them to the end of the translations if they are kept.
+pub struct Ruby {
+    policy: RubyPolicy,
+    /// The annotations removed from each cue, by cue, until their
+    /// translations are seen.
+    removed: HashMap<usize, Vec<Annotation>>,
+}
+
+/// Text and the reading given for it.
+struct Annotation {
+    base: String,
+    reading: String,
+}
+
+impl Ruby {
+    pub fn new(policy: RubyPolicy) -> Self {
+        Self {
+            policy,
+            removed: HashMap::new(),
+        }
+    }
+}
+
+impl Hook for Ruby {
+    fn pre_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let mut annotations = Vec::new();
+        let line = strip_html(line, &mut annotations);
+        let line = strip_karaoke(&line, &mut annotations);
+        if matches!(self.policy, RubyPolicy::Keep) {
+            self.removed.insert(cue, annotations);
+        }
+        Ok(line)
+    }
+
+    fn post_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let Some(annotations) = self.removed.remove(&cue).filter(|a| !a.is_empty()) else {
+            return Ok(line.to_string());
+        };
+        let readings: Vec<_> = annotations
+            .iter()
+            .map(|annotation| format!("{} ({})", annotation.base, annotation.reading))
+            .collect();
+        Ok(format!("{line}\n{}", readings.join(", ")))
+    }
+}
+
+/// Remove HTML ruby, keeping the text it annotates.
+fn strip_html(line: &str, annotations: &mut Vec<Annotation>) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("<ruby>") {
+        let Some(end) = rest[start..].find("</ruby>") else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let inner = &rest[start + "<ruby>".len()..start + end];
+        let (base, reading) = match inner.split_once("<rt>") {
+            Some((base, reading)) => (base, reading),
+            None => (inner, ""),
+        };
+        let base = without_tag(base, "rp");
+        let reading = without_tag(reading.replace("</rt>", "").as_str(), "rp");
+        result.push_str(&base);
+        if !reading.is_empty() {
+            annotations.push(Annotation { base, reading });
+        }
+        rest = &rest[start + end + "</ruby>".len()..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Remove the elements with a tag, such as the `<rp>` fallback parentheses.
+fn without_tag(text: &str, tag: &str) -> String {
+    let (open, close) = (format!("<{tag}>"), format!("</{tag}>"));
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(&open) {
+        result.push_str(&rest[..start]);
+        rest = match rest[start..].find(&close) {
+            Some(end) => &rest[start + end + close.len()..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+    result.trim().to_string()
+}
+
+/// Remove the furigana from a karaoke line's syllables, each of which is
+/// written `base|reading` after its `\k` tag.
+fn strip_karaoke(line: &str, annotations: &mut Vec<Annotation>) -> String {
+    let lower = line.to_ascii_lowercase();
+    if !lower.contains("\\k") || !line.contains('|') {
+        return line.to_string();
+    }
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    while !rest.is_empty() {
+        // Override blocks are kept as they are, and separate the syllables.
+        if rest.starts_with('{') {
+            let end = rest.find('}').map_or(rest.len(), |end| end + 1);
+            result.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        let end = rest.find('{').unwrap_or(rest.len());
+        let syllable = &rest[..end];
+        match syllable.split_once('|') {
+            Some((base, reading)) => {
+                let reading = reading.trim_start_matches('<');
+                // `#` continues the reading of the previous syllable's base.
+                if base == "#"
+                    && let Some(previous) = annotations.last_mut()
+                {
+                    previous.reading.push_str(reading);
+                } else {
+                    result.push_str(base);
+                    if !reading.is_empty() {
+                        annotations.push(Annotation {
+                            base: base.to_string(),
+                            reading: reading.to_string(),
+                        });
+                    }
+                }
+            }
+            None => result.push_str(syllable),
+        }
+        rest = &rest[end..];
+    }
+    result
+}
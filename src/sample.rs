@@ -0,0 +1,55 @@
+use anyhow::Context;
+
+use subtitle_translate::{
+    GenericSubtitle, TranslationPipeline, entities, subtitle, summary::Summary,
+};
+
+use crate::{
+    cli::{CHUNK_SIZE, EngineArgs, SampleArgs},
+    exit_code::{self, Failure, WithFailure},
+    preview,
+};
+
+/// Translate an evenly spaced sample of a subtitle file's cues and print them
+/// beside their translations, to judge the quality of a translation before
+/// translating the whole file.
+pub async fn run(engine: &EngineArgs, args: &SampleArgs) -> anyhow::Result<()> {
+    let subtitles = subtitle::read(&args.file)
+        .context("Failed to read subtitles")
+        .failure(Failure::Parse)?;
+    let with_text: Vec<_> = subtitles
+        .into_iter()
+        .filter(|subtitle| !subtitle::is_blank(&subtitle.text))
+        .map(|subtitle| GenericSubtitle {
+            text: entities::decode(&subtitle.text),
+            ..subtitle
+        })
+        .collect();
+    let count = args.lines.min(with_text.len());
+    let originals: Vec<_> = (0..count)
+        .map(|idx| with_text[idx * with_text.len() / count].clone())
+        .collect();
+
+    let mut translated = originals.clone();
+    let pipeline = TranslationPipeline::new(engine.translator(), &args.language_to)
+        .source(&args.language_from)
+        .chunk_size(CHUNK_SIZE);
+    if let Err(e) = pipeline
+        .translate(&mut translated, &mut Summary::new(), |_| {})
+        .await
+    {
+        let failure = if exit_code::is_unreachable(&e) {
+            Failure::Unreachable
+        } else {
+            Failure::Translation
+        };
+        return Err(e.context("Failed to translate the sample")).failure(failure);
+    }
+
+    preview::print_terminal(&originals, &translated);
+    println!(
+        "\nTranslated {count} of {} cue(s) with text",
+        with_text.len()
+    );
+    Ok(())
+}
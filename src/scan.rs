@@ -0,0 +1,313 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use subtitle_translate::{
+    languages::{self, KNOWN_LANGUAGES, KnownLanguage},
+    subtitle::{self, SubtitleFormat},
+};
+use tokio::process::Command;
+
+use crate::{
+    cli::{EngineArgs, ScanLibraryArgs, TranslateArgs},
+    cost, translate,
+};
+
+/// The extensions of video files media centres play.
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "avi", "m2ts", "m4v", "mkv", "mov", "mp4", "mpg", "ts", "webm", "wmv",
+];
+
+/// The codecs of embedded subtitle tracks which are text, and so can be
+/// translated.
+const TEXT_CODECS: &[&str] = &["ass", "mov_text", "ssa", "subrip", "text", "webvtt"];
+
+/// Where a video's source subtitles come from.
+enum Source {
+    /// A subtitle file beside the video.
+    External(PathBuf),
+    /// A track inside the video, with the stream index ffmpeg gives it.
+    Embedded(usize),
+}
+
+/// A video which has subtitles in the source language but not the target.
+struct Candidate {
+    video: PathBuf,
+    source: Source,
+    destination: PathBuf,
+}
+
+/// Translate the subtitles of every video in a library which has them in the
+/// source language but not the target, continuing past failures.
+///
+/// Subtitle files belong to a video if they are named after it, and their
+/// language is given by a tag in the name, such as `Film.en.srt` or
+/// `Film.eng.forced.srt`. Embedded tracks are found with `ffprobe` and
+/// extracted with `ffmpeg`, if they are installed.
+pub async fn run(engine: &EngineArgs, args: &ScanLibraryArgs) -> anyhow::Result<()> {
+    let from = known(&args.language_from)?;
+    let to = known(&args.language_to)?;
+    let tag = target_tag(&args.language_to)?;
+    let mut videos = Vec::new();
+    find_videos(&args.media_root, &mut videos)?;
+    videos.sort();
+
+    let mut candidates = Vec::new();
+    for video in videos {
+        let subtitles = subtitles_of(&video)?;
+        if subtitles.iter().any(|(_, tags)| has_target(tags, to, &tag)) {
+            continue;
+        }
+        let source = match subtitles
+            .into_iter()
+            .find(|(_, tags)| has_language(tags, from))
+        {
+            Some((path, _)) => Source::External(path),
+            None => match embedded_track(&video, from).await {
+                Some(index) => Source::Embedded(index),
+                None => continue,
+            },
+        };
+        let destination = translate::default_destination(&video, &tag);
+        candidates.push(Candidate {
+            video,
+            source,
+            destination,
+        });
+    }
+
+    let total = candidates.len();
+    eprintln!(
+        "Found {total} video(s) with {} but not {} subtitles",
+        from.name, to.name
+    );
+    if args.dry_run {
+        for candidate in &candidates {
+            let source = match &candidate.source {
+                Source::External(path) => path.display().to_string(),
+                Source::Embedded(index) => format!("embedded track {index}"),
+            };
+            println!(
+                "{}: {source} -> {}",
+                candidate.video.display(),
+                candidate.destination.display()
+            );
+        }
+        if args.estimate_cost {
+            print_estimate(&candidates)?;
+        }
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    let mut first_error = None;
+    for (idx, candidate) in candidates.into_iter().enumerate() {
+        eprint!(
+            "[{}/{total}] {} -> {} ... ",
+            idx + 1,
+            candidate.video.display(),
+            candidate.destination.display()
+        );
+        match translate_candidate(engine, args, candidate).await {
+            Ok(()) => eprintln!("done"),
+            Err(e) => {
+                eprintln!("failed: {e:#}");
+                failed += 1;
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    if total > 0 {
+        eprintln!("{} of {total} video(s) translated", total - failed);
+    }
+    match first_error {
+        Some(e) => Err(e).context(format!("{failed} of {total} video(s) failed")),
+        None => Ok(()),
+    }
+}
+
+/// Print what translating the candidates' external subtitles would cost.
+/// Embedded tracks aren't extracted to be counted.
+fn print_estimate(candidates: &[Candidate]) -> anyhow::Result<()> {
+    let mut usage = cost::Usage::default();
+    let mut embedded = 0;
+    for candidate in candidates {
+        match &candidate.source {
+            Source::External(path) => match subtitle::read(path) {
+                Ok(subtitles) => usage.add(cost::Usage::of(&subtitles)),
+                Err(e) => tracing::warn!("Couldn't read {} to count: {e}", path.display()),
+            },
+            Source::Embedded(_) => embedded += 1,
+        }
+    }
+    println!();
+    cost::print_estimate(usage)?;
+    if embedded > 0 {
+        println!("Not counting {embedded} video(s) with only embedded subtitles");
+    }
+    Ok(())
+}
+
+/// Look up a language given on the command line, by its code or a BCP-47
+/// tag such as `pt-BR`.
+pub fn known(code: &str) -> anyhow::Result<&'static KnownLanguage> {
+    let resolved = languages::resolve(code).ok();
+    KNOWN_LANGUAGES
+        .iter()
+        .find(|language| Some(language.code) == resolved.as_deref())
+        .with_context(|| format!("Unknown language '{code}'"))
+}
+
+/// The tag to name translations into a language given on the command line
+/// with, keeping any region or script, such as `pt-BR`.
+pub fn target_tag(code: &str) -> anyhow::Result<String> {
+    languages::resolve_target(code).map_err(|_| anyhow::anyhow!("Unknown language '{code}'"))
+}
+
+/// Whether a subtitle's tags name a target language, as [`has_language`]
+/// does or by the tag translations into it are named with.
+pub fn has_target(tags: &[String], language: &KnownLanguage, tag: &str) -> bool {
+    has_language(tags, language) || tags.contains(&tag.to_ascii_lowercase())
+}
+
+/// Recursively collect the videos in a folder.
+fn find_videos(dir: &Path, videos: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_videos(&path, videos)?;
+        } else if path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|extension| {
+                VIDEO_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            })
+        {
+            videos.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Find the subtitle files beside a video which are named after it, with the
+/// lowercased tags between the video's name and the extension.
+pub fn subtitles_of(video: &Path) -> anyhow::Result<Vec<(PathBuf, Vec<String>)>> {
+    let (Some(dir), Some(stem)) = (video.parent(), video.file_stem().and_then(OsStr::to_str))
+    else {
+        return Ok(Vec::new());
+    };
+    let prefix = format!("{stem}.");
+    let mut subtitles = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(rest) = path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .and_then(|name| name.strip_prefix(&prefix))
+        else {
+            continue;
+        };
+        if SubtitleFormat::from_path(&path).is_some() {
+            let tags = rest.split('.').map(str::to_ascii_lowercase).collect();
+            subtitles.push((path, tags));
+        }
+    }
+    subtitles.sort();
+    Ok(subtitles)
+}
+
+/// Whether a subtitle's tags name a language, by its code, ISO 639-2 code or
+/// English name.
+pub fn has_language(tags: &[String], language: &KnownLanguage) -> bool {
+    tags.iter().any(|tag| {
+        tag == language.code
+            || language.alpha3.contains(&tag.as_str())
+            || tag.eq_ignore_ascii_case(language.name)
+    })
+}
+
+/// Find a text subtitle track in a language embedded in a video, returning
+/// its stream index. Nothing is found if `ffprobe` isn't installed.
+async fn embedded_track(video: &Path, language: &KnownLanguage) -> Option<usize> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "s"])
+        .args([
+            "-show_entries",
+            "stream=index,codec_name:stream_tags=language",
+        ])
+        .args(["-of", "csv=p=0"])
+        .arg(video)
+        .output()
+        .await;
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            tracing::debug!(
+                "ffprobe failed on {}: {}",
+                video.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        Err(e) => {
+            tracing::debug!("Couldn't run ffprobe: {e}");
+            return None;
+        }
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split(',');
+            let index = fields.next()?.parse().ok()?;
+            let codec = fields.next()?;
+            let tag = fields.next()?.to_ascii_lowercase();
+            (TEXT_CODECS.contains(&codec) && has_language(&[tag], language)).then_some(index)
+        })
+}
+
+/// Translate a video's source subtitles, extracting them first if they are
+/// embedded.
+async fn translate_candidate(
+    engine: &EngineArgs,
+    args: &ScanLibraryArgs,
+    candidate: Candidate,
+) -> anyhow::Result<()> {
+    let (source, extracted) = match candidate.source {
+        Source::External(path) => (path, false),
+        Source::Embedded(index) => {
+            let path =
+                std::env::temp_dir().join(format!("subtitle-translate-{}.srt", std::process::id()));
+            let status = Command::new("ffmpeg")
+                .args(["-v", "error", "-y", "-i"])
+                .arg(&candidate.video)
+                .args(["-map", &format!("0:{index}"), "-f", "srt"])
+                .arg(&path)
+                .status()
+                .await
+                .context("Failed to run ffmpeg")?;
+            if !status.success() {
+                anyhow::bail!("ffmpeg couldn't extract the embedded subtitles");
+            }
+            (path, true)
+        }
+    };
+
+    let mut translate_args = TranslateArgs::new(
+        source.clone(),
+        args.language_from.clone(),
+        args.language_to.clone(),
+        Some(candidate.destination),
+    );
+    translate_args.chunk_size = args.chunk_size;
+    let result = translate::run(engine, translate_args, true).await;
+    if extracted && let Err(e) = std::fs::remove_file(&source) {
+        tracing::warn!("Failed to remove extracted subtitles: {e}");
+    }
+    result
+}
@@ -0,0 +1,81 @@
+//! Lua scripts, for light changes to lines without compiling a plugin.
+//!
+//! A script may define any of these global functions:
+//!
+//! - `on_source_line(line)`, called with each line before it is translated.
+//! - `on_translated_line(line)`, called with each translation.
+//! - `on_cue(cue)`, called with each translated cue as a table of `index`,
+//!   `start` and `end` (in milliseconds) and `text`. Changes to the table are
+//!   kept, as is a table it returns instead.
+//!
+//! The line functions return the replacement line, or `nil` to leave it as it
+//! is.
+
+use std::path::Path;
+
+use anyhow::Context;
+use mlua::{Function, Lua, Table};
+use subtitle_translate::subtitle::GenericSubtitle;
+
+use crate::hooks::Hook;
+
+pub struct Script {
+    lua: Lua,
+}
+
+impl Script {
+    /// Load and run a script, which defines its hooks.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read script {}", path.display()))?;
+        let lua = Lua::new();
+        lua.load(source)
+            .set_name(path.display().to_string())
+            .exec()
+            .with_context(|| format!("Failed to run script {}", path.display()))?;
+        Ok(Self { lua })
+    }
+
+    /// Get one of the script's functions, if it defines it.
+    fn function(&self, name: &str) -> anyhow::Result<Option<Function<'_>>> {
+        Ok(self.lua.globals().get(name)?)
+    }
+
+    /// Pass a line to one of the script's functions.
+    fn call(&self, name: &str, line: &str) -> anyhow::Result<String> {
+        let Some(function) = self.function(name)? else {
+            return Ok(line.to_string());
+        };
+        let replacement: Option<String> = function.call(line)?;
+        Ok(replacement.unwrap_or_else(|| line.to_string()))
+    }
+}
+
+impl Hook for Script {
+    fn pre_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        self.call("on_source_line", line)
+    }
+
+    fn post_translate(&mut self, _cue: usize, line: &str) -> anyhow::Result<String> {
+        self.call("on_translated_line", line)
+    }
+
+    fn on_cue(&mut self, cue: usize, subtitle: &mut GenericSubtitle) -> anyhow::Result<()> {
+        let Some(function) = self.function("on_cue")? else {
+            return Ok(());
+        };
+        let table = self.lua.create_table()?;
+        table.set("index", cue)?;
+        table.set("start", subtitle.start.as_ms())?;
+        table.set("end", subtitle.end.as_ms())?;
+        table.set("text", subtitle.text.as_str())?;
+        let table = function
+            .call::<_, Option<Table>>(table.clone())?
+            .unwrap_or(table);
+
+        subtitle.start = table.get::<_, i64>("start")?.into();
+        subtitle.end = table.get::<_, i64>("end")?.into();
+        subtitle.text = table.get("text")?;
+        Ok(())
+    }
+}
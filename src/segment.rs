@@ -0,0 +1,165 @@
+//! Splitting text into sentences by rules for each language.
+//!
+//! As with SRX, a sentence may end at any terminating punctuation, unless an
+//! exception applies: a known abbreviation or an initial before a full stop,
+//! a lowercase word after it, or no space after it in languages which put
+//! spaces between sentences. Spanish `¿` and `¡` open the next sentence, and
+//! Chinese and Japanese full-width punctuation ends one without a space.
+
+/// Punctuation which may end a sentence.
+const TERMINATORS: &[char] = &[
+    '.', '?', '!', '…', '‼', '⁇', '⁈', '⁉', '。', '．', '？', '！', '｡',
+];
+
+/// Punctuation which closes a sentence after its terminator.
+const CLOSING: &[char] = &[
+    '"', '\'', ')', ']', '»', '”', '’', '」', '』', '）', '】', '》', '〉',
+];
+
+/// Punctuation which may open a sentence before its first word.
+const OPENING: &[char] = &[
+    '"', '\'', '(', '[', '«', '“', '‘', '¿', '¡', '-', '–', '—', '「', '『', '（', '【',
+];
+
+/// Where sentences end in a language.
+#[derive(Clone, Copy, Debug)]
+pub struct Rules {
+    /// Words which are followed by a full stop without ending a sentence,
+    /// without the final full stop.
+    pub abbreviations: &'static [&'static str],
+    /// Whether sentences are separated by spaces. If not, only full-width
+    /// punctuation ends a sentence without a space after it.
+    pub spaced: bool,
+}
+
+impl Rules {
+    /// The rules for a language, given as a code such as `en` or `zh-Hans`.
+    /// Languages without rules of their own get only the general ones.
+    #[must_use]
+    pub fn for_language(language: &str) -> Self {
+        let base = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let abbreviations: &[&str] = match base.as_str() {
+            "en" => &[
+                "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "vs", "e.g", "i.e", "lt",
+                "col", "gen", "capt", "sgt", "rev", "fig", "approx",
+            ],
+            "de" => &[
+                "hr", "fr", "dr", "prof", "nr", "st", "z.b", "bzw", "ca", "vgl", "d.h", "u.a",
+                "evtl", "ggf",
+            ],
+            "fr" => &["m", "mme", "mlle", "dr", "pr", "st", "ste", "cf", "p.ex"],
+            "es" => &[
+                "sr", "sra", "srta", "dr", "dra", "ud", "uds", "d", "dña", "pág", "p.ej",
+            ],
+            "it" => &["sig", "sig.ra", "dott", "prof", "avv", "ing", "p.es"],
+            "pt" => &["sr", "sra", "dr", "dra", "prof", "v.ex", "p.ex"],
+            "nl" => &["dhr", "mevr", "dr", "prof", "bijv", "o.a", "d.w.z"],
+            "ru" => &["г", "гг", "ул", "т.е", "т.д", "т.п", "др"],
+            _ => &[],
+        };
+        Self {
+            abbreviations,
+            spaced: !matches!(base.as_str(), "ja" | "zh" | "zt"),
+        }
+    }
+
+    /// Whether a sentence ends at a terminator, given the text before it and
+    /// the text after it and any closing punctuation.
+    fn ends(&self, before: &str, terminator: char, after: &str) -> bool {
+        if (self.spaced || terminator.is_ascii())
+            && !after.is_empty()
+            && !after.starts_with(char::is_whitespace)
+        {
+            return false;
+        }
+        if self.spaced {
+            let next = after
+                .trim_start()
+                .trim_start_matches(OPENING)
+                .chars()
+                .next();
+            if next.is_some_and(char::is_lowercase) {
+                return false;
+            }
+        }
+        if terminator == '.' {
+            let word = before
+                .rsplit(char::is_whitespace)
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches(OPENING);
+            let mut letters = word.chars();
+            let initial =
+                letters.next().is_some_and(char::is_uppercase) && letters.next().is_none();
+            if initial
+                || self
+                    .abbreviations
+                    .iter()
+                    .any(|abbreviation| word.to_lowercase() == *abbreviation)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Split text into its sentences, trimmed of surrounding whitespace.
+///
+/// ```
+/// use subtitle_translate::segment::sentences;
+///
+/// assert_eq!(
+///     sentences("Dr. Watson is here. ¿Vienes? ¡Sí!", "en"),
+///     ["Dr. Watson is here.", "¿Vienes?", "¡Sí!"],
+/// );
+/// assert_eq!(sentences("行こう。「はい！」", "ja"), ["行こう。", "「はい！」"]);
+/// ```
+#[must_use]
+pub fn sentences<'a>(text: &'a str, language: &str) -> Vec<&'a str> {
+    let rules = Rules::for_language(language);
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        if !TERMINATORS.contains(&c) {
+            continue;
+        }
+        let mut end = idx + c.len_utf8();
+        while let Some(&(next_idx, next)) = chars.peek()
+            && (TERMINATORS.contains(&next) || CLOSING.contains(&next))
+        {
+            end = next_idx + next.len_utf8();
+            chars.next();
+        }
+        if rules.ends(&text[start..idx], c, &text[end..]) {
+            push_trimmed(&mut sentences, &text[start..end]);
+            start = end;
+        }
+    }
+    push_trimmed(&mut sentences, &text[start..]);
+    sentences
+}
+
+/// Whether text ends a sentence, rather than continuing into whatever follows
+/// it, as when a cue's text runs on into the next cue's.
+#[must_use]
+pub fn ends_sentence(text: &str, language: &str) -> bool {
+    let text = text.trim_end().trim_end_matches(CLOSING);
+    let body = text.trim_end_matches(TERMINATORS);
+    let Some(terminator) = text[body.len()..].chars().next() else {
+        return false;
+    };
+    Rules::for_language(language).ends(body, terminator, "")
+}
+
+fn push_trimmed<'a>(sentences: &mut Vec<&'a str>, sentence: &'a str) {
+    let sentence = sentence.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+}
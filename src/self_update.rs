@@ -0,0 +1,146 @@
+use std::{
+    env::consts::{ARCH, OS},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::cli::SelfUpdateArgs;
+
+/// Where releases are published.
+const LATEST_RELEASE: &str =
+    "https://api.github.com/repos/lilopkins/subtitle-translate-rs/releases/latest";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Replace the running executable with the latest release, if it is newer.
+pub async fn run(args: &SelfUpdateArgs) -> anyhow::Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let client = Client::builder()
+        .user_agent(concat!("subtitle-translate/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let release: Release = client
+        .get(LATEST_RELEASE)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .context("Failed to check for the latest release")?
+        .json()
+        .await
+        .context("Failed to read the latest release")?;
+
+    let latest = release.tag_name.trim_start_matches('v');
+    if version(latest) <= version(current) {
+        println!("Already up to date ({current})");
+        return Ok(());
+    }
+    println!("Version {latest} is available (currently {current})");
+    if args.check {
+        return Ok(());
+    }
+
+    let name = asset_name().with_context(|| {
+        format!("No release is built for {OS} on {ARCH}, so it must be updated manually")
+    })?;
+    let binary = find_asset(&release, name)?;
+    let checksum = find_asset(&release, &format!("{name}.sha256"))?;
+
+    println!("Downloading {name}…");
+    let binary = download(&client, binary).await?;
+    let checksum = String::from_utf8(download(&client, checksum).await?)
+        .context("The published checksum isn't text")?;
+    let expected = checksum.split_whitespace().next().unwrap_or_default();
+    let actual = format!("{:x}", Sha256::digest(&binary));
+    if !expected.eq_ignore_ascii_case(&actual) {
+        anyhow::bail!("The download's SHA-256 checksum {actual} doesn't match {expected}");
+    }
+
+    let exe = std::env::current_exe().context("Couldn't find the running executable")?;
+    replace(&exe, &binary).context("Failed to replace the executable")?;
+    println!("Updated to {latest}");
+    Ok(())
+}
+
+/// The name of the release asset built for this platform.
+fn asset_name() -> Option<&'static str> {
+    match (OS, ARCH) {
+        ("linux", "x86_64") => Some("subtitle-translate-linux-amd64"),
+        ("windows", "x86_64") => Some("subtitle-translate-amd64.exe"),
+        ("macos", "x86_64") => Some("subtitle-translate-darwin-amd64"),
+        ("macos", "aarch64") => Some("subtitle-translate-darwin-arm64"),
+        _ => None,
+    }
+}
+
+fn find_asset<'a>(release: &'a Release, name: &str) -> anyhow::Result<&'a str> {
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .map(|asset| asset.browser_download_url.as_str())
+        .with_context(|| format!("Release {} has no {name}", release.tag_name))
+}
+
+async fn download(client: &Client, url: &str) -> anyhow::Result<Vec<u8>> {
+    let bytes = client
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download {url}"))?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+/// Split a version into its numeric parts, for comparison.
+fn version(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Swap the executable for a new one. The old executable is moved aside
+/// first, as a running executable can't be overwritten on every platform.
+fn replace(exe: &Path, binary: &[u8]) -> std::io::Result<()> {
+    let new = with_suffix(exe, ".new");
+    let old = with_suffix(exe, ".old");
+    std::fs::write(&new, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::rename(exe, &old)?;
+    if let Err(e) = std::fs::rename(&new, exe) {
+        std::fs::rename(&old, exe)?;
+        return Err(e);
+    }
+    // This fails on Windows while the old executable is still running, in
+    // which case it is left behind.
+    let _ = std::fs::remove_file(&old);
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
@@ -0,0 +1,623 @@
+mod arr;
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path as UrlPath, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+    routing::{get, post},
+};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use subtitle_translate::{
+    LibreTranslate, TranslationPipeline,
+    cancel::{CancellationToken, Cancelled},
+    entities, languages,
+    pipeline::Event,
+    subtitle::{self, SubtitleFormat},
+    summary::Summary,
+};
+use tokio::sync::Notify;
+
+use crate::{
+    cli::{EngineArgs, ServeArgs},
+    convert, dirs,
+};
+
+/// How long to wait for the webhook to respond.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An error response, with a plain text message.
+pub type ApiError = (StatusCode, String);
+
+pub struct Server {
+    translator: LibreTranslate,
+    /// The languages to translate imports from Sonarr and Radarr from, and
+    /// into.
+    arr_from: String,
+    arr_to: Vec<String>,
+    /// The secret imports must be sent with.
+    arr_secret: Option<String>,
+    /// The folders, made canonical, which imported videos must be inside.
+    arr_roots: Vec<PathBuf>,
+    /// Where to report finished jobs.
+    webhook: Option<Url>,
+    client: Client,
+    chunk_size: usize,
+    /// Where jobs and their files are kept.
+    dir: PathBuf,
+    /// How many finished jobs to keep.
+    keep_jobs: usize,
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<u64, Job>>,
+    /// Cancels each running job.
+    running: Mutex<HashMap<u64, CancellationToken>>,
+    /// Wakes a worker when a job is queued.
+    queued: Notify,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub status: JobStatus,
+    /// Jobs with a higher priority are run first.
+    pub priority: i32,
+    pub language_from: String,
+    pub language_to: String,
+    /// The extension of the uploaded file's format.
+    pub format: String,
+    /// The extension of the translated file's format.
+    pub output_format: String,
+    /// How many cues need translating.
+    pub cues: usize,
+    /// How many cues have been attempted.
+    pub done: usize,
+    pub error: Option<String>,
+    /// Where to also write the translation, for jobs queued by an import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Deserialize)]
+pub struct Submission {
+    /// The source language, detected if not given.
+    pub from: Option<String>,
+    pub to: String,
+    /// The extension of the uploaded file's format.
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// The extension of the format to return, the same as the upload's if not
+    /// given.
+    pub output: Option<String>,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_format() -> String {
+    "srt".to_string()
+}
+
+/// Sent to the webhook when a job finishes or fails.
+#[derive(Serialize)]
+struct Notification<'a> {
+    #[serde(flatten)]
+    job: &'a Job,
+    /// The uploaded file, as stored by the server.
+    source: PathBuf,
+    /// The translated file, if the job succeeded.
+    output: Option<PathBuf>,
+    translated: usize,
+    skipped: usize,
+    failed: usize,
+    characters: usize,
+}
+
+/// Serve a REST API for translating subtitle files:
+///
+/// - `POST /jobs?to=de&from=en&format=srt&output=vtt&priority=1` with the file
+///   as the body queues a job, returning it.
+/// - `GET /jobs/{id}` returns the job's status and progress.
+/// - `GET /jobs/{id}/result` downloads the translation once it is done, or
+///   what was translated of a cancelled job.
+/// - `POST /jobs/{id}/cancel` cancels a queued or running job.
+/// - `POST /webhooks/arr` takes Sonarr and Radarr's import webhooks, queueing
+///   jobs to translate the imported video's subtitles into `--arr-languages`.
+///   They must be sent with `--arr-secret`, for videos inside an `--arr-root`.
+///
+/// Jobs are stored in the state directory, and any unfinished when the server
+/// stopped are queued again when it starts. Only the last `--keep-jobs`
+/// finished jobs are kept. With `--grpc-listen`, the same queue
+/// is also served over gRPC, as described by `proto/subtitle_translate.proto`.
+/// With `--webhook`, each job is posted to the URL when it finishes or fails.
+pub async fn run(engine: &EngineArgs, args: &ServeArgs) -> anyhow::Result<()> {
+    let dir = match &args.state_dir {
+        Some(dir) => dir.clone(),
+        None => dirs::data()
+            .context("Couldn't find a data directory, pass --state-dir")?
+            .join("jobs"),
+    };
+    std::fs::create_dir_all(&dir).context("Failed to create the state directory")?;
+    anyhow::ensure!(
+        args.arr_languages.is_empty() || (args.arr_secret.is_some() && !args.arr_roots.is_empty()),
+        "Translating imports needs --arr-secret and at least one --arr-root"
+    );
+    let arr_roots = args
+        .arr_roots
+        .iter()
+        .map(|root| {
+            root.canonicalize()
+                .with_context(|| format!("Failed to find {}", root.display()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let jobs = load_jobs(&dir)?;
+    let next_id = jobs.keys().max().map_or(1, |id| id + 1);
+    tracing::info!("Loaded {} job(s) from {}", jobs.len(), dir.display());
+
+    let server = Arc::new(Server {
+        translator: engine.translator(),
+        arr_from: args.arr_source_language.clone(),
+        arr_to: args.arr_languages.clone(),
+        arr_secret: args.arr_secret.clone(),
+        arr_roots,
+        webhook: args.webhook.clone(),
+        client: Client::new(),
+        chunk_size: args.chunk_size,
+        dir,
+        keep_jobs: args.keep_jobs,
+        next_id: AtomicU64::new(next_id),
+        jobs: Mutex::new(jobs),
+        running: Mutex::default(),
+        queued: Notify::new(),
+    });
+    server.prune();
+    for _ in 0..args.workers.max(1) {
+        tokio::spawn(work(Arc::clone(&server)));
+    }
+
+    let app = Router::new()
+        .route("/jobs", post(submit))
+        .route("/jobs/{id}", get(status))
+        .route("/jobs/{id}/result", get(result))
+        .route("/jobs/{id}/cancel", post(cancel))
+        .route("/webhooks/arr", post(arr::import))
+        .with_state(Arc::clone(&server));
+    let listener = tokio::net::TcpListener::bind(args.listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", args.listen))?;
+    tracing::info!("Listening on {}", args.listen);
+    #[cfg(feature = "grpc")]
+    if let Some(address) = args.grpc_listen {
+        let incoming = crate::grpc::bind(address)?;
+        tokio::spawn(crate::grpc::serve(Arc::clone(&server), incoming));
+    }
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Load the stored jobs, queueing any which were interrupted.
+fn load_jobs(dir: &Path) -> anyhow::Result<HashMap<u64, Job>> {
+    let mut jobs = HashMap::new();
+    for entry in std::fs::read_dir(dir).context("Failed to read the state directory")? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let job = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|job| Ok(serde_json::from_str::<Job>(&job)?));
+        match job {
+            Ok(mut job) => {
+                if job.status == JobStatus::Running {
+                    job.status = JobStatus::Queued;
+                    job.done = 0;
+                }
+                jobs.insert(job.id, job);
+            }
+            Err(e) => tracing::warn!("Ignoring unreadable job {}: {e:#}", path.display()),
+        }
+    }
+    Ok(jobs)
+}
+
+/// Run queued jobs one at a time, forever.
+async fn work(server: Arc<Server>) {
+    loop {
+        match server.claim() {
+            Some((job, token)) => translate(&server, job, token).await,
+            None => server.queued.notified().await,
+        }
+    }
+}
+
+async fn submit(
+    State(server): State<Arc<Server>>,
+    Query(submission): Query<Submission>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Job>), ApiError> {
+    let job = server.queue(&submission, &body)?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn status(
+    State(server): State<Arc<Server>>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<Json<Job>, ApiError> {
+    server.job(id).map(Json)
+}
+
+async fn result(
+    State(server): State<Arc<Server>>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (job, contents) = server.result(id)?;
+    let name = format!("{id}.{}", job.output_format);
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{name}\""),
+            ),
+        ],
+        contents,
+    ))
+}
+
+async fn cancel(
+    State(server): State<Arc<Server>>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<Json<Job>, ApiError> {
+    server.cancel(id).map(Json)
+}
+
+/// Run a job, recording its progress and outcome. A cancelled job's partial
+/// translation is kept as its result, but not written to its destination.
+async fn translate(server: &Server, job: Job, token: CancellationToken) {
+    let id = job.id;
+    let mut summary = Summary::new();
+    let result = async {
+        let source = server.source(&job);
+        let (format, mut subtitles) =
+            subtitle::read_with_format(&source).context("Failed to read subtitles")?;
+        for subtitle in &mut subtitles {
+            subtitle.text = entities::decode(&subtitle.text);
+        }
+        convert::to_tags(
+            &source,
+            format,
+            SubtitleFormat::from_path(&server.output(&job)),
+            &mut subtitles,
+        )?;
+        server.update(id, |job| {
+            job.cues = subtitles
+                .iter()
+                .filter(|s| !subtitle::is_blank(&s.text))
+                .count();
+        });
+
+        let pipeline = TranslationPipeline::new(server.translator.clone(), &job.language_to)
+            .source(&job.language_from)
+            .chunk_size(server.chunk_size)
+            .cancellation(token);
+        let on_event = |_: &Event<'_>| server.update(id, |job| job.done += 1);
+        let result = pipeline
+            .translate(&mut subtitles, &mut summary, on_event)
+            .await;
+        if result.as_ref().is_err_and(anyhow::Error::is::<Cancelled>) {
+            subtitle::write(&subtitles, &server.output(&job))
+                .context("Failed to write partial translation")?;
+        }
+        result?;
+        subtitle::write(&subtitles, &server.output(&job)).context("Failed to write translation")?;
+        if let Some(destination) = &job.destination {
+            subtitle::write(&subtitles, destination).with_context(|| {
+                format!("Failed to write translation to {}", destination.display())
+            })?;
+        }
+        anyhow::Ok(())
+    }
+    .await;
+    server.running().remove(&id);
+
+    server.update(id, |job| match result {
+        Ok(()) => job.status = JobStatus::Done,
+        Err(e) if e.is::<Cancelled>() => {
+            tracing::info!("Job {id} was cancelled");
+            job.status = JobStatus::Cancelled;
+        }
+        Err(e) => {
+            tracing::warn!("Job {id} failed: {e:#}");
+            job.status = JobStatus::Failed;
+            job.error = Some(format!("{e:#}"));
+        }
+    });
+    let Ok(job) = server.job(id) else {
+        return;
+    };
+    if let Err(e) = server.save(&job) {
+        tracing::warn!("Failed to save job {id}: {e}");
+    }
+    server.prune();
+    if let Some(url) = &server.webhook {
+        notify(server, url, &job, &summary).await;
+    }
+}
+
+/// Tell the webhook a job has finished, logging rather than failing if it
+/// can't be reached.
+async fn notify(server: &Server, url: &Url, job: &Job, summary: &Summary) {
+    let notification = Notification {
+        job,
+        source: server.source(job),
+        output: matches!(job.status, JobStatus::Done | JobStatus::Cancelled)
+            .then(|| server.output(job)),
+        translated: summary.translated,
+        skipped: summary.skipped,
+        failed: summary.failed,
+        characters: summary.characters,
+    };
+    let result = server
+        .client
+        .post(url.clone())
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&notification)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status);
+    if let Err(e) = result {
+        tracing::warn!("Failed to notify webhook of job {}: {e}", job.id);
+    }
+}
+
+impl Server {
+    /// Store an uploaded file and queue a job to translate it.
+    pub fn queue(&self, submission: &Submission, contents: &[u8]) -> Result<Job, ApiError> {
+        self.queue_to(submission, contents, None)
+    }
+
+    /// Queue a job, which also writes its translation to `destination` if it
+    /// is given.
+    fn queue_to(
+        &self,
+        submission: &Submission,
+        contents: &[u8],
+        destination: Option<PathBuf>,
+    ) -> Result<Job, ApiError> {
+        let language_from = match submission.from.as_deref() {
+            None | Some("" | "auto") => "auto".to_string(),
+            Some(language) => resolve(language)?,
+        };
+        let language_to = resolve(&submission.to)?;
+        let format = extension(&submission.format)?;
+        let output_format = match submission.output.as_deref() {
+            None | Some("") => format.clone(),
+            Some(output) => extension(output)?,
+        };
+
+        let job = Job {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            status: JobStatus::Queued,
+            priority: submission.priority,
+            language_from,
+            language_to,
+            format,
+            output_format,
+            cues: 0,
+            done: 0,
+            error: None,
+            destination,
+        };
+        std::fs::write(self.source(&job), contents).map_err(internal)?;
+        self.save(&job).map_err(internal)?;
+        self.jobs().insert(job.id, job.clone());
+        self.queued.notify_one();
+        Ok(job)
+    }
+
+    /// Remove the oldest finished jobs, and their files, beyond the number
+    /// which are kept.
+    fn prune(&self) {
+        let removed = {
+            let mut jobs = self.jobs();
+            let mut finished: Vec<u64> = jobs
+                .values()
+                .filter(|job| {
+                    matches!(
+                        job.status,
+                        JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled
+                    )
+                })
+                .map(|job| job.id)
+                .collect();
+            finished.sort_unstable();
+            let excess = finished.len().saturating_sub(self.keep_jobs);
+            finished[..excess]
+                .iter()
+                .filter_map(|id| jobs.remove(id))
+                .collect::<Vec<_>>()
+        };
+        for job in removed {
+            let files = [
+                self.dir.join(format!("{}.json", job.id)),
+                self.source(&job),
+                self.output(&job),
+            ];
+            for file in files {
+                match std::fs::remove_file(&file) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        tracing::warn!("Failed to remove {}: {e}", file.display());
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Cancel a job. A queued job is cancelled at once, and a running one
+    /// once the requests in flight are dropped.
+    pub fn cancel(&self, id: u64) -> Result<Job, ApiError> {
+        let mut jobs = self.jobs();
+        let job = jobs
+            .get_mut(&id)
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No job {id}")))?;
+        match job.status {
+            JobStatus::Queued => {
+                job.status = JobStatus::Cancelled;
+                let job = job.clone();
+                drop(jobs);
+                self.save(&job).map_err(internal)?;
+                self.prune();
+                Ok(job)
+            }
+            JobStatus::Running => {
+                if let Some(token) = self.running().get(&id) {
+                    token.cancel();
+                }
+                Ok(job.clone())
+            }
+            JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled => Err((
+                StatusCode::CONFLICT,
+                format!("Job {id} has already finished"),
+            )),
+        }
+    }
+
+    /// Get a finished job's translated file, or what was translated of a
+    /// cancelled job.
+    pub fn result(&self, id: u64) -> Result<(Job, Vec<u8>), ApiError> {
+        let job = self.job(id)?;
+        if !matches!(job.status, JobStatus::Done | JobStatus::Cancelled) {
+            return Err((StatusCode::CONFLICT, format!("Job {id} isn't done")));
+        }
+        match std::fs::read(self.output(&job)) {
+            Ok(contents) => Ok((job, contents)),
+            Err(e)
+                if e.kind() == std::io::ErrorKind::NotFound
+                    && job.status == JobStatus::Cancelled =>
+            {
+                Err((
+                    StatusCode::CONFLICT,
+                    format!("Job {id} was cancelled before it started"),
+                ))
+            }
+            Err(e) => Err(internal(e)),
+        }
+    }
+
+    fn jobs(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Job>> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn running(&self) -> std::sync::MutexGuard<'_, HashMap<u64, CancellationToken>> {
+        self.running
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub fn job(&self, id: u64) -> Result<Job, ApiError> {
+        self.jobs()
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No job {id}")))
+    }
+
+    /// Take the queued job with the highest priority, oldest first, marking
+    /// it as running, with the token which cancels it. The token is
+    /// registered at once, so a cancel is never lost before the job starts
+    /// translating.
+    fn claim(&self) -> Option<(Job, CancellationToken)> {
+        let mut jobs = self.jobs();
+        let job = jobs
+            .values_mut()
+            .filter(|job| job.status == JobStatus::Queued)
+            .max_by_key(|job| (job.priority, Reverse(job.id)))?;
+        job.status = JobStatus::Running;
+        let job = job.clone();
+        let token = CancellationToken::new();
+        self.running().insert(job.id, token.clone());
+        drop(jobs);
+        if let Err(e) = self.save(&job) {
+            tracing::warn!("Failed to save job {}: {e}", job.id);
+        }
+        Some((job, token))
+    }
+
+    /// Store a job so it survives restarts.
+    fn save(&self, job: &Job) -> std::io::Result<()> {
+        let json = serde_json::to_string(job)?;
+        std::fs::write(self.dir.join(format!("{}.json", job.id)), json)
+    }
+
+    fn source(&self, job: &Job) -> PathBuf {
+        self.dir.join(format!("{}.source.{}", job.id, job.format))
+    }
+
+    fn output(&self, job: &Job) -> PathBuf {
+        self.dir
+            .join(format!("{}.translated.{}", job.id, job.output_format))
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs().get_mut(&id) {
+            change(job);
+        }
+    }
+}
+
+/// Check a format is a supported file extension, so it can't name a path
+/// outside the state directory.
+fn extension(format: &str) -> Result<String, ApiError> {
+    let format = format.to_ascii_lowercase();
+    if !format.is_empty()
+        && format.bytes().all(|b| b.is_ascii_alphanumeric())
+        && SubtitleFormat::from_path(Path::new(&format!("file.{format}"))).is_some()
+    {
+        Ok(format)
+    } else {
+        Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported format '{format}'"),
+        ))
+    }
+}
+
+fn resolve(language: &str) -> Result<String, ApiError> {
+    languages::resolve(language).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Unknown language '{language}'"),
+        )
+    })
+}
+
+fn internal(error: impl std::fmt::Display) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+}
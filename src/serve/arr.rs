@@ -0,0 +1,172 @@
+//! Sonarr and Radarr's webhooks, so the subtitles of imported videos are
+//! translated as soon as they arrive.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use serde::Deserialize;
+
+use super::{ApiError, Job, Server, Submission, internal};
+use crate::{scan, translate};
+
+/// The parts of a Sonarr or Radarr webhook which say what was imported.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    event_type: String,
+    /// Sonarr's series and imported episode.
+    series: Option<Folder>,
+    episode_file: Option<MediaFile>,
+    /// Radarr's movie and imported file.
+    movie: Option<Folder>,
+    movie_file: Option<MediaFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Folder {
+    path: Option<PathBuf>,
+    folder_path: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MediaFile {
+    path: Option<PathBuf>,
+    relative_path: Option<PathBuf>,
+}
+
+impl Webhook {
+    /// The imported video's path, from the file's own path if it is given,
+    /// or else from its path relative to the series or movie's folder.
+    fn video(&self) -> Option<PathBuf> {
+        let (folder, file) = match (&self.episode_file, &self.movie_file) {
+            (Some(file), _) => (self.series.as_ref(), file),
+            (None, Some(file)) => (self.movie.as_ref(), file),
+            (None, None) => return None,
+        };
+        if let Some(path) = &file.path {
+            return Some(path.clone());
+        }
+        let folder = folder?;
+        let folder = folder.path.as_ref().or(folder.folder_path.as_ref())?;
+        Some(folder.join(file.relative_path.as_ref()?))
+    }
+}
+
+/// The header Sonarr and Radarr must send the secret in.
+const SECRET_HEADER: &str = "x-webhook-secret";
+
+/// Queue jobs translating an imported video's subtitles into each configured
+/// language it doesn't have subtitles in yet.
+pub async fn import(
+    State(server): State<Arc<Server>>,
+    headers: HeaderMap,
+    Json(event): Json<Webhook>,
+) -> Result<(StatusCode, Json<Vec<Job>>), ApiError> {
+    let authorised = match (&server.arr_secret, headers.get(SECRET_HEADER)) {
+        (Some(expected), Some(secret)) => same(secret.as_bytes(), expected.as_bytes()),
+        _ => false,
+    };
+    if !authorised {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            format!("Imports must be sent with the secret in the {SECRET_HEADER} header"),
+        ));
+    }
+    // Sonarr and Radarr call imports "Download" events.
+    if event.event_type != "Download" {
+        return Ok((StatusCode::OK, Json(Vec::new())));
+    }
+    if server.arr_to.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "No languages to translate imports into, pass --arr-languages".to_string(),
+        ));
+    }
+    let video = event.video().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "The event doesn't say which file was imported".to_string(),
+        )
+    })?;
+    let video = inside_roots(&video, &server.arr_roots)?;
+
+    let subtitles = scan::subtitles_of(&video).map_err(internal)?;
+    let from = scan::known(&server.arr_from).map_err(internal)?;
+    let Some((source, _)) = subtitles
+        .iter()
+        .find(|(_, tags)| scan::has_language(tags, from))
+    else {
+        tracing::info!(
+            "Imported {} has no {} subtitles to translate",
+            video.display(),
+            from.name
+        );
+        return Ok((StatusCode::OK, Json(Vec::new())));
+    };
+    let contents = std::fs::read(source).map_err(internal)?;
+    let format = source
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or("srt")
+        .to_ascii_lowercase();
+
+    let mut jobs = Vec::new();
+    for code in &server.arr_to {
+        let to = scan::known(code).map_err(internal)?;
+        let tag = scan::target_tag(code).map_err(internal)?;
+        if subtitles
+            .iter()
+            .any(|(_, tags)| scan::has_target(tags, to, &tag))
+        {
+            continue;
+        }
+        let submission = Submission {
+            from: Some(from.code.to_string()),
+            to: tag.clone(),
+            format: format.clone(),
+            output: None,
+            priority: 0,
+        };
+        let destination = translate::default_destination(&video, &tag);
+        jobs.push(server.queue_to(&submission, &contents, Some(destination))?);
+    }
+    tracing::info!(
+        "Queued {} job(s) for imported {}",
+        jobs.len(),
+        video.display()
+    );
+    Ok((StatusCode::ACCEPTED, Json(jobs)))
+}
+
+/// The canonical path of an imported video, if it is inside one of the
+/// library folders, so imports can't read or write files elsewhere.
+fn inside_roots(video: &Path, roots: &[PathBuf]) -> Result<PathBuf, ApiError> {
+    let outside = || {
+        (
+            StatusCode::FORBIDDEN,
+            format!("{} isn't inside a library folder", video.display()),
+        )
+    };
+    let video = video.canonicalize().map_err(|_| outside())?;
+    if roots.iter().any(|root| video.starts_with(root)) {
+        Ok(video)
+    } else {
+        Err(outside())
+    }
+}
+
+/// Whether two secrets are the same, taking as long to tell whatever they
+/// start with.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
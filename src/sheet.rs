@@ -0,0 +1,144 @@
+//! Spreadsheets of cues, so reviewers can post-edit translations beside their
+//! sources in a spreadsheet and the subtitle file can be rebuilt from it.
+
+use std::path::Path;
+
+use anyhow::Context;
+use aspasia::Moment;
+use serde::{Deserialize, Serialize};
+use subtitle_translate::{
+    align::{Pair, align},
+    subtitle::{self, GenericSubtitle, format_moment, parse_timestamp},
+};
+
+use crate::{
+    cli::{ExportCsvArgs, ImportCsvArgs},
+    exit_code::{Failure, WithFailure},
+};
+
+/// A cue, as a row of the spreadsheet.
+#[derive(Serialize, Deserialize)]
+struct Row {
+    index: usize,
+    start: String,
+    end: String,
+    #[serde(default)]
+    source: String,
+    #[serde(default)]
+    translation: String,
+}
+
+/// Write the cues of a source file and its translation, paired by when they
+/// are shown, as rows of a CSV or TSV file.
+pub fn export(args: &ExportCsvArgs) -> anyhow::Result<()> {
+    refuse_to_replace(&args.output, args.overwrite)?;
+    let read = |path| {
+        subtitle::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+            .failure(Failure::Parse)
+    };
+    let (sources, translations) = (read(&args.source)?, read(&args.translation)?);
+
+    let rows: Vec<Row> = align(&sources, &translations)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, pair)| {
+            let (timed, source, translation) = match pair {
+                Pair::Both(i, j) => (&sources[i], &sources[i].text, &translations[j].text),
+                Pair::Left(i) => (&sources[i], &sources[i].text, &String::new()),
+                Pair::Right(j) => (&translations[j], &String::new(), &translations[j].text),
+            };
+            Row {
+                index: idx + 1,
+                start: format_moment(timed.start),
+                end: format_moment(timed.end),
+                source: source.clone(),
+                translation: translation.clone(),
+            }
+        })
+        .collect();
+
+    write_rows(&args.output, &rows)
+        .with_context(|| format!("Failed to write {}", args.output.display()))
+        .failure(Failure::Write)?;
+    tracing::info!("Wrote {} cue(s)", rows.len());
+    Ok(())
+}
+
+fn write_rows(path: &Path, rows: &[Row]) -> anyhow::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter(path))
+        .from_path(path)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Rebuild a subtitle file from the timing and translations of a CSV or TSV
+/// file written by [`export`], in the order of its index column. Cues left
+/// without a translation keep their source text.
+pub fn import(args: &ImportCsvArgs) -> anyhow::Result<()> {
+    refuse_to_replace(&args.output, args.overwrite)?;
+    let mut rows: Vec<Row> = csv::ReaderBuilder::new()
+        .delimiter(delimiter(&args.sheet))
+        .from_path(&args.sheet)
+        .and_then(|mut reader| reader.deserialize().collect())
+        .with_context(|| format!("Failed to read {}", args.sheet.display()))
+        .failure(Failure::Parse)?;
+    rows.sort_by_key(|row| row.index);
+
+    let mut untranslated = 0;
+    let mut subtitles = Vec::with_capacity(rows.len());
+    for row in rows {
+        let time = |timestamp: &str| {
+            parse_timestamp(timestamp)
+                .map(Moment::from)
+                .with_context(|| format!("Row {} has an unreadable time {timestamp:?}", row.index))
+                .failure(Failure::Parse)
+        };
+        let text = if row.translation.trim().is_empty() {
+            untranslated += 1;
+            row.source.clone()
+        } else {
+            row.translation.clone()
+        };
+        subtitles.push(GenericSubtitle {
+            text,
+            start: time(&row.start)?,
+            end: time(&row.end)?,
+            coordinates: None,
+            style: None,
+        });
+    }
+    if untranslated > 0 {
+        tracing::warn!("{untranslated} cue(s) had no translation, so kept their source text");
+    }
+
+    subtitle::write(&subtitles, &args.output)
+        .context("Failed to write destination subtitle file")
+        .failure(Failure::Write)
+}
+
+fn refuse_to_replace(output: &Path, overwrite: bool) -> anyhow::Result<()> {
+    if output.exists() && !overwrite {
+        anyhow::bail!(
+            "Destination file {} already exists, pass --overwrite to replace it",
+            output.display()
+        );
+    }
+    Ok(())
+}
+
+/// Tabs for `.tsv` files, and commas otherwise.
+fn delimiter(path: &Path) -> u8 {
+    if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("tsv"))
+    {
+        b'\t'
+    } else {
+        b','
+    }
+}
@@ -0,0 +1,103 @@
+use aspasia::Moment;
+
+use crate::subtitle::{GenericSubtitle, visible_text};
+
+/// Statistics describing a set of subtitles.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    /// The number of cues.
+    pub cues: usize,
+    /// When the first cue starts.
+    pub first_start: Option<Moment>,
+    /// When the last cue ends.
+    pub last_end: Option<Moment>,
+    /// The number of visible characters, excluding line breaks.
+    pub characters: usize,
+    /// The average reading speed across all cues, in characters per second.
+    pub average_cps: f64,
+    /// The fastest cue to read, as its index and characters per second.
+    pub max_cps: Option<(usize, f64)>,
+    /// The longest line, as its cue's index and length in characters.
+    pub longest_line: Option<(usize, usize)>,
+    /// The number of cues starting before an earlier cue has ended.
+    pub overlaps: usize,
+}
+
+impl Statistics {
+    #[must_use]
+    pub fn new(subtitles: &[GenericSubtitle]) -> Self {
+        let mut stats = Self {
+            cues: subtitles.len(),
+            first_start: subtitles.iter().map(|s| s.start).min(),
+            last_end: subtitles.iter().map(|s| s.end).max(),
+            ..Default::default()
+        };
+
+        let mut total_ms = 0;
+        let mut latest_end: Option<Moment> = None;
+        for (idx, subtitle) in subtitles.iter().enumerate() {
+            let characters = characters(&subtitle.text);
+            stats.characters += characters;
+            if subtitle.duration_ms() > 0 {
+                total_ms += subtitle.duration_ms();
+            }
+
+            if let Some(cps) = cps(subtitle)
+                && stats.max_cps.is_none_or(|(_, max)| cps > max)
+            {
+                stats.max_cps = Some((idx, cps));
+            }
+
+            let longest = longest_line(&subtitle.text);
+            if stats.longest_line.is_none_or(|(_, max)| longest > max) {
+                stats.longest_line = Some((idx, longest));
+            }
+
+            if latest_end.is_some_and(|end| subtitle.start < end) {
+                stats.overlaps += 1;
+            }
+            latest_end = latest_end.max(Some(subtitle.end));
+        }
+
+        if total_ms > 0 {
+            #[allow(clippy::cast_precision_loss)]
+            let average = stats.characters as f64 / (total_ms as f64 / 1000.0);
+            stats.average_cps = average;
+        }
+        stats
+    }
+}
+
+/// The number of visible characters in a cue, excluding line breaks.
+#[must_use]
+pub fn characters(text: &str) -> usize {
+    visible_text(text).chars().filter(|c| *c != '\n').count()
+}
+
+/// The length in characters of the longest visible line in a cue.
+#[must_use]
+pub fn longest_line(text: &str) -> usize {
+    visible_text(text)
+        .lines()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// The number of visible lines in a cue, not counting blank ones.
+#[must_use]
+pub fn lines(text: &str) -> usize {
+    visible_text(text)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count()
+}
+
+/// The reading speed of a cue in characters per second, if it has a positive
+/// duration.
+#[must_use]
+pub fn cps(subtitle: &GenericSubtitle) -> Option<f64> {
+    let duration = subtitle.duration_ms();
+    #[allow(clippy::cast_precision_loss)]
+    (duration > 0).then(|| characters(&subtitle.text) as f64 / (duration as f64 / 1000.0))
+}
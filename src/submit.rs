@@ -0,0 +1,40 @@
+use anyhow::Context;
+use reqwest::{Client, Url};
+
+use crate::cli::SubmitArgs;
+
+/// Upload a subtitle file to a server's queue, printing the job it creates.
+pub async fn run(args: &SubmitArgs) -> anyhow::Result<()> {
+    let format = args
+        .source_file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .context("Can't tell the source file's format from its extension")?;
+    let contents = std::fs::read(&args.source_file).context("Failed to read source file")?;
+
+    let mut url = Url::parse(&args.server)
+        .and_then(|url| url.join("jobs"))
+        .context("Invalid server URL")?;
+    url.query_pairs_mut()
+        .append_pair("from", &args.language_from)
+        .append_pair("to", &args.language_to)
+        .append_pair("format", format)
+        .append_pair("priority", &args.priority.to_string());
+    if let Some(output) = &args.output_format {
+        url.query_pairs_mut().append_pair("output", output);
+    }
+
+    let response = Client::new()
+        .post(url)
+        .body(contents)
+        .send()
+        .await
+        .context("Failed to reach the server")?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        anyhow::bail!("The server refused the job: {body}");
+    }
+    println!("{body}");
+    Ok(())
+}
@@ -0,0 +1,231 @@
+//! ASS and SSA styles and override tags, so their italics, bold, underlining
+//! and colours can be kept as the formatting tags other formats use.
+
+use std::{collections::HashMap, path::Path};
+
+use crate::{colors, subtitle::detect_encoding};
+
+/// The formatting of a style which other formats have tags for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    /// The colour of the text, unless it is white, as most is.
+    pub color: Option<[u8; 3]>,
+}
+
+/// The styles defined in a script, by name.
+#[derive(Clone, Debug, Default)]
+pub struct Styles(HashMap<String, Style>);
+
+impl Styles {
+    /// Read the styles defined in an ASS or SSA file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let (contents, _, _) = detect_encoding(path)?.decode(&bytes);
+        Ok(Self::parse(&contents))
+    }
+
+    /// Parse the styles defined in the `[V4+ Styles]` or `[V4 Styles]`
+    /// section of a script, using its `Format` line to find their fields.
+    ///
+    /// ```
+    /// use subtitle_translate::substation::Styles;
+    ///
+    /// let styles = Styles::parse(
+    ///     "[V4+ Styles]\nFormat: Name, Fontname, Bold, Italic\nStyle: Thoughts,Arial,0,-1\n",
+    /// );
+    /// assert!(styles.get("Thoughts").is_some_and(|style| style.italic && !style.bold));
+    /// ```
+    #[must_use]
+    pub fn parse(script: &str) -> Self {
+        let mut styles = HashMap::new();
+        let mut in_styles = false;
+        let mut format: Vec<String> = Vec::new();
+        for line in script.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_styles = line.to_ascii_lowercase().contains("styles");
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':').filter(|_| in_styles) else {
+                continue;
+            };
+            match key.trim() {
+                "Format" => {
+                    format = value
+                        .split(',')
+                        .map(|field| field.trim().to_ascii_lowercase())
+                        .collect();
+                }
+                "Style" => {
+                    let values: Vec<&str> = value.splitn(format.len().max(1), ',').collect();
+                    let field = |name: &str| {
+                        format
+                            .iter()
+                            .position(|field| field == name)
+                            .and_then(|idx| values.get(idx))
+                            .map(|value| value.trim())
+                    };
+                    let flag = |name: &str| field(name).is_some_and(|value| value != "0");
+                    if let Some(name) = field("name") {
+                        styles.insert(
+                            name.to_string(),
+                            Style {
+                                bold: flag("bold"),
+                                italic: flag("italic"),
+                                underline: flag("underline"),
+                                color: field("primarycolour")
+                                    .and_then(colors::parse_ass)
+                                    .filter(|rgb| *rgb != [0xff; 3]),
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self(styles)
+    }
+
+    /// The style of a name, which matches regardless of case, as renderers
+    /// do.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Style> {
+        let name = name.trim().trim_start_matches('*');
+        self.0.get(name).or_else(|| {
+            self.0
+                .iter()
+                .find(|(style, _)| style.eq_ignore_ascii_case(name))
+                .map(|(_, style)| style)
+        })
+    }
+}
+
+/// Write a cue's formatting, from its style and the override tags in its
+/// text, as `<b>`, `<i>`, `<u>` and `<font color>` tags. Other override tags
+/// are dropped, except alignments, such as `{\an8}`, which many players of
+/// other formats understand.
+///
+/// ```
+/// use subtitle_translate::substation::{Styles, to_tags};
+///
+/// let styles = Styles::parse("[V4+ Styles]\nFormat: Name, Italic\nStyle: Thoughts,-1\n");
+/// assert_eq!(to_tags(r"{\an8}Wait, {\b1}now{\b0}?", None, &styles), r"{\an8}Wait, <b>now</b>?");
+/// assert_eq!(
+///     to_tags(r"{\c&H00FFFF&}Stop{\c} it", None, &styles),
+///     "<font color=\"#FFFF00\">Stop</font> it"
+/// );
+/// assert_eq!(to_tags(r"I {\i0}must{\r} go", Some("Thoughts"), &styles), "<i>I </i>must<i> go</i>");
+/// ```
+#[must_use]
+pub fn to_tags(text: &str, style: Option<&str>, styles: &Styles) -> String {
+    let base = style
+        .and_then(|style| styles.get(style))
+        .copied()
+        .unwrap_or_default();
+    let mut current = base;
+    let mut written = Style::default();
+    let mut tagged = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '{'
+            && let Some(end) = rest.find('}')
+        {
+            for tag in rest[1..end].split('\\').skip(1) {
+                apply_override(tag, base, styles, &mut current, &mut tagged);
+            }
+            rest = &rest[end + 1..];
+            continue;
+        }
+        if current != written {
+            close(written, &mut tagged);
+            open(current, &mut tagged);
+            written = current;
+        }
+        tagged.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    close(written, &mut tagged);
+    tagged
+}
+
+/// Apply an override tag, without its backslash, to the current formatting.
+fn apply_override(
+    tag: &str,
+    base: Style,
+    styles: &Styles,
+    current: &mut Style,
+    tagged: &mut String,
+) {
+    let tag = tag.trim();
+    // Whether a tag turns its formatting on, as with `1`, or a weight of
+    // bold, such as `700`, or off, as with `0`.
+    let on = |value: &str| {
+        value
+            .parse::<u32>()
+            .ok()
+            .map(|value| value == 1 || value >= 700)
+    };
+    if let Some(alignment) = tag.strip_prefix("an") {
+        tagged.push_str("{\\an");
+        tagged.push_str(alignment);
+        tagged.push('}');
+    } else if let Some(name) = tag.strip_prefix('r') {
+        *current = if name.is_empty() {
+            base
+        } else {
+            styles.get(name).copied().unwrap_or(base)
+        };
+    } else if let Some(color) = tag.strip_prefix("1c").or_else(|| tag.strip_prefix('c')) {
+        current.color = if color.is_empty() {
+            base.color
+        } else {
+            colors::parse_ass(color).or(current.color)
+        };
+    } else if let Some(bold) = tag.strip_prefix('b').and_then(on) {
+        current.bold = bold;
+    } else if let Some(italic) = tag.strip_prefix('i').and_then(on) {
+        current.italic = italic;
+    } else if let Some(underline) = tag.strip_prefix('u').and_then(on) {
+        current.underline = underline;
+    }
+}
+
+/// Open the tags for formatting, colour outermost.
+fn open(style: Style, tagged: &mut String) {
+    if let Some(color) = style.color {
+        tagged.push_str("<font color=\"");
+        tagged.push_str(&colors::to_hex(color));
+        tagged.push_str("\">");
+    }
+    for (on, tag) in [
+        (style.bold, "<b>"),
+        (style.italic, "<i>"),
+        (style.underline, "<u>"),
+    ] {
+        if on {
+            tagged.push_str(tag);
+        }
+    }
+}
+
+/// Close the tags opened by [`open`].
+fn close(style: Style, tagged: &mut String) {
+    for (on, tag) in [
+        (style.underline, "</u>"),
+        (style.italic, "</i>"),
+        (style.bold, "</b>"),
+    ] {
+        if on {
+            tagged.push_str(tag);
+        }
+    }
+    if style.color.is_some() {
+        tagged.push_str("</font>");
+    }
+}
@@ -0,0 +1,480 @@
+use std::{fmt, path::Path};
+
+use aspasia::{
+    AssSubtitle, MicroDvdSubtitle, Moment, SsaSubtitle, SubRipSubtitle, Subtitle,
+    TimedSubtitleFile, WebVttSubtitle, subrip::SubRipEvent,
+};
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+
+use crate::{colors, entities};
+
+mod lenient;
+mod subviewer;
+
+pub use lenient::{Recovered, Recovery, read_lenient};
+pub use subviewer::{parse_subviewer, to_subviewer};
+
+#[derive(Clone, Debug)]
+pub struct GenericSubtitle {
+    pub text: String,
+    pub start: Moment,
+    pub end: Moment,
+    pub coordinates: Option<String>,
+    /// The style of an ASS or SSA event, such as `Default` or `Signs`.
+    pub style: Option<String>,
+}
+
+impl GenericSubtitle {
+    /// How long the cue is displayed for, in milliseconds.
+    #[must_use]
+    pub fn duration_ms(&self) -> i64 {
+        self.end.as_ms() - self.start.as_ms()
+    }
+}
+
+/// The supported subtitle formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Ass,
+    MicroDvd,
+    Ssa,
+    SubRip,
+    /// SubViewer, or YouTube's `.sbv` captions. They are always written as
+    /// `.sbv`, as `.sub` files are written as MicroDVD.
+    SubViewer,
+    WebVtt,
+}
+
+impl fmt::Display for SubtitleFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ass => "Advanced SubStation Alpha",
+            Self::MicroDvd => "MicroDVD",
+            Self::Ssa => "SubStation Alpha",
+            Self::SubRip => "SubRip",
+            Self::SubViewer => "SubViewer",
+            Self::WebVtt => "WebVTT",
+        })
+    }
+}
+
+impl SubtitleFormat {
+    /// Determine a format from a file's extension.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ass" => Some(Self::Ass),
+            "sbv" => Some(Self::SubViewer),
+            "sub" => Some(Self::MicroDvd),
+            "ssa" => Some(Self::Ssa),
+            "srt" => Some(Self::SubRip),
+            "vtt" => Some(Self::WebVtt),
+            _ => None,
+        }
+    }
+
+    /// Determine the format of an existing file from its extension, and its
+    /// contents for `.sub` files, which may be MicroDVD or SubViewer.
+    #[must_use]
+    pub fn detect(path: &Path) -> Option<Self> {
+        match subviewer::is_subviewer(path) {
+            Ok(true) => Some(Self::SubViewer),
+            _ => Self::from_path(path),
+        }
+    }
+}
+
+/// Read a subtitle file of any supported format.
+///
+/// # Errors
+///
+/// Fails if the file can't be read or parsed.
+pub fn read(path: &Path) -> anyhow::Result<Vec<GenericSubtitle>> {
+    Ok(read_with_format(path)?.1)
+}
+
+/// Read a subtitle file of any supported format, also returning which format
+/// it was.
+///
+/// # Errors
+///
+/// Fails if the file can't be read or parsed.
+pub fn read_with_format(path: &Path) -> anyhow::Result<(SubtitleFormat, Vec<GenericSubtitle>)> {
+    if subviewer::is_subviewer(path)? {
+        return Ok((SubtitleFormat::SubViewer, subviewer::read(path)?));
+    }
+    let subs = TimedSubtitleFile::new(path)?;
+    tracing::debug!("Read subtitles file");
+    let format = match &subs {
+        TimedSubtitleFile::Ass(_) => SubtitleFormat::Ass,
+        TimedSubtitleFile::MicroDvd(_) => SubtitleFormat::MicroDvd,
+        TimedSubtitleFile::Ssa(_) => SubtitleFormat::Ssa,
+        TimedSubtitleFile::SubRip(_) => SubtitleFormat::SubRip,
+        TimedSubtitleFile::WebVtt(_) => SubtitleFormat::WebVtt,
+    };
+    Ok((format, timed_subtitle_file_events_to_generic(subs)))
+}
+
+/// Parse subtitles of a known format from memory, such as a file a browser
+/// was given.
+///
+/// ```
+/// use subtitle_translate::subtitle::{self, SubtitleFormat};
+///
+/// let cues = subtitle::parse("0:00:01.000,0:00:02.500\nHello\n", SubtitleFormat::SubViewer).unwrap();
+/// assert_eq!(cues[0].text, "Hello");
+/// assert_eq!(cues[0].end.as_ms(), 2500);
+/// ```
+///
+/// # Errors
+///
+/// Fails if the text isn't subtitles of that format.
+pub fn parse(contents: &str, format: SubtitleFormat) -> anyhow::Result<Vec<GenericSubtitle>> {
+    let contents = contents.trim_start_matches('\u{FEFF}');
+    let subs = match format {
+        SubtitleFormat::SubViewer => {
+            return parse_subviewer(contents)
+                .ok_or_else(|| anyhow::anyhow!("The text isn't a SubViewer or SBV file"));
+        }
+        SubtitleFormat::Ass => TimedSubtitleFile::Ass(contents.parse()?),
+        SubtitleFormat::MicroDvd => TimedSubtitleFile::MicroDvd(contents.parse()?),
+        SubtitleFormat::Ssa => TimedSubtitleFile::Ssa(contents.parse()?),
+        SubtitleFormat::SubRip => TimedSubtitleFile::SubRip(contents.parse()?),
+        SubtitleFormat::WebVtt => TimedSubtitleFile::WebVtt(contents.parse()?),
+    };
+    Ok(timed_subtitle_file_events_to_generic(subs))
+}
+
+/// Guess the character encoding of a file.
+///
+/// # Errors
+///
+/// Fails if the file can't be read.
+pub fn detect_encoding(path: &Path) -> anyhow::Result<&'static Encoding> {
+    let bytes = std::fs::read(path)?;
+    if let Some((encoding, _)) = Encoding::for_bom(&bytes) {
+        return Ok(encoding);
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(&bytes, true);
+    Ok(detector.guess(None, true))
+}
+
+/// The text of a cue as it would be displayed, without formatting tags or
+/// override blocks.
+#[must_use]
+pub fn visible_text(text: &str) -> String {
+    let mut visible = String::with_capacity(text.len());
+    let mut closing = None;
+    for c in text.chars() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (None, c) => visible.push(c),
+            (Some(end), c) if c == end => closing = None,
+            (Some(_), _) => {}
+        }
+    }
+    visible
+        .replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ")
+}
+
+/// Whether a cue shows no text: it is empty, or only whitespace, formatting
+/// tags or override blocks.
+///
+/// ```
+/// use subtitle_translate::subtitle::is_blank;
+///
+/// assert!(is_blank(" <i> </i>\n"));
+/// assert!(!is_blank("{\\an8}Hello"));
+/// ```
+#[must_use]
+pub fn is_blank(text: &str) -> bool {
+    visible_text(text).trim().is_empty()
+}
+
+/// Write subtitles out as an SRT file.
+///
+/// # Errors
+///
+/// Fails if the file can't be written.
+pub fn write_srt(subtitles: &[GenericSubtitle], path: &Path) -> anyhow::Result<()> {
+    write_atomically(path, |temp| {
+        Ok(std::fs::write(
+            temp,
+            to_string(subtitles, SubtitleFormat::SubRip),
+        )?)
+    })
+}
+
+/// Write subtitles out in the format matching the file's extension.
+///
+/// # Errors
+///
+/// Fails if the extension isn't a supported format, or the file can't be
+/// written.
+pub fn write(subtitles: &[GenericSubtitle], path: &Path) -> anyhow::Result<()> {
+    let format = SubtitleFormat::from_path(path).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Can't tell which format to write {} in from its extension",
+            path.display()
+        )
+    })?;
+    tracing::debug!("Writing subtitles as {format}");
+    write_atomically(path, |temp| {
+        Ok(std::fs::write(temp, to_string(subtitles, format))?)
+    })
+}
+
+/// Write subtitles out in a format, in memory, such as for a browser to
+/// offer as a download.
+///
+/// ```
+/// use aspasia::Moment;
+/// use subtitle_translate::subtitle::{self, GenericSubtitle, SubtitleFormat};
+///
+/// let cue = GenericSubtitle {
+///     text: "Hallo".to_string(),
+///     start: Moment::from(1000),
+///     end: Moment::from(2500),
+///     coordinates: None,
+///     style: None,
+/// };
+/// assert_eq!(subtitle::to_string(&[cue], SubtitleFormat::SubViewer), "0:00:01.000,0:00:02.500\nHallo\n");
+/// ```
+#[must_use]
+pub fn to_string(subtitles: &[GenericSubtitle], format: SubtitleFormat) -> String {
+    match format {
+        SubtitleFormat::SubRip => to_srt(subtitles).to_string(),
+        // WebVTT is the only format needing characters to be escaped.
+        SubtitleFormat::WebVtt => {
+            let webvtt = |text: &str| entities::encode_webvtt(&colors::to_webvtt(text));
+            WebVttSubtitle::from(&to_srt(&map_text(subtitles, webvtt))).to_string()
+        }
+        SubtitleFormat::Ass => {
+            let ass = |text: &str| to_ass_text(&colors::to_ass(text));
+            AssSubtitle::from(&to_srt(&map_text(subtitles, ass))).to_string()
+        }
+        SubtitleFormat::Ssa => {
+            let ssa = |text: &str| to_ass_text(&colors::to_ass(text));
+            SsaSubtitle::from(&to_srt(&map_text(subtitles, ssa))).to_string()
+        }
+        SubtitleFormat::MicroDvd => MicroDvdSubtitle::from(&to_srt(subtitles)).to_string(),
+        SubtitleFormat::SubViewer => to_subviewer(subtitles),
+    }
+}
+
+/// Export a file to a temporary file beside `path`, and only rename it into
+/// place once it is written, so a crash or full disk never leaves a truncated
+/// file where the old one was.
+fn write_atomically(
+    path: &Path,
+    export: impl FnOnce(&Path) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!(".{stem}.{}.tmp", std::process::id());
+    // Keep the extension, in case the format is told from it.
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
+    }
+    let temp = path.with_file_name(name);
+    let result = export(&temp).and_then(|()| Ok(std::fs::rename(&temp, path)?));
+    if result.is_err() {
+        // It may not have been created.
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Copies of subtitles with their text changed.
+fn map_text(subtitles: &[GenericSubtitle], f: impl Fn(&str) -> String) -> Vec<GenericSubtitle> {
+    subtitles
+        .iter()
+        .map(|subtitle| GenericSubtitle {
+            text: f(&subtitle.text),
+            ..subtitle.clone()
+        })
+        .collect()
+}
+
+/// Turn the line breaks of ASS and SSA text, `\N` and `\n`, into real ones,
+/// and its hard spaces, `\h`, into no-break spaces, so engines are sent text
+/// rather than escapes. Override blocks are left alone.
+///
+/// ```
+/// use subtitle_translate::subtitle::{from_ass_text, to_ass_text};
+///
+/// let text = r"{\i1}Wait\Nfor\hme";
+/// assert_eq!(from_ass_text(text), "{\\i1}Wait\nfor\u{a0}me");
+/// assert_eq!(to_ass_text(&from_ass_text(text)), text);
+/// ```
+#[must_use]
+pub fn from_ass_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_block = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (in_block, c, chars.peek()) {
+            (false, '\\', Some('N' | 'n')) => {
+                chars.next();
+                plain.push('\n');
+            }
+            (false, '\\', Some('h')) => {
+                chars.next();
+                plain.push('\u{a0}');
+            }
+            (_, c, _) => {
+                in_block = match c {
+                    '{' => true,
+                    '}' => false,
+                    _ => in_block,
+                };
+                plain.push(c);
+            }
+        }
+    }
+    plain
+}
+
+/// Write real line breaks and no-break spaces as ASS and SSA escapes, undoing
+/// [`from_ass_text`].
+#[must_use]
+pub fn to_ass_text(text: &str) -> String {
+    text.replace("\r\n", "\\N")
+        .replace('\n', "\\N")
+        .replace('\u{a0}', "\\h")
+}
+
+/// Convert subtitles into SRT events.
+fn to_srt(subtitles: &[GenericSubtitle]) -> SubRipSubtitle {
+    tracing::debug!("Converting subtitles back into SRT events");
+    let mut events = vec![];
+    for (idx, subtitle) in subtitles.iter().enumerate() {
+        events.push(SubRipEvent {
+            line_number: idx + 1,
+            text: subtitle.text.clone(),
+            start: subtitle.start,
+            end: subtitle.end,
+            coordinates: subtitle.coordinates.clone(),
+        });
+    }
+
+    let mut srt = SubRipSubtitle::from_events(events);
+    srt.renumber();
+    srt
+}
+
+/// Read a timestamp, `hh:mm:ss,mmm`, in milliseconds, accepting a full stop
+/// before the milliseconds, leaving out the hours or the milliseconds, and
+/// stray spaces.
+#[must_use]
+pub fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    let timestamp: String = timestamp.chars().filter(|c| !c.is_whitespace()).collect();
+    let (time, ms) = match timestamp.split_once([',', '.']) {
+        Some((time, ms)) => {
+            // Only the first three digits are milliseconds.
+            let digits: String = ms.chars().take(3).collect();
+            let scale = 10_i64.pow(3 - u32::try_from(digits.len()).ok()?);
+            (time, digits.parse::<i64>().ok()? * scale)
+        }
+        None => (timestamp.as_str(), 0),
+    };
+    let mut seconds = 0;
+    let mut parts = 0;
+    for part in time.split(':') {
+        seconds = seconds * 60 + part.parse::<i64>().ok()?;
+        parts += 1;
+    }
+    (2..=3).contains(&parts).then_some(seconds * 1000 + ms)
+}
+
+/// Format a moment as an SRT-style `hh:mm:ss,mmm` timestamp.
+#[must_use]
+pub fn format_moment(moment: Moment) -> String {
+    let ms = moment.as_ms();
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[must_use]
+pub fn timed_subtitle_file_events_to_generic(subs: TimedSubtitleFile) -> Vec<GenericSubtitle> {
+    let mut subtitles = vec![];
+    match subs {
+        TimedSubtitleFile::Ass(ass) => subtitles.append(
+            &mut ass
+                .events()
+                .iter()
+                .map(|ev| GenericSubtitle {
+                    text: from_ass_text(&ev.text),
+                    start: ev.start,
+                    end: ev.end,
+                    coordinates: None,
+                    style: ev.style.clone(),
+                })
+                .collect(),
+        ),
+        TimedSubtitleFile::MicroDvd(dvd) => subtitles.append(
+            &mut dvd
+                .events()
+                .iter()
+                .map(|ev| GenericSubtitle {
+                    text: ev.text.clone(),
+                    start: ev.start,
+                    end: ev.end,
+                    coordinates: None,
+                    style: None,
+                })
+                .collect(),
+        ),
+        TimedSubtitleFile::Ssa(ssa) => subtitles.append(
+            &mut ssa
+                .events()
+                .iter()
+                .map(|ev| GenericSubtitle {
+                    text: from_ass_text(&ev.text),
+                    start: ev.start,
+                    end: ev.end,
+                    coordinates: None,
+                    style: ev.style.clone(),
+                })
+                .collect(),
+        ),
+        TimedSubtitleFile::SubRip(srt) => subtitles.append(
+            &mut srt
+                .events()
+                .iter()
+                .map(|ev| GenericSubtitle {
+                    text: ev.text.clone(),
+                    start: ev.start,
+                    end: ev.end,
+                    coordinates: ev.coordinates.clone(),
+                    style: None,
+                })
+                .collect(),
+        ),
+        TimedSubtitleFile::WebVtt(vtt) => subtitles.append(
+            &mut vtt
+                .events()
+                .iter()
+                .map(|ev| GenericSubtitle {
+                    text: ev.text.clone(),
+                    start: ev.start,
+                    end: ev.end,
+                    coordinates: None,
+                    style: None,
+                })
+                .collect(),
+        ),
+    }
+    subtitles
+}
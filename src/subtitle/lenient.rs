@@ -0,0 +1,203 @@
+//! Reading SRT files which strict parsers reject, recovering as many cues as
+//! possible.
+
+use std::{fmt, path::Path};
+
+use aspasia::Moment;
+
+use super::{GenericSubtitle, detect_encoding, parse_timestamp};
+
+/// Something repaired or dropped while reading a file leniently.
+#[derive(Clone, Debug)]
+pub struct Recovery {
+    /// The line of the file it was found on, numbered from 1.
+    pub line: usize,
+    pub recovered: Recovered,
+}
+
+/// What was repaired or dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recovered {
+    /// A byte order mark was found part way through the file, and removed.
+    StrayBom,
+    /// A cue started without a blank line after the previous cue.
+    MissingBlankLine,
+    /// A cue's number was missing or out of sequence. Cues are renumbered
+    /// when written anyway.
+    BadIndex,
+    /// Text outside any cue was dropped.
+    DroppedText { text: String },
+    /// A cue was dropped because its timing couldn't be read.
+    DroppedCue { timing: String },
+}
+
+impl fmt::Display for Recovered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StrayBom => write!(f, "removed a stray byte order mark"),
+            Self::MissingBlankLine => write!(f, "added a missing blank line before a cue"),
+            Self::BadIndex => write!(f, "ignored a missing or out of sequence cue number"),
+            Self::DroppedText { text } => write!(f, "dropped text outside any cue: {text:?}"),
+            Self::DroppedCue { timing } => {
+                write!(f, "dropped a cue with unreadable timing {timing:?}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Recovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.recovered)
+    }
+}
+
+/// Read an SRT file, recovering from missing blank lines, broken cue numbers,
+/// stray byte order marks and unreadable cues, and returning what was
+/// recovered from alongside the cues.
+///
+/// # Errors
+///
+/// Fails if the file can't be read.
+pub fn read_lenient(path: &Path) -> anyhow::Result<(Vec<GenericSubtitle>, Vec<Recovery>)> {
+    let bytes = std::fs::read(path)?;
+    let (contents, _, _) = detect_encoding(path)?.decode(&bytes);
+    let mut parser = Parser::default();
+    for (idx, line) in contents.lines().enumerate() {
+        let mut line = line.trim_end_matches('\r');
+        if idx == 0 {
+            line = line.trim_start_matches('\u{FEFF}');
+        }
+        let cleaned;
+        if line.contains('\u{FEFF}') {
+            parser.recover(idx, Recovered::StrayBom);
+            cleaned = line.replace('\u{FEFF}', "");
+            line = &cleaned;
+        }
+        parser.line(idx, line);
+    }
+    parser.finish();
+    Ok((parser.subtitles, parser.recoveries))
+}
+
+#[derive(Default)]
+struct Parser {
+    subtitles: Vec<GenericSubtitle>,
+    recoveries: Vec<Recovery>,
+    /// The cue being read, if its text hasn't ended yet.
+    current: Option<GenericSubtitle>,
+    /// Lines read since the last cue ended which don't belong to one yet,
+    /// with their indices.
+    pending: Vec<(usize, String)>,
+    /// Whether the text of a dropped cue is being skipped.
+    skipping: bool,
+}
+
+impl Parser {
+    fn recover(&mut self, idx: usize, recovered: Recovered) {
+        self.recoveries.push(Recovery {
+            line: idx + 1,
+            recovered,
+        });
+    }
+
+    fn line(&mut self, idx: usize, line: &str) {
+        if line.contains("-->") {
+            self.skipping = false;
+            if let Some((start, end, coordinates)) = parse_timing(line) {
+                self.start_cue(idx, start, end, coordinates);
+            } else {
+                self.end_cue();
+                self.drop_pending();
+                let timing = line.trim().to_string();
+                self.recover(idx, Recovered::DroppedCue { timing });
+                self.skipping = true;
+            }
+        } else if line.trim().is_empty() {
+            self.skipping = false;
+            self.end_cue();
+            self.drop_pending();
+        } else if let Some(cue) = &mut self.current {
+            if !cue.text.is_empty() {
+                cue.text.push('\n');
+            }
+            cue.text.push_str(line);
+        } else if !self.skipping {
+            self.pending.push((idx, line.to_string()));
+        }
+    }
+
+    /// Start a new cue, working out whether the line before it is its number.
+    fn start_cue(&mut self, idx: usize, start: i64, end: i64, coordinates: Option<String>) {
+        if self.current.is_some() {
+            self.recover(idx, Recovered::MissingBlankLine);
+        }
+        if let Some(cue) = &mut self.current {
+            // No blank line ended the previous cue, so its last line may be
+            // this cue's number.
+            let last = cue.text.rsplit('\n').next().unwrap_or_default();
+            if is_index(last) {
+                let number = last.to_string();
+                cue.text.truncate(cue.text.len() - number.len());
+                cue.text.truncate(cue.text.trim_end().len());
+                self.pending.push((idx - 1, number));
+            }
+        }
+        self.end_cue();
+
+        let expected = self.subtitles.len() + 1;
+        match self.pending.pop() {
+            Some((_, number)) if number.trim().parse() == Ok(expected) => {}
+            Some((number_idx, number)) if is_index(&number) => {
+                self.recover(number_idx, Recovered::BadIndex);
+            }
+            Some(other) => {
+                self.pending.push(other);
+                self.recover(idx, Recovered::BadIndex);
+            }
+            None => self.recover(idx, Recovered::BadIndex),
+        }
+        self.drop_pending();
+        self.current = Some(GenericSubtitle {
+            text: String::new(),
+            start: Moment::from(start),
+            end: Moment::from(end),
+            coordinates,
+            style: None,
+        });
+    }
+
+    fn end_cue(&mut self) {
+        if let Some(cue) = self.current.take() {
+            self.subtitles.push(cue);
+        }
+    }
+
+    /// Drop the lines which didn't belong to any cue.
+    fn drop_pending(&mut self) {
+        for (idx, text) in std::mem::take(&mut self.pending) {
+            self.recover(idx, Recovered::DroppedText { text });
+        }
+    }
+
+    fn finish(&mut self) {
+        self.end_cue();
+        self.drop_pending();
+    }
+}
+
+/// Whether a line is a cue number.
+fn is_index(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && line.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Read a timing line, `start --> end`, optionally followed by coordinates.
+fn parse_timing(line: &str) -> Option<(i64, i64, Option<String>)> {
+    let (start, rest) = line.split_once("-->")?;
+    let rest = rest.trim();
+    let (end, coordinates) = match rest.split_once(char::is_whitespace) {
+        Some((end, coordinates)) => (end, Some(coordinates.trim().to_string())),
+        None => (rest, None),
+    };
+    Some((parse_timestamp(start)?, parse_timestamp(end)?, coordinates))
+}
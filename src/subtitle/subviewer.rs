@@ -0,0 +1,137 @@
+//! SubViewer files, and the YouTube `.sbv` captions derived from them, which
+//! time each cue with a `start,end` line rather than SRT's arrow.
+
+use std::path::Path;
+
+use aspasia::Moment;
+
+use super::{GenericSubtitle, detect_encoding, parse_timestamp};
+
+/// Whether a file is SubViewer: any `.sbv` file, or a `.sub` file whose first
+/// cue is timed as SubViewer's are, rather than being MicroDVD, which shares
+/// the extension.
+pub(super) fn is_subviewer(path: &Path) -> anyhow::Result<bool> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("sbv") => Ok(true),
+        Some("sub") => Ok(parse_subviewer(&decode(path)?).is_some_and(|cues| !cues.is_empty())),
+        _ => Ok(false),
+    }
+}
+
+/// Read a SubViewer or `.sbv` file.
+///
+/// # Errors
+///
+/// Fails if the file can't be read or isn't SubViewer.
+pub(super) fn read(path: &Path) -> anyhow::Result<Vec<GenericSubtitle>> {
+    parse_subviewer(&decode(path)?)
+        .ok_or_else(|| anyhow::anyhow!("{} isn't a SubViewer or SBV file", path.display()))
+}
+
+fn decode(path: &Path) -> anyhow::Result<String> {
+    let bytes = std::fs::read(path)?;
+    let (contents, _, _) = detect_encoding(path)?.decode(&bytes);
+    Ok(contents.trim_start_matches('\u{FEFF}').to_string())
+}
+
+/// Parse the cues of a SubViewer 2.0 or YouTube `.sbv` file, skipping any
+/// `[INFORMATION]` header. SubViewer's `[br]` line breaks are made real
+/// ones. Nothing is returned if text is found outside any cue.
+///
+/// ```
+/// use subtitle_translate::subtitle::parse_subviewer;
+///
+/// let cues = parse_subviewer("[SUBTITLE]\n00:00:01.50,00:00:03.00\nHi[br]there\n").unwrap();
+/// assert_eq!(cues[0].text, "Hi\nthere");
+/// assert_eq!(cues[0].start.as_ms(), 1500);
+///
+/// let cues = parse_subviewer("0:00:04.250,0:00:06.000\nWelcome back\n").unwrap();
+/// assert_eq!(cues[0].end.as_ms(), 6000);
+/// ```
+#[must_use]
+pub fn parse_subviewer(contents: &str) -> Option<Vec<GenericSubtitle>> {
+    let mut subtitles = Vec::new();
+    let mut current: Option<GenericSubtitle> = None;
+    for line in contents.lines().map(|line| line.trim_end_matches('\r')) {
+        if line.trim().is_empty() {
+            subtitles.extend(current.take());
+        } else if let Some(cue) = &mut current {
+            if !cue.text.is_empty() {
+                cue.text.push('\n');
+            }
+            cue.text
+                .push_str(&line.replace("[br]", "\n").replace("[BR]", "\n"));
+        } else if let Some((start, end)) = parse_timing(line) {
+            current = Some(GenericSubtitle {
+                text: String::new(),
+                start: Moment::from(start),
+                end: Moment::from(end),
+                coordinates: None,
+                style: None,
+            });
+        } else if !line.trim_start().starts_with('[') {
+            return None;
+        }
+    }
+    subtitles.extend(current);
+    Some(subtitles)
+}
+
+/// Write cues as a YouTube `.sbv` file, which SubViewer players also read.
+///
+/// ```
+/// use aspasia::Moment;
+/// use subtitle_translate::subtitle::{GenericSubtitle, to_subviewer};
+///
+/// let cue = GenericSubtitle {
+///     text: "Hi\nthere".to_string(),
+///     start: Moment::from(1500),
+///     end: Moment::from(3_723_000),
+///     coordinates: None,
+///     style: None,
+/// };
+/// assert_eq!(to_subviewer(&[cue]), "0:00:01.500,1:02:03.000\nHi\nthere\n");
+/// ```
+#[must_use]
+pub fn to_subviewer(subtitles: &[GenericSubtitle]) -> String {
+    subtitles
+        .iter()
+        .map(|subtitle| {
+            // A blank line would end the cue early.
+            let text: Vec<&str> = subtitle
+                .text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .collect();
+            format!(
+                "{},{}\n{}\n",
+                format_timestamp(subtitle.start),
+                format_timestamp(subtitle.end),
+                text.join("\n")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read a timing line, `start,end`.
+fn parse_timing(line: &str) -> Option<(i64, i64)> {
+    let (start, end) = line.trim().split_once(',')?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Format a moment as an `.sbv` timestamp, `h:mm:ss.mmm`.
+fn format_timestamp(moment: Moment) -> String {
+    let ms = moment.as_ms();
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
@@ -0,0 +1,210 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use serde::Serialize;
+use web_time::Instant;
+
+use crate::{api_types::Translation, repair::Repair, subtitle::Recovery};
+
+/// What happened to a single cue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Translated,
+    /// Translated from the cache, without asking the engine.
+    Cached,
+    Failed,
+    Skipped,
+    /// The run stopped before the cue was attempted.
+    Pending,
+}
+
+impl Status {
+    /// Whether the cue has a translation, from the engine or the cache.
+    #[must_use]
+    pub fn is_translated(self) -> bool {
+        matches!(self, Self::Translated | Self::Cached)
+    }
+}
+
+/// The outcome of translating a single cue.
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub status: Status,
+    /// How long the translation took, if one was requested.
+    pub elapsed: Option<Duration>,
+    pub error: Option<String>,
+    /// Alternative translations, if any were requested.
+    pub alternatives: Vec<String>,
+    /// How confident the engine was of the source language, from 0 to 100,
+    /// if it detected it.
+    pub detection_confidence: Option<f32>,
+}
+
+/// Tallies what happened during a run, to report once it finishes.
+pub struct Summary {
+    started: Instant,
+    /// Cues which were translated, including those from the cache.
+    pub translated: usize,
+    /// Cues translated from the cache, without asking the engine.
+    pub cached: usize,
+    /// Cues which didn't need translating.
+    pub skipped: usize,
+    /// Cues which failed to translate.
+    pub failed: usize,
+    /// Characters sent for translation.
+    pub characters: usize,
+    /// How many cues were detected as each source language.
+    pub languages: BTreeMap<String, usize>,
+    /// The total time spent waiting for translations.
+    pub latency: Duration,
+    /// The outcome of each cue attempted so far, in order.
+    pub outcomes: Vec<Outcome>,
+    /// What was recovered from when reading a malformed source leniently.
+    pub recovered: Vec<Recovery>,
+    /// Repairs made to the cues before they were translated.
+    pub repairs: Vec<Repair>,
+}
+
+impl Summary {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            translated: 0,
+            cached: 0,
+            skipped: 0,
+            failed: 0,
+            characters: 0,
+            languages: BTreeMap::new(),
+            latency: Duration::ZERO,
+            outcomes: vec![],
+            recovered: vec![],
+            repairs: vec![],
+        }
+    }
+
+    /// The outcome of a cue, which is pending if it hasn't been attempted.
+    #[must_use]
+    pub fn outcome(&self, cue: usize) -> Outcome {
+        self.outcomes.get(cue).cloned().unwrap_or(Outcome {
+            status: Status::Pending,
+            elapsed: None,
+            error: None,
+            alternatives: vec![],
+            detection_confidence: None,
+        })
+    }
+
+    /// The language the most cues were detected as, unless `expected` is
+    /// given, and the share of the cues whose language was detected which
+    /// were detected as another language. `None` if no languages were
+    /// detected.
+    #[must_use]
+    pub fn language_mismatch<'a>(&'a self, expected: Option<&'a str>) -> Option<(&'a str, f64)> {
+        let total: usize = self.languages.values().sum();
+        let majority = self
+            .languages
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(language, _)| language.as_str())?;
+        let expected = expected.unwrap_or(majority);
+        let matching = self.languages.get(expected).copied().unwrap_or_default();
+        #[allow(clippy::cast_precision_loss)]
+        let share = (total - matching) as f64 / total as f64;
+        Some((expected, share))
+    }
+
+    /// Record a cue which didn't need translating.
+    pub fn record_skip(&mut self) {
+        self.skipped += 1;
+        self.outcomes.push(Outcome {
+            status: Status::Skipped,
+            elapsed: None,
+            error: None,
+            alternatives: vec![],
+            detection_confidence: None,
+        });
+    }
+
+    /// Record a successful translation of some input. Translations from the
+    /// cache aren't counted as sent to the engine.
+    pub fn record(&mut self, input: &str, translation: &Translation, latency: Duration) {
+        self.translated += 1;
+        if translation.cached {
+            self.cached += 1;
+        } else {
+            self.characters += input.chars().count();
+            self.latency += latency;
+        }
+        self.outcomes.push(Outcome {
+            status: if translation.cached {
+                Status::Cached
+            } else {
+                Status::Translated
+            },
+            elapsed: Some(latency),
+            error: None,
+            alternatives: translation.alternatives.clone().unwrap_or_default(),
+            detection_confidence: translation
+                .detected_language
+                .as_ref()
+                .map(|detected| detected.confidence),
+        });
+        if let Some(detected) = &translation.detected_language {
+            *self.languages.entry(detected.language.clone()).or_default() += 1;
+        }
+    }
+
+    /// Record a failed translation.
+    pub fn record_failure(&mut self, input: &str, error: &anyhow::Error, latency: Duration) {
+        self.failed += 1;
+        self.characters += input.chars().count();
+        self.latency += latency;
+        self.outcomes.push(Outcome {
+            status: Status::Failed,
+            elapsed: Some(latency),
+            error: Some(format!("{error:#}")),
+            alternatives: vec![],
+            detection_confidence: None,
+        });
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Cues translated:   {}", self.translated)?;
+        if self.cached > 0 {
+            writeln!(f, "From cache:        {}", self.cached)?;
+        }
+        writeln!(f, "Cues skipped:      {}", self.skipped)?;
+        writeln!(f, "Cues failed:       {}", self.failed)?;
+        writeln!(f, "Characters sent:   {}", self.characters)?;
+        if !self.recovered.is_empty() {
+            writeln!(f, "Parse recoveries:  {}", self.recovered.len())?;
+        }
+        if !self.repairs.is_empty() {
+            writeln!(f, "Repairs made:      {}", self.repairs.len())?;
+        }
+        if !self.languages.is_empty() {
+            let languages: Vec<_> = self
+                .languages
+                .iter()
+                .map(|(language, count)| format!("{language} ({count})"))
+                .collect();
+            writeln!(f, "Detected language: {}", languages.join(", "))?;
+        }
+        writeln!(f, "Wall time:         {:.1?}", self.started.elapsed())?;
+        let requests =
+            u32::try_from(self.translated - self.cached + self.failed).unwrap_or(u32::MAX);
+        if requests > 0 {
+            writeln!(f, "Average latency:   {:.1?}", self.latency / requests)?;
+        }
+        Ok(())
+    }
+}
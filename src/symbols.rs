@@ -0,0 +1,114 @@
+//! Protecting emoji, arrows and dingbats, which some engines drop or repeat,
+//! by putting placeholders in their place while lines are translated.
+
+use std::collections::HashMap;
+
+use crate::hooks::Hook;
+
+/// The brackets around the placeholders, which engines leave alone.
+const OPEN: char = '⟦';
+const CLOSE: char = '⟧';
+
+/// Replaces runs of symbols with numbered placeholders before lines are
+/// translated, and puts them back afterwards.
+#[derive(Default)]
+pub struct Symbols {
+    /// The symbols removed from each cue, by cue, until their translations
+    /// are seen.
+    removed: HashMap<usize, Vec<String>>,
+}
+
+impl Hook for Symbols {
+    fn pre_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let mut symbols = Vec::new();
+        let mut result = String::with_capacity(line.len());
+        let mut run = String::new();
+        for c in line.chars().chain(std::iter::once('\0')) {
+            if is_symbol(c) || (!run.is_empty() && is_joiner(c)) {
+                run.push(c);
+                continue;
+            }
+            if !run.is_empty() {
+                symbols.push(std::mem::take(&mut run));
+                result.push(OPEN);
+                result.push_str(&symbols.len().to_string());
+                result.push(CLOSE);
+            }
+            if c != '\0' {
+                result.push(c);
+            }
+        }
+        self.removed.insert(cue, symbols);
+        Ok(result)
+    }
+
+    /// Put each symbol back in place of its placeholder. Placeholders the
+    /// engine repeated are dropped, and symbols whose placeholder it dropped
+    /// are put at the end of the line.
+    fn post_translate(&mut self, cue: usize, line: &str) -> anyhow::Result<String> {
+        let symbols = self.removed.remove(&cue).unwrap_or_default();
+        if symbols.is_empty() {
+            return Ok(line.to_string());
+        }
+        let mut restored = vec![false; symbols.len()];
+        let mut result = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find(OPEN) {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + OPEN.len_utf8()..];
+            let placeholder = after.split_once(CLOSE).and_then(|(number, after)| {
+                let idx = number.trim().parse::<usize>().ok()?.checked_sub(1)?;
+                Some((idx, after))
+            });
+            match placeholder {
+                Some((idx, after)) if idx < symbols.len() => {
+                    if !std::mem::replace(&mut restored[idx], true) {
+                        result.push_str(&symbols[idx]);
+                    } else if result.ends_with(' ') && after.starts_with([' ', '\n']) {
+                        result.pop();
+                    }
+                    rest = after;
+                }
+                _ => {
+                    result.push(OPEN);
+                    rest = after;
+                }
+            }
+        }
+        result.push_str(rest);
+        for (symbol, _) in symbols.iter().zip(restored).filter(|(_, r)| !r) {
+            tracing::debug!("The translator dropped {symbol:?}, putting it at the end");
+            if !result.ends_with([' ', '\n']) {
+                result.push(' ');
+            }
+            result.push_str(symbol);
+        }
+        Ok(result)
+    }
+}
+
+/// Whether a character is an emoji, arrow, dingbat or other pictographic
+/// symbol.
+fn is_symbol(c: char) -> bool {
+    matches!(
+        c,
+        '\u{2190}'..='\u{21FF}' // Arrows
+            | '\u{2300}'..='\u{23FF}' // Miscellaneous Technical
+            | '\u{25A0}'..='\u{27BF}' // Geometric Shapes, Miscellaneous Symbols, Dingbats
+            | '\u{27F0}'..='\u{27FF}' // Supplemental Arrows-A
+            | '\u{2900}'..='\u{297F}' // Supplemental Arrows-B
+            | '\u{2B00}'..='\u{2BFF}' // Miscellaneous Symbols and Arrows
+            | '\u{3030}'
+            | '\u{303D}'
+            | '\u{1F000}'..='\u{1FAFF}' // Emoji and pictographs
+    )
+}
+
+/// Whether a character continues the symbol before it, as variation
+/// selectors, zero width joiners, keycaps and tags do.
+fn is_joiner(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200D}' | '\u{20E3}' | '\u{FE0E}' | '\u{FE0F}' | '\u{E0020}'..='\u{E007F}'
+    )
+}
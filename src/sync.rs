@@ -0,0 +1,112 @@
+//! Correcting subtitles' timing against a video's audio, in the style of
+//! alass: cues are shifted by whichever offset best lines them up with the
+//! speech in the audio.
+
+use std::{path::Path, process::Stdio};
+
+use anyhow::Context;
+use subtitle_translate::subtitle::GenericSubtitle;
+use tokio::{io::AsyncReadExt, process::Command};
+
+/// The sample rate the audio is decoded at, which is plenty to find speech.
+const SAMPLE_RATE: usize = 8000;
+/// The length of each frame of audio judged to be speech or not.
+const FRAME_MS: i64 = 10;
+
+/// Shift every cue by the offset which best lines the subtitles up with the
+/// speech in a video, searching up to `max_offset_ms` either way. The audio is
+/// decoded with `ffmpeg`. Returns the offset applied, in milliseconds.
+pub async fn sync(
+    subtitles: &mut [GenericSubtitle],
+    video: &Path,
+    max_offset_ms: i64,
+) -> anyhow::Result<i64> {
+    let speech = speech_frames(video).await?;
+    let offset = best_offset(&speech, subtitles, max_offset_ms / FRAME_MS) * FRAME_MS;
+    for subtitle in subtitles {
+        subtitle.start = (subtitle.start.as_ms() + offset).max(0).into();
+        subtitle.end = (subtitle.end.as_ms() + offset).max(0).into();
+    }
+    Ok(offset)
+}
+
+/// Decode a video's audio, judging whether each frame is speech by whether it
+/// is louder than average.
+async fn speech_frames(video: &Path) -> anyhow::Result<Vec<bool>> {
+    let mut child = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(video)
+        .args(["-vn", "-ac", "1", "-ar", &SAMPLE_RATE.to_string()])
+        .args(["-f", "s16le", "-"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run ffmpeg, which is needed to read the video's audio")?;
+    let mut stdout = child.stdout.take().context("ffmpeg has no output")?;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let frame_bytes = SAMPLE_RATE * FRAME_MS as usize / 1000 * 2;
+    let mut frame = vec![0; frame_bytes];
+    let mut loudness = Vec::new();
+    loop {
+        match stdout.read_exact(&mut frame).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e).context("Failed to read the video's audio"),
+        }
+        let total: u64 = frame
+            .chunks_exact(2)
+            .map(|sample| u64::from(i16::from_le_bytes([sample[0], sample[1]]).unsigned_abs()))
+            .sum();
+        loudness.push(total);
+    }
+    if !child.wait().await?.success() {
+        anyhow::bail!("ffmpeg couldn't decode the video's audio");
+    }
+    if loudness.is_empty() {
+        anyhow::bail!("The video has no audio");
+    }
+
+    let average = loudness.iter().sum::<u64>() / loudness.len() as u64;
+    Ok(loudness.into_iter().map(|level| level > average).collect())
+}
+
+/// Find the offset, in frames, which puts the most of the cues' time over
+/// speech. Smaller offsets win ties.
+fn best_offset(speech: &[bool], subtitles: &[GenericSubtitle], max_offset: i64) -> i64 {
+    // The number of speech frames before each frame, so the speech under a
+    // cue can be counted at once.
+    let mut before = Vec::with_capacity(speech.len() + 1);
+    before.push(0_u64);
+    for &is_speech in speech {
+        before.push(before[before.len() - 1] + u64::from(is_speech));
+    }
+    let len = i64::try_from(speech.len()).unwrap_or(i64::MAX);
+    let at = |frame: i64| {
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let idx = frame.clamp(0, len) as usize;
+        before[idx]
+    };
+
+    let cues: Vec<_> = subtitles
+        .iter()
+        // Cues ending before they start haven't been repaired yet, and cover
+        // no speech.
+        .filter(|subtitle| !subtitle.text.is_empty() && subtitle.duration_ms() > 0)
+        .map(|subtitle| {
+            (
+                subtitle.start.as_ms() / FRAME_MS,
+                subtitle.end.as_ms() / FRAME_MS,
+            )
+        })
+        .collect();
+    let overlap = |offset: i64| -> u64 {
+        cues.iter()
+            .map(|(start, end)| at(end + offset).saturating_sub(at(start + offset)))
+            .sum()
+    };
+
+    (-max_offset..=max_offset)
+        .map(|offset| (overlap(offset), offset))
+        .max_by_key(|&(overlap, offset)| (overlap, std::cmp::Reverse(offset.abs())))
+        .map_or(0, |(_, offset)| offset)
+}
@@ -0,0 +1,112 @@
+//! Changing when cues are shown, such as to match another release of a video.
+
+use aspasia::Moment;
+
+use crate::{stats, subtitle::GenericSubtitle};
+
+/// Move every cue later by `offset_ms`, or earlier if it is negative. Cues
+/// moved to before the start of the video start at its start, and are
+/// dropped if they would end there too.
+pub fn shift(subtitles: &mut Vec<GenericSubtitle>, offset_ms: i64) {
+    subtitles.retain_mut(|subtitle| {
+        let end = subtitle.end.as_ms() + offset_ms;
+        subtitle.start = Moment::from((subtitle.start.as_ms() + offset_ms).max(0));
+        subtitle.end = Moment::from(end.max(0));
+        end > 0
+    });
+}
+
+/// Multiply every cue's start and end by `factor`, such as to convert
+/// subtitles timed for a video at 25 frames per second to one at 23.976,
+/// with a factor of `25 / 23.976`.
+pub fn scale(subtitles: &mut [GenericSubtitle], factor: f64) {
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    let scale = |moment: Moment| Moment::from((moment.as_ms() as f64 * factor).round() as i64);
+    for subtitle in subtitles {
+        subtitle.start = scale(subtitle.start);
+        subtitle.end = scale(subtitle.end);
+    }
+}
+
+/// Show each cue for as long as reading it at `cps` characters per second
+/// takes, but for at least `min_ms`, and without running into the next cue.
+/// A reading speed which isn't positive is ignored, showing each cue for
+/// `min_ms`. Returns how many cues were changed.
+pub fn retime(subtitles: &mut [GenericSubtitle], cps: f64, min_ms: i64) -> usize {
+    let mut changed = 0;
+    for cue in 0..subtitles.len() {
+        let subtitle = &subtitles[cue];
+        #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+        let reading = if cps.is_finite() && cps > 0.0 {
+            (stats::characters(&subtitle.text) as f64 / cps * 1000.0).ceil() as i64
+        } else {
+            0
+        };
+        let start = subtitle.start.as_ms();
+        let mut end = start + reading.max(min_ms);
+        if let Some(next) = subtitles
+            .get(cue + 1)
+            .map(|next| next.start.as_ms())
+            .filter(|next| *next > start)
+        {
+            end = end.min(next);
+        }
+        if end != subtitle.end.as_ms() {
+            subtitles[cue].end = Moment::from(end);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Cut subtitles into parts at each of the times in `cuts_ms`, which must be
+/// in order, with each part's times counted from the start of its part. Cues
+/// go in the part they start in, ending at its end if they would run past it.
+#[must_use]
+pub fn split(subtitles: &[GenericSubtitle], cuts_ms: &[i64]) -> Vec<Vec<GenericSubtitle>> {
+    let mut parts = vec![Vec::new(); cuts_ms.len() + 1];
+    for subtitle in subtitles {
+        let part = cuts_ms
+            .iter()
+            .take_while(|cut| subtitle.start.as_ms() >= **cut)
+            .count();
+        let start = part.checked_sub(1).map_or(0, |previous| cuts_ms[previous]);
+        let end = cuts_ms.get(part).map_or(i64::MAX, |cut| *cut);
+        let mut subtitle = subtitle.clone();
+        subtitle.start = Moment::from(subtitle.start.as_ms() - start);
+        subtitle.end = Moment::from(subtitle.end.as_ms().min(end) - start);
+        parts[part].push(subtitle);
+    }
+    parts
+}
+
+/// Join parts of subtitles into one, the second and later parts starting at
+/// the times in `offsets_ms`, in order, or straight after the cues of the part
+/// before if no time is given. Cues running past the start of the next part
+/// end when it starts. Returns the joined cues and how many were shortened.
+#[must_use]
+pub fn concat(
+    parts: Vec<Vec<GenericSubtitle>>,
+    offsets_ms: &[i64],
+) -> (Vec<GenericSubtitle>, usize) {
+    let mut joined: Vec<GenericSubtitle> = Vec::new();
+    let mut shortened = 0;
+    for (idx, mut part) in parts.into_iter().enumerate() {
+        if idx > 0 {
+            let offset = offsets_ms
+                .get(idx - 1)
+                .copied()
+                .unwrap_or_else(|| joined.iter().map(|cue| cue.end.as_ms()).max().unwrap_or(0));
+            shift(&mut part, offset);
+            let seam = part.first().map_or(offset, |cue| cue.start.as_ms());
+            for cue in &mut joined {
+                if cue.start.as_ms() < seam && cue.end.as_ms() > seam {
+                    cue.end = Moment::from(seam);
+                    shortened += 1;
+                }
+            }
+        }
+        joined.append(&mut part);
+    }
+    (joined, shortened)
+}
@@ -0,0 +1,976 @@
+use std::{
+    collections::BTreeMap,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use subtitle_translate::{
+    TranslationPipeline, Translator, casing, confidence, entities,
+    hyphenation::Patterns,
+    languages, layout,
+    libretranslate::LibreTranslate,
+    numbers, repair,
+    subtitle::{self, GenericSubtitle, SubtitleFormat},
+    summary::Summary,
+};
+
+use crate::{
+    cache::{Cache, Cached},
+    checkpoint::{self, Checkpoint},
+    cli::{EmptyCues, EngineArgs, KeepOriginal, TranslateArgs},
+    config::Config,
+    convert, cost,
+    exit_code::{self, Failure, WithFailure},
+    history,
+    hooks::Hooks,
+    interactive,
+    opensubtitles::OpenSubtitles,
+    picker, preview,
+    progress::Progress,
+    qa_report::QaReport,
+    report, sync, whisper,
+};
+
+/// The share of cues detected as another language at which a warning is given.
+const LANGUAGE_MISMATCH: f64 = 0.3;
+
+/// Translate a subtitle file.
+pub async fn run(engine: &EngineArgs, mut args: TranslateArgs, quiet: bool) -> anyhow::Result<()> {
+    let mut engine = engine.clone();
+    let formatting = Config::for_source(&args.source_file)?.apply(&mut args, &mut engine)?;
+    let translator = engine.translator();
+    let target = match &args.language_to {
+        Some(language) => language.clone(),
+        None => ask_target(&translator, &args.language_from.to_ascii_lowercase()).await?,
+    };
+    formatting.apply(&target, &mut args);
+    let real_target = destination_path(&args, &target)?;
+    let entry = history::Entry::new(&args, &engine, &target, &real_target);
+
+    // Step 1: Read source subs
+    let mut summary = Summary::new();
+    let mut subtitles = read_source(&mut args, &translator, &mut summary).await?;
+    let source = args.language_from.to_ascii_lowercase();
+    let sources: Vec<String> = subtitles.iter().map(|s| s.text.clone()).collect();
+    let mut hooks = Hooks::load(&args)?;
+    hooks.pre_translate(&mut subtitles)?;
+    if args.estimate_cost {
+        return cost::print_estimate(cost::Usage::of(&subtitles));
+    }
+
+    // Step 2: Translate line by line, asynchronously in batches
+    tracing::info!("Translating…");
+    let progress = Progress::new(args.progress);
+    progress.started(
+        &args.source_file,
+        &real_target,
+        &source,
+        &target,
+        subtitles.len(),
+    );
+    let originals = subtitles.clone();
+    let (kept, previous) =
+        keep_untranslated(&args, &translator, &target, &real_target, &mut subtitles).await?;
+    let cache = open_cache(&args);
+    let translator = Cached::new(translator, engine.instances(), cache.clone());
+    let (resumed, fingerprint) = checkpoint::resume(&args, &engine, &target, &real_target)?;
+    // Only resuming a run may replace the file it was writing without asking.
+    ensure_replaceable(&args, &real_target, resumed.is_some())?;
+    let resumed = resumed.unwrap_or_default();
+    let pipeline = build_pipeline(&args, translator, &source, &target).resume(resumed);
+    let (result, interrupted) =
+        translate_interruptibly(&pipeline, &mut subtitles, &mut summary, &progress).await;
+    let (result, continued) = match result {
+        Err(e) if args.continue_on_error => (Ok(()), Some(e)),
+        result => (result, None),
+    };
+    for cue in kept {
+        subtitles[cue].text.clone_from(&originals[cue].text);
+    }
+    save_cache(cache.as_deref());
+    let checkpoint = Checkpoint::new(fingerprint, &subtitles, &summary);
+    if let Err(e) = result {
+        checkpoint.update(&real_target, false);
+        progress.finished(&summary, false);
+        finish(
+            &args, &entry, &sources, &originals, &subtitles, &summary, quiet,
+        )?;
+        return translation_error(e);
+    }
+    post_process(
+        &args,
+        &target,
+        &mut hooks,
+        &sources,
+        &originals,
+        &mut subtitles,
+        &mut summary,
+    )?;
+    for (cue, text) in previous {
+        subtitles[cue].text = text;
+    }
+
+    if args.interactive {
+        interactive::review(
+            pipeline.translator().inner(),
+            &source,
+            &target,
+            &originals,
+            &mut subtitles,
+        )
+        .await?;
+    }
+
+    show_preview(&args, &originals, &subtitles)?;
+
+    // Step 3: Write final file
+    tracing::info!("Writing translated subtitles…");
+    let show_diff = !quiet && !progress.uses_stdout();
+    write_destination(&args, &real_target, &subtitles, show_diff)?;
+    checkpoint.update(&real_target, !interrupted && continued.is_none());
+
+    progress.finished(&summary, !interrupted && continued.is_none());
+    finish(
+        &args, &entry, &sources, &originals, &subtitles, &summary, quiet,
+    )?;
+    if interrupted {
+        return Err(interruption(&real_target, &summary)).failure(Failure::Interrupted);
+    }
+    if let Some(e) = continued {
+        return translation_error(e.context(format!(
+            "{} line(s) failed to translate and were left untranslated",
+            summary.failed
+        )));
+    }
+    check_strict(&args, &sources, &subtitles, &summary)
+}
+
+/// The pipeline translating with the settings given, warning if the engine
+/// can't follow the hints given.
+fn build_pipeline(
+    args: &TranslateArgs,
+    translator: Cached<LibreTranslate>,
+    source: &str,
+    target: &str,
+) -> TranslationPipeline<Cached<LibreTranslate>> {
+    if !args.hints().is_empty() && !translator.supports_hints() {
+        tracing::warn!("The translation engine ignores formality and gender hints");
+    }
+    TranslationPipeline::new(translator, target)
+        .source(source)
+        .chunk_size(args.chunk_size)
+        .hints(args.hints())
+        .alternatives(args.alternatives)
+        .keep_line_breaks(args.keep_line_breaks)
+        .continue_on_error(args.continue_on_error)
+}
+
+/// Translate the subtitles, stopping as soon as Ctrl-C is pressed rather than
+/// dying, so what was translated can still be saved. Whether translation was
+/// interrupted is returned with its result.
+async fn translate_interruptibly(
+    pipeline: &TranslationPipeline<Cached<LibreTranslate>>,
+    subtitles: &mut [GenericSubtitle],
+    summary: &mut Summary,
+    progress: &Progress,
+) -> (anyhow::Result<()>, bool) {
+    let translated = tokio::select! {
+        result = pipeline.translate(subtitles, summary, |event| progress.emit(event)) => {
+            (result, false)
+        }
+        _ = tokio::signal::ctrl_c() => {
+            tracing::warn!("Interrupted, saving what was translated…");
+            (Ok(()), true)
+        }
+    };
+    report_health(pipeline.translator().inner());
+    translated
+}
+
+/// Log how the requests to each instance went, if they were shared between
+/// several.
+fn report_health(translator: &LibreTranslate) {
+    let instances = translator.health();
+    if instances.len() < 2 {
+        return;
+    }
+    for health in instances {
+        let latency = health.mean_latency().map_or_else(
+            || "-".to_string(),
+            |latency| format!("{}ms", latency.as_millis()),
+        );
+        tracing::info!(
+            "{}: {} request(s), {} failed, {latency} mean latency",
+            health.instance,
+            health.requests,
+            health.failures
+        );
+    }
+}
+
+/// Fail with a translation error, tagged with whether the instance couldn't
+/// be reached or lines failed to translate.
+fn translation_error(e: anyhow::Error) -> anyhow::Result<()> {
+    let failure = if exit_code::is_unreachable(&e) {
+        Failure::Unreachable
+    } else {
+        Failure::Translation
+    };
+    Err(e).failure(failure)
+}
+
+/// The error an interrupted run ends with, saying how to finish it.
+fn interruption(destination: &Path, summary: &Summary) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Interrupted after translating {} cue(s), which were written to {}. Run the same \
+         command again to carry on from where it stopped, or with --restart to start over",
+        summary.translated,
+        destination.display()
+    )
+}
+
+/// Write the translated subtitles, first showing how they differ from any
+/// existing file they replace if asked to, and backing it up if asked to.
+fn write_destination(
+    args: &TranslateArgs,
+    path: &Path,
+    subtitles: &[GenericSubtitle],
+    show_diff: bool,
+) -> anyhow::Result<()> {
+    if show_diff && path.exists() {
+        match subtitle::read(path) {
+            Ok(previous) => preview::print_diff(&previous, subtitles),
+            Err(e) => tracing::warn!("Couldn't read existing destination to compare: {e}"),
+        }
+    }
+    if args.in_place {
+        let original = original_path(path);
+        if original.exists() {
+            tracing::info!("Keeping the existing original {original:?}");
+        } else {
+            tracing::info!("Copying the original to {original:?}");
+            std::fs::copy(path, &original)
+                .context("Failed to copy the original file")
+                .failure(Failure::Write)?;
+        }
+    }
+    if args.backup && path.exists() {
+        let backup = backup_path(path);
+        tracing::info!("Backing up existing destination to {backup:?}");
+        // Copied rather than moved, so the file stays in place if writing fails.
+        std::fs::copy(path, &backup)
+            .context("Failed to back up existing destination file")
+            .failure(Failure::Write)?;
+    }
+
+    subtitle::write_srt(subtitles, path)
+        .context("Failed to write destination subtitle file")
+        .failure(Failure::Write)
+}
+
+/// A warning if many cues were detected as a language other than the source
+/// language, or the language most were detected as, which usually means the
+/// wrong source language was given or the file mixes languages.
+fn language_warning(summary: &Summary, source: &str) -> Option<String> {
+    let expected = (source != "auto").then_some(source);
+    let (language, share) = summary.language_mismatch(expected)?;
+    if share < LANGUAGE_MISMATCH {
+        return None;
+    }
+    let detected: Vec<_> = summary
+        .languages
+        .iter()
+        .map(|(language, count)| format!("{language} ({count})"))
+        .collect();
+    Some(format!(
+        "{:.0}% of cues were detected as a language other than {language}: {}. \
+         Check the source language, or try --detect-per-line if the file mixes languages",
+        share * 100.0,
+        detected.join(", ")
+    ))
+}
+
+/// Run the hooks over the translated subtitles, then tidy them up as asked.
+/// Repairs are recorded in the summary.
+fn post_process(
+    args: &TranslateArgs,
+    target: &str,
+    hooks: &mut Hooks,
+    sources: &[String],
+    originals: &[GenericSubtitle],
+    subtitles: &mut [GenericSubtitle],
+    summary: &mut Summary,
+) -> anyhow::Result<()> {
+    hooks.post_translate(subtitles)?;
+    if let Some(format) = SubtitleFormat::detect(&args.source_file) {
+        // Translations are written as SRT.
+        convert::to_tags(
+            &args.source_file,
+            format,
+            Some(SubtitleFormat::SubRip),
+            subtitles,
+        )?;
+    }
+    let source = args.language_from.to_ascii_lowercase();
+    let hyphenation = match &args.hyphenation {
+        Some(path) => Patterns::parse(
+            &std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+        ),
+        None => Patterns::default(),
+    };
+    for (cue, (original, subtitle)) in originals.iter().zip(subtitles.iter_mut()).enumerate() {
+        let outcome = summary.outcome(cue);
+        if !outcome.status.is_translated() {
+            continue;
+        }
+        let uncertain = args.min_confidence.is_some_and(|min| {
+            let detection = outcome.detection_confidence.map(f64::from);
+            confidence::score(&sources[cue], &subtitle.text, detection) < min
+        });
+        if args.fix_case {
+            subtitle.text = casing::fix_case(&original.text, &subtitle.text, target);
+        }
+        if args.localize_numbers {
+            subtitle.text = numbers::localize(&original.text, &source, &subtitle.text, target);
+        }
+        if args.smart_quotes {
+            subtitle.text = layout::smart_quotes(&subtitle.text, target);
+        }
+        if args.space_punctuation {
+            subtitle.text = layout::space_punctuation(&subtitle.text);
+        }
+        if let Some(max) = args.max_line_length {
+            subtitle.text = layout::wrap_hyphenated(&subtitle.text, max, &hyphenation);
+        }
+        if uncertain {
+            subtitle.text.insert_str(0, &args.uncertain_marker);
+        }
+        if let Some(KeepOriginal::Comments) = args.keep_original {
+            subtitle.text = format!("{}{}", comment(&sources[cue]), subtitle.text);
+        }
+        if !outcome.alternatives.is_empty() {
+            let alternatives = outcome.alternatives.join(" | ");
+            subtitle.text = format!("{}{}", subtitle.text, comment(&alternatives));
+        }
+    }
+    if let Some(min_ms) = args.limits().min_duration {
+        let gap_ms = args.min_gap.unwrap_or(0);
+        let repairs = repair::enforce_duration(subtitles, i64::from(min_ms), i64::from(gap_ms));
+        for repair in &repairs {
+            tracing::info!("Repaired {repair}");
+        }
+        summary.repairs.extend(repairs);
+    }
+    if let Some(gap_ms) = args.min_gap {
+        let repairs = repair::enforce_gap(subtitles, i64::from(gap_ms));
+        for repair in &repairs {
+            tracing::info!("Repaired {repair}");
+        }
+        summary.repairs.extend(repairs);
+    }
+    Ok(())
+}
+
+/// Show the preview, if it was asked for, in the terminal or as an HTML file.
+fn show_preview(
+    args: &TranslateArgs,
+    originals: &[GenericSubtitle],
+    translated: &[GenericSubtitle],
+) -> anyhow::Result<()> {
+    if let Some(path) = &args.preview {
+        if path.as_os_str() == "-" {
+            preview::print_terminal(originals, translated);
+        } else {
+            preview::write_html(originals, translated, path)
+                .context("Failed to write preview")
+                .failure(Failure::Write)?;
+        }
+    }
+    Ok(())
+}
+
+/// Text as a comment on one line, which players hide as they do override
+/// blocks.
+fn comment(text: &str) -> String {
+    let text = subtitle::visible_text(text)
+        .replace(['{', '}'], "")
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" / ");
+    format!("{{{text}}}")
+}
+
+/// Read the source subtitles, fetching or transcribing them first if the
+/// source is a video and that was asked for, and syncing and repairing them
+/// if asked to, and keeping only the cues for a forced track if asked to.
+/// Repairs are recorded in the summary.
+async fn read_source(
+    args: &mut TranslateArgs,
+    translator: &LibreTranslate,
+    summary: &mut Summary,
+) -> anyhow::Result<Vec<GenericSubtitle>> {
+    if SubtitleFormat::from_path(&args.source_file).is_none() {
+        if args.fetch {
+            args.source_file = fetch_source(args).await?;
+        } else if args.whisper.transcribe {
+            transcribe_source(args).await?;
+        }
+    }
+
+    tracing::info!("Reading source subtitles…");
+    let mut subtitles = if args.lenient
+        && SubtitleFormat::from_path(&args.source_file) == Some(SubtitleFormat::SubRip)
+    {
+        let (subtitles, recovered) = subtitle::read_lenient(&args.source_file)
+            .context("Failed to read source subtitles")
+            .failure(Failure::Parse)?;
+        for recovery in &recovered {
+            tracing::warn!("Recovered from {recovery}");
+        }
+        summary.recovered = recovered;
+        subtitles
+    } else {
+        subtitle::read(&args.source_file)
+            .context("Failed to read source subtitles")
+            .failure(Failure::Parse)?
+    };
+    if let Some(video) = &args.sync_to {
+        tracing::info!("Syncing subtitles to {video:?}…");
+        let offset = sync::sync(&mut subtitles, video, i64::from(args.max_offset) * 1000)
+            .await
+            .context("Failed to sync subtitles")?;
+        tracing::info!("Shifted subtitles by {offset}ms");
+    }
+
+    for subtitle in &mut subtitles {
+        subtitle.text = entities::decode(&subtitle.text);
+    }
+    if let EmptyCues::Drop = args.empty_cues {
+        let before = subtitles.len();
+        subtitles.retain(|subtitle| !subtitle::is_blank(&subtitle.text));
+        if subtitles.len() < before {
+            tracing::info!("Dropped {} empty cue(s)", before - subtitles.len());
+        }
+    }
+    if args.sort {
+        let moved = repair::sort(&mut subtitles);
+        if moved > 0 {
+            tracing::info!("Moved {moved} cue(s) to put them in order");
+        }
+    }
+    let repairs = &mut summary.repairs;
+    if let Some(strategy) = args.fix_overlaps {
+        repairs.extend(repair::fix_overlaps(&mut subtitles, strategy));
+    }
+    if let Some(max_ms) = args.merge_short {
+        let language = args.language_from.to_ascii_lowercase();
+        repairs.extend(repair::merge_short(
+            &mut subtitles,
+            i64::from(max_ms),
+            &language,
+        ));
+    }
+    repairs.extend(repair::fix_durations(
+        &mut subtitles,
+        i64::from(args.min_duration),
+        args.duration_cps,
+    ));
+    for repair in repairs.iter() {
+        tracing::warn!("Repaired {repair}");
+    }
+    if args.forced_only {
+        keep_forced(translator, args, &mut subtitles).await;
+    }
+    Ok(subtitles)
+}
+
+/// Detect the language of each cue, blanking those already in the target
+/// language so they are skipped, and returning their indices so their text can
+/// be put back. Cues whose language can't be detected are translated.
+async fn keep_in_target(
+    translator: &LibreTranslate,
+    target: &str,
+    chunk_size: usize,
+    subtitles: &mut [GenericSubtitle],
+) -> Vec<usize> {
+    let target = base_language(target);
+    let detected = detect_languages(translator, chunk_size, subtitles).await;
+    let mut kept = Vec::new();
+    for (cue, (subtitle, detected)) in subtitles.iter_mut().zip(detected).enumerate() {
+        if detected.is_some_and(|language| language == target) {
+            subtitle.text.clear();
+            kept.push(cue);
+        }
+    }
+    tracing::info!(
+        "Keeping {} cue(s) already in the target language",
+        kept.len()
+    );
+    kept
+}
+
+/// Blank the cues which aren't to be translated, so they are skipped,
+/// returning their indices so their text can be put back, and the existing
+/// translations of those not listed with --lines-from.
+async fn keep_untranslated(
+    args: &TranslateArgs,
+    translator: &LibreTranslate,
+    target: &str,
+    destination: &Path,
+    subtitles: &mut [GenericSubtitle],
+) -> anyhow::Result<(Vec<usize>, Vec<(usize, String)>)> {
+    let previous = keep_translated(args, destination, subtitles)?;
+    let mut kept = keep_styles(args, subtitles);
+    kept.extend(previous.iter().map(|(cue, _)| *cue));
+    if args.detect_per_line {
+        kept.extend(keep_in_target(translator, target, args.chunk_size, subtitles).await);
+    }
+    Ok((kept, previous))
+}
+
+/// Blank the cues not listed in the file given with --lines-from, so only
+/// those listed are translated, returning the translation of each blanked cue
+/// in the existing destination, to be put back once the rest are translated.
+fn keep_translated(
+    args: &TranslateArgs,
+    destination: &Path,
+    subtitles: &mut [GenericSubtitle],
+) -> anyhow::Result<Vec<(usize, String)>> {
+    let Some(path) = &args.lines_from else {
+        return Ok(Vec::new());
+    };
+    let listed = report::read_cues(path)
+        .with_context(|| {
+            format!(
+                "Failed to read the cues to translate from {}",
+                path.display()
+            )
+        })
+        .failure(Failure::Parse)?;
+    let previous = subtitle::read(destination)
+        .with_context(|| {
+            format!(
+                "Failed to read the existing translation {} to retranslate cues of",
+                destination.display()
+            )
+        })
+        .failure(Failure::Parse)?;
+    if previous.len() != subtitles.len() {
+        return Err(anyhow::anyhow!(
+            "The existing translation {} has {} cue(s), but the source has {}",
+            destination.display(),
+            previous.len(),
+            subtitles.len()
+        ))
+        .failure(Failure::Parse);
+    }
+    let mut kept = Vec::new();
+    for (cue, (subtitle, previous)) in subtitles.iter_mut().zip(previous).enumerate() {
+        if !listed.contains(&(cue + 1)) {
+            subtitle.text.clear();
+            kept.push((cue, previous.text));
+        }
+    }
+    tracing::info!(
+        "Translating {} listed cue(s), keeping the existing translations of {}",
+        subtitles.len() - kept.len(),
+        kept.len()
+    );
+    Ok(kept)
+}
+
+/// Blank the cues of styles which aren't to be translated, so they are
+/// skipped, returning their indices so their text can be put back. Cues
+/// without a style, as in formats other than ASS and SSA, are translated.
+fn keep_styles(args: &TranslateArgs, subtitles: &mut [GenericSubtitle]) -> Vec<usize> {
+    let listed = |styles: &[String], style: &str| {
+        styles
+            .iter()
+            .any(|listed| listed.trim().eq_ignore_ascii_case(style.trim()))
+    };
+    let mut kept = Vec::new();
+    for (cue, subtitle) in subtitles.iter_mut().enumerate() {
+        let Some(style) = &subtitle.style else {
+            continue;
+        };
+        let skipped = if args.only_styles.is_empty() {
+            listed(&args.skip_styles, style)
+        } else {
+            !listed(&args.only_styles, style)
+        };
+        if skipped && !subtitle::is_blank(&subtitle.text) {
+            subtitle.text.clear();
+            kept.push(cue);
+        }
+    }
+    if !kept.is_empty() {
+        tracing::info!("Keeping {} cue(s) of untranslated styles", kept.len());
+    }
+    kept
+}
+
+/// Keep only the cues to go in a forced track: those in a language other
+/// than the rest of the file, such as signs or foreign dialogue. If the file
+/// is already a forced track, every cue is kept. The language of the rest of
+/// the file is the source language, if given, or else the one most cues are
+/// detected as.
+async fn keep_forced(
+    translator: &LibreTranslate,
+    args: &TranslateArgs,
+    subtitles: &mut Vec<GenericSubtitle>,
+) {
+    if is_forced(&args.source_file) {
+        tracing::info!("The source is a forced track, so every cue is translated");
+        return;
+    }
+    let detected = detect_languages(translator, args.chunk_size, subtitles).await;
+    let main = if args.language_from == "auto" {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for language in detected.iter().flatten() {
+            *counts.entry(language).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(language, _)| language.to_string())
+    } else {
+        Some(base_language(&args.language_from))
+    };
+    let mut detected = detected.into_iter();
+    subtitles.retain(|_| {
+        detected
+            .next()
+            .flatten()
+            .is_some_and(|language| Some(&language) != main.as_ref())
+    });
+    tracing::info!(
+        "Keeping {} cue(s) in a language other than {} for the forced track",
+        subtitles.len(),
+        main.as_deref().unwrap_or("the rest")
+    );
+}
+
+/// Detect the language of each cue, without its region. Cues whose language
+/// can't be detected, such as those without text, have none.
+async fn detect_languages(
+    translator: &LibreTranslate,
+    chunk_size: usize,
+    subtitles: &[GenericSubtitle],
+) -> Vec<Option<String>> {
+    tracing::info!("Detecting the language of each cue…");
+    let mut languages = Vec::with_capacity(subtitles.len());
+    for (chunk_idx, chunk) in subtitles.chunks(chunk_size).enumerate() {
+        let detections = chunk.iter().map(|subtitle| {
+            let text = subtitle::visible_text(&subtitle.text);
+            async move {
+                if text.trim().is_empty() {
+                    return None;
+                }
+                match translator.detect(text).await {
+                    Ok(detected) => detected.into_iter().next(),
+                    Err(e) => {
+                        tracing::warn!("Failed to detect the language of a cue: {e}");
+                        None
+                    }
+                }
+            }
+        });
+        let detections = futures::future::join_all(detections).await;
+        for (idx, detected) in detections.into_iter().enumerate() {
+            if let Some(detected) = &detected {
+                tracing::debug!(
+                    cue = chunk_idx * chunk_size + idx + 1,
+                    language = detected.language,
+                    "Detected language"
+                );
+            }
+            languages.push(detected.map(|detected| base_language(&detected.language)));
+        }
+    }
+    languages
+}
+
+/// A language code without its region, such as `pt` for `pt-BR`.
+fn base_language(language: &str) -> String {
+    let base = language.split(['-', '_']).next().unwrap_or_default();
+    base.to_ascii_lowercase()
+}
+
+/// Open the translation cache, unless it is turned off. Translation goes on
+/// without it if it can't be opened.
+fn open_cache(args: &TranslateArgs) -> Option<Arc<Cache>> {
+    if args.no_cache {
+        return None;
+    }
+    match Cache::open() {
+        Ok(cache) => Some(Arc::new(cache)),
+        Err(e) => {
+            tracing::warn!("Not using the translation cache: {e:#}");
+            None
+        }
+    }
+}
+
+/// Save the translations cached during the run, warning if they can't be.
+fn save_cache(cache: Option<&Cache>) {
+    if let Some(cache) = cache
+        && let Err(e) = cache.save()
+    {
+        tracing::warn!("Failed to save the translation cache: {e:#}");
+    }
+}
+
+/// Record the run in the history, write the report and print the summary
+/// once translation has finished, whether or not it succeeded.
+fn finish(
+    args: &TranslateArgs,
+    entry: &history::Entry,
+    sources: &[String],
+    originals: &[GenericSubtitle],
+    translated: &[GenericSubtitle],
+    summary: &Summary,
+    quiet: bool,
+) -> anyhow::Result<()> {
+    entry.record(summary);
+    if let Some(path) = &args.report {
+        report::write(path, originals, translated, summary, args.limits())
+            .context("Failed to write report")
+            .failure(Failure::Write)?;
+    }
+    if let Some(path) = &args.errors {
+        report::write_errors(path, originals, summary)
+            .context("Failed to write errors file")
+            .failure(Failure::Write)?;
+    }
+    if !quiet {
+        eprint!("\n{summary}");
+    }
+    if let Some(path) = &args.qa_report {
+        let qa = QaReport::new(sources, translated, summary, args.limits());
+        qa.write(path)
+            .context("Failed to write QA report")
+            .failure(Failure::Write)?;
+        if !quiet {
+            eprint!("\n{qa}");
+        }
+    }
+    if let Some(warning) = language_warning(summary, &args.language_from.to_ascii_lowercase()) {
+        tracing::warn!("{warning}");
+        if !quiet {
+            eprintln!("\nWarning: {warning}");
+        }
+    }
+    Ok(())
+}
+
+/// In strict mode, fail if any cue would be flagged in the QA report or any
+/// line failed to translate.
+fn check_strict(
+    args: &TranslateArgs,
+    sources: &[String],
+    translated: &[GenericSubtitle],
+    summary: &Summary,
+) -> anyhow::Result<()> {
+    if !args.strict {
+        return Ok(());
+    }
+    let flagged = QaReport::new(sources, translated, summary, args.limits()).flagged();
+    if flagged > 0 || summary.failed > 0 {
+        return Err(anyhow::anyhow!(
+            "Strict mode: {flagged} cue(s) failed quality checks and {} line(s) failed to translate",
+            summary.failed
+        ))
+        .failure(Failure::Qa);
+    }
+    Ok(())
+}
+
+/// Download subtitles for the source video from OpenSubtitles, beside it as
+/// `<video>.<language>.srt`, returning their path. If they are already there,
+/// they are used instead.
+async fn fetch_source(args: &TranslateArgs) -> anyhow::Result<PathBuf> {
+    let language = match args.language_from.as_str() {
+        "auto" => "en",
+        language => language,
+    };
+    let mut name = args.source_file.file_stem().unwrap_or_default().to_owned();
+    name.push(format!(".{language}.srt"));
+    let path = args.source_file.with_file_name(name);
+    if path.exists() {
+        tracing::info!("Using previously fetched subtitles {path:?}");
+        return Ok(path);
+    }
+
+    let api_key = args
+        .opensubtitles_apikey
+        .as_deref()
+        .filter(|key| !key.is_empty())
+        .context("An OpenSubtitles API key is needed to fetch subtitles")?;
+    let contents = OpenSubtitles::new(api_key)?
+        .fetch(&args.source_file, args.imdb.as_deref(), language)
+        .await
+        .failure(Failure::Unreachable)?;
+    std::fs::write(&path, contents)
+        .context("Failed to save the fetched subtitles")
+        .failure(Failure::Write)?;
+    tracing::info!("Fetched subtitles to {path:?}");
+    Ok(path)
+}
+
+/// Transcribe the source video or audio, writing the transcription beside it
+/// as `<video>.<language>.srt` and translating that instead. If the language
+/// was detected, it becomes the source language.
+async fn transcribe_source(args: &mut TranslateArgs) -> anyhow::Result<()> {
+    let transcript = whisper::transcribe(&args.whisper, &args.source_file, &args.language_from)
+        .await
+        .failure(Failure::Unreachable)?;
+    if args.language_from == "auto"
+        && let Some(language) = &transcript.language
+    {
+        args.language_from.clone_from(language);
+    }
+
+    let mut name = args.source_file.file_stem().unwrap_or_default().to_owned();
+    name.push(format!(".{}.srt", args.language_from));
+    let path = args.source_file.with_file_name(name);
+    subtitle::write_srt(&transcript.subtitles, &path)
+        .context("Failed to save the transcription")
+        .failure(Failure::Write)?;
+    tracing::info!(
+        "Transcribed {} cue(s) to {path:?}",
+        transcript.subtitles.len()
+    );
+    args.source_file = path;
+    Ok(())
+}
+
+/// Ask which language to translate into, when none was given.
+async fn ask_target(translator: &LibreTranslate, source: &str) -> anyhow::Result<String> {
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("No target language was given");
+    }
+    let mut languages = translator
+        .languages()
+        .await
+        .context("Failed to fetch supported languages")?;
+    if let Some(targets) = languages
+        .iter()
+        .find(|language| language.code == source)
+        .map(|language| language.targets.clone())
+    {
+        languages.retain(|language| targets.contains(&language.code));
+    }
+    picker::pick_language(&languages)?.context("No target language was chosen")
+}
+
+/// Determine the path the translated subtitles will be written to, checking that
+/// any existing file there may be replaced. One with a checkpoint beside it is
+/// checked again once it is known whether the checkpoint is resumed.
+fn destination_path(args: &TranslateArgs, target: &str) -> anyhow::Result<PathBuf> {
+    if args.in_place {
+        anyhow::ensure!(
+            SubtitleFormat::from_path(&args.source_file) == Some(SubtitleFormat::SubRip),
+            "Only SRT files can be translated in place, as translations are written as SRT"
+        );
+        return Ok(args.source_file.clone());
+    }
+    let mut real_target = match (&args.destination_file, &args.output_template) {
+        (Some(path), _) => path.clone(),
+        (None, Some(template)) => args.source_file.with_file_name(
+            template
+                .replace("{name}", &source_name(&args.source_file))
+                .replace("{lang}", target),
+        ),
+        (None, None) if args.forced_only || is_forced(&args.source_file) => {
+            let name = source_name(&args.source_file);
+            args.source_file
+                .with_file_name(format!("{name}.{target}.forced.srt"))
+        }
+        (None, None) => default_destination(&args.source_file, target),
+    };
+    real_target.set_extension("srt");
+    tracing::debug!("Real destination is {real_target:?}");
+
+    ensure_replaceable(args, &real_target, checkpoint::path(&real_target).exists())?;
+    Ok(real_target)
+}
+
+/// Check the destination may be replaced if it exists, which it may be if
+/// asked to, or when `resuming` the run which was writing it.
+fn ensure_replaceable(
+    args: &TranslateArgs,
+    destination: &Path,
+    resuming: bool,
+) -> anyhow::Result<()> {
+    let replaceable = args.overwrite || args.backup || args.lines_from.is_some() || resuming;
+    if destination.exists() && !replaceable {
+        anyhow::bail!(
+            "Destination file {} already exists, pass --overwrite or --backup to replace it",
+            destination.display()
+        );
+    }
+    Ok(())
+}
+
+/// The path translations are written to when no destination is given: beside
+/// the source as `<name>.<language>.srt`, as media centres expect. A subtitle
+/// source's own language is dropped from its name, so `Film.en.srt` becomes
+/// `Film.de.srt`.
+pub fn default_destination(source: &Path, language: &str) -> PathBuf {
+    source.with_file_name(format!("{}.{language}.srt", source_name(source)))
+}
+
+/// A source's name without its extension, or the language or forced tag of a
+/// subtitle source.
+fn source_name(source: &Path) -> String {
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let stem = match stem.rsplit_once('.') {
+        Some((name, tag))
+            if SubtitleFormat::from_path(source).is_some()
+                && tag.eq_ignore_ascii_case("forced") =>
+        {
+            name.to_string()
+        }
+        _ => stem.into_owned(),
+    };
+    match stem.rsplit_once('.') {
+        Some((name, tag))
+            if SubtitleFormat::from_path(source).is_some() && languages::resolve(tag).is_ok() =>
+        {
+            name.to_string()
+        }
+        _ => stem,
+    }
+}
+
+/// Whether a subtitle source is a forced track, by a tag in its name, such as
+/// `Film.en.forced.srt`.
+fn is_forced(source: &Path) -> bool {
+    SubtitleFormat::from_path(source).is_some()
+        && source
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .split('.')
+            .skip(1)
+            .any(|tag| tag.eq_ignore_ascii_case("forced"))
+}
+
+/// Get the path a file translated in place is copied to first. An existing
+/// copy is kept, as it is the original from before an earlier translation.
+fn original_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".orig");
+    PathBuf::from(name)
+}
+
+/// Get the path an existing file should be copied to when backing it up.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
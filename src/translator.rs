@@ -0,0 +1,91 @@
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+
+use crate::api_types::Translation;
+
+/// How a translation should address and refer to people, for languages which
+/// distinguish them and engines which can be told.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Hints {
+    /// How formally the listener should be addressed, such as `du` or `Sie`.
+    pub formality: Option<Formality>,
+    /// The gender of the speaker, for languages where words referring to
+    /// them agree with it.
+    pub speaker_gender: Option<Gender>,
+}
+
+impl Hints {
+    /// Whether no hints are given.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Formality {
+    Formal,
+    Informal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Female,
+    Male,
+}
+
+/// Something which can translate text, such as a translation engine's API.
+///
+/// Implement this to use the [`TranslationPipeline`](crate::TranslationPipeline)
+/// with another engine.
+pub trait Translator: Send + Sync {
+    /// Translate a single piece of text from the `source` language, which may
+    /// be `auto`, into the `target` language, optionally requesting a number
+    /// of alternative translations.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text couldn't be translated.
+    fn translate(
+        &self,
+        input: String,
+        source: &str,
+        target: &str,
+        alternatives: u32,
+    ) -> impl Future<Output = anyhow::Result<Translation>> + Send;
+
+    /// The code this translator uses for a language, which may be a BCP-47
+    /// tag with a region or script, such as `pt-BR`. By default the tag is
+    /// used as it is.
+    fn language_code(&self, language: &str) -> String {
+        language.to_string()
+    }
+
+    /// Whether [`Hints`] change this translator's translations.
+    fn supports_hints(&self) -> bool {
+        false
+    }
+
+    /// Translate as [`translate`](Self::translate) does, following the hints
+    /// if the engine can. By default they are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text couldn't be translated.
+    fn translate_with_hints(
+        &self,
+        input: String,
+        source: &str,
+        target: &str,
+        alternatives: u32,
+        hints: Hints,
+    ) -> impl Future<Output = anyhow::Result<Translation>> + Send {
+        let _ = hints;
+        self.translate(input, source, target, alternatives)
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::Context;
+
+use subtitle_translate::{
+    guidelines::Guidelines,
+    qa::{self, Limits},
+    subtitle,
+};
+
+use crate::{
+    cli::ValidateArgs,
+    exit_code::{Failure, WithFailure},
+};
+
+/// Check a subtitle file for problems, failing if there are too many.
+pub fn run(args: &ValidateArgs) -> anyhow::Result<()> {
+    let subtitles = subtitle::read(&args.file)
+        .context("Failed to read subtitles")
+        .failure(Failure::Parse)?;
+    let defaults = args
+        .profile_rules
+        .map_or_else(Limits::default, Guidelines::limits);
+    let issues = qa::check(
+        &subtitles,
+        Limits {
+            max_line_length: args.max_line_length.unwrap_or(defaults.max_line_length),
+            max_cps: args.max_cps.unwrap_or(defaults.max_cps),
+            ..defaults
+        },
+    );
+
+    for issue in &issues {
+        println!("{}", issue.describe(&subtitles));
+    }
+    println!(
+        "{} problem(s) found in {} cues",
+        issues.len(),
+        subtitles.len()
+    );
+
+    if issues.len() > args.max_violations {
+        return Err(anyhow::anyhow!(
+            "{} problem(s) found, more than the {} allowed",
+            issues.len(),
+            args.max_violations
+        ))
+        .failure(Failure::Qa);
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Transcribing videos and audio with a Whisper API, hosted or running
+//! locally, for content with no subtitles at all.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use reqwest::{
+    Client,
+    multipart::{Form, Part},
+};
+use serde::Deserialize;
+use subtitle_translate::{languages, subtitle::GenericSubtitle};
+use tokio::process::Command;
+
+use crate::cli::WhisperArgs;
+
+/// A transcription, split into timed cues.
+pub struct Transcript {
+    /// The language which was spoken, if the API detected it.
+    pub language: Option<String>,
+    pub subtitles: Vec<GenericSubtitle>,
+}
+
+#[derive(Deserialize)]
+struct Transcription {
+    language: Option<String>,
+    segments: Vec<Segment>,
+}
+
+/// A span of speech, timed in seconds.
+#[derive(Deserialize)]
+struct Segment {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+/// Transcribe a video or audio file. The audio is extracted with `ffmpeg`
+/// first if it is installed, as the API may not accept every container and
+/// limits the size of uploads. The language is detected if it is `auto`.
+pub async fn transcribe(
+    args: &WhisperArgs,
+    media: &Path,
+    language: &str,
+) -> anyhow::Result<Transcript> {
+    let audio = extract_audio(media).await;
+    let upload = audio.as_deref().unwrap_or(media);
+    let contents =
+        std::fs::read(upload).with_context(|| format!("Failed to read {}", upload.display()));
+    if let Some(audio) = &audio
+        && let Err(e) = std::fs::remove_file(audio)
+    {
+        tracing::warn!("Failed to remove extracted audio: {e}");
+    }
+    let name = upload
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    let mut form = Form::new()
+        .part("file", Part::bytes(contents?).file_name(name))
+        .text("model", args.whisper_model.clone())
+        .text("response_format", "verbose_json");
+    if language != "auto" {
+        form = form.text("language", language.to_string());
+    }
+    let mut request = Client::new().post(&args.whisper_url).multipart(form);
+    if let Some(key) = &args.whisper_apikey {
+        request = request.bearer_auth(key);
+    }
+    tracing::info!("Transcribing {}…", media.display());
+    let transcription: Transcription = request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .context("Failed to transcribe")?
+        .json()
+        .await
+        .context("Failed to read the transcription")?;
+
+    let subtitles = transcription
+        .segments
+        .into_iter()
+        .map(|segment| GenericSubtitle {
+            text: segment.text.trim().to_string(),
+            start: milliseconds(segment.start).into(),
+            end: milliseconds(segment.end).into(),
+            coordinates: None,
+            style: None,
+        })
+        .filter(|subtitle| !subtitle.text.is_empty())
+        .collect();
+    Ok(Transcript {
+        language: transcription
+            .language
+            .and_then(|language| languages::resolve(&language).ok()),
+        subtitles,
+    })
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn milliseconds(seconds: f64) -> i64 {
+    (seconds * 1000.0).round() as i64
+}
+
+/// Extract a media file's audio as 16 kHz mono FLAC, which is all Whisper
+/// uses, returning where it was written.
+async fn extract_audio(media: &Path) -> Option<PathBuf> {
+    let path = std::env::temp_dir().join(format!("subtitle-translate-{}.flac", std::process::id()));
+    let status = Command::new("ffmpeg")
+        .args(["-v", "error", "-y", "-i"])
+        .arg(media)
+        .args(["-vn", "-ac", "1", "-ar", "16000", "-c:a", "flac"])
+        .arg(&path)
+        .status()
+        .await;
+    match status {
+        Ok(status) if status.success() => Some(path),
+        Ok(_) => {
+            tracing::warn!("ffmpeg couldn't extract the audio, so uploading the whole file");
+            None
+        }
+        Err(e) => {
+            tracing::debug!("Couldn't run ffmpeg, so uploading the whole file: {e}");
+            None
+        }
+    }
+}